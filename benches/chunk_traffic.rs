@@ -0,0 +1,203 @@
+// Benchmarks for the clientbound traffic of a player loading chunks: big compressed ChunkData packets with a lot of small packets in between.
+// Run with `cargo bench`, the reports end up in target/criterion.
+use bytes::{Bytes, BytesMut};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+
+use mc_proxy_v2::{
+    chunk_section::{ChunkSection, SECTION_VOLUME},
+    codec::FieldCodec,
+    compression::Compressor,
+    framing::{decompress_frame, read_frame, split_packet_id},
+    functions::{clientbound::play::ChunkData, fid_to_pid, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    Direction, RawPacket,
+};
+
+const COMPRESSION_THRESHOLD: u32 = 256;
+const CHUNKS: i32 = 64;
+// The amount of small packets (like entity movement) that are sent after every chunk.
+const SMALL_PACKETS_PER_CHUNK: usize = 20;
+// The receiver reads up to this much at a time.
+const READ_SIZE: usize = 4096;
+
+// A full chunk with the bottom 8 sections filled, with 4 bits per block.
+fn chunk_data(chunk_x: i32, chunk_z: i32) -> RawPacket {
+    let mut packet = RawPacket::new();
+    packet.encode_int(chunk_x);
+    packet.encode_int(chunk_z);
+    packet.encode_bool(true);
+    packet.encode_varint(0xFF);
+
+    let mut heightmaps = nbt::Blob::new();
+    heightmaps
+        .insert("MOTION_BLOCKING", nbt::Value::LongArray(vec![0x0101; 36]))
+        .unwrap();
+    packet.encode_nbt_blob(heightmaps);
+
+    packet.encode_varint(1024);
+    for _ in 0..1024 {
+        packet.encode_varint(1);
+    }
+
+    let mut sections = RawPacket::new();
+    for y in 0..8 {
+        let blocks: Vec<i32> = (0..SECTION_VOLUME)
+            .map(|i| [0, 1, 9, 33][(i ^ (i * y)) % 4])
+            .collect();
+        ChunkSection::from_blocks(&blocks)
+            .unwrap()
+            .encode(&mut sections)
+            .unwrap();
+    }
+    sections.prepend_length();
+    packet.push_slice(sections.get_slice());
+
+    packet.encode_varint(0);
+    packet
+}
+
+fn small_packet(entity_id: i32) -> RawPacket {
+    let mut packet = RawPacket::new();
+    packet.encode_varint(entity_id);
+    packet.encode_short(12);
+    packet.encode_short(0);
+    packet.encode_short(-12);
+    packet.encode_bool(true);
+    packet
+}
+
+// All the traffic, like it comes out of the socket.
+fn traffic() -> Vec<u8> {
+    let mut compressor = Compressor::default();
+    let mut traffic = Vec::new();
+    for i in 0..CHUNKS {
+        let chunk = Packet::from(chunk_data(i % 8 + 1, i / 8 + 1), fid_to_pid(Fid::ChunkData));
+        traffic.extend_from_slice(
+            &chunk
+                .get_data(COMPRESSION_THRESHOLD, &mut compressor)
+                .unwrap(),
+        );
+        for entity_id in 0..SMALL_PACKETS_PER_CHUNK {
+            let packet = Packet::from(small_packet(entity_id as i32), 0x27);
+            traffic.extend_from_slice(
+                &packet
+                    .get_data(COMPRESSION_THRESHOLD, &mut compressor)
+                    .unwrap(),
+            );
+        }
+    }
+    traffic
+}
+
+// This does what the parser does for packets that don't get parsed.
+fn pass_through(reads: Vec<Bytes>) -> usize {
+    let mut buffer = RawPacket::new();
+    let mut sent = 0;
+    for read in reads {
+        buffer.push_bytes(BytesMut::from(read));
+        while let Some(frame) = read_frame(&mut buffer).unwrap() {
+            let data =
+                decompress_frame(frame.body(), COMPRESSION_THRESHOLD, Direction::Clientbound)
+                    .unwrap();
+            let (packet_id, _) = split_packet_id(data).unwrap();
+            black_box(packet_id);
+            sent += black_box(frame.into_bytes()).len();
+        }
+    }
+    sent
+}
+
+fn bench_pass_through(c: &mut Criterion) {
+    let traffic = traffic();
+    let mut group = c.benchmark_group("chunk_traffic");
+    group.throughput(Throughput::Bytes(traffic.len() as u64));
+    group.bench_function("pass_through", |b| {
+        b.iter_batched(
+            || {
+                traffic
+                    .chunks(READ_SIZE)
+                    .map(Bytes::copy_from_slice)
+                    .collect::<Vec<Bytes>>()
+            },
+            |reads| assert_eq!(pass_through(reads), traffic.len()),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_chunk_data(c: &mut Criterion) {
+    let chunk = chunk_data(1, 1).into_bytes();
+    let frame = Packet::from(RawPacket::from(chunk.clone()), fid_to_pid(Fid::ChunkData))
+        .get_data(COMPRESSION_THRESHOLD, &mut Compressor::default())
+        .unwrap();
+
+    let mut group = c.benchmark_group("chunk_data");
+    group.throughput(Throughput::Bytes(chunk.len() as u64));
+    group.bench_function("decompress", |b| {
+        b.iter(|| {
+            let mut buffer = RawPacket::from(frame.clone());
+            let frame = read_frame(&mut buffer).unwrap().unwrap();
+            decompress_frame(frame.body(), COMPRESSION_THRESHOLD, Direction::Clientbound).unwrap()
+        })
+    });
+    group.bench_function("parse", |b| {
+        b.iter_batched(
+            || RawPacket::from(chunk.clone()),
+            |packet| {
+                let mut chunk_data = <ChunkData as Parsable>::default();
+                chunk_data.parse_packet(packet).unwrap();
+                chunk_data
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("encode", |b| {
+        let mut compressor = Compressor::default();
+        b.iter(|| {
+            Packet::from(RawPacket::from(chunk.clone()), fid_to_pid(Fid::ChunkData))
+                .get_data(COMPRESSION_THRESHOLD, &mut compressor)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+// Compressing a chunk the way the proxy does it for edited packets, with the compressor kept for the connection
+// and with a new one for every packet (like it was done before).
+fn bench_compress(c: &mut Criterion) {
+    let packet = Packet::from(chunk_data(1, 1), fid_to_pid(Fid::ChunkData));
+    let length = chunk_data(1, 1).len();
+
+    let mut group = c.benchmark_group("get_data_compressed");
+    group.throughput(Throughput::Bytes(length as u64));
+    for level in [1, 6, 9] {
+        group.bench_with_input(BenchmarkId::new("reused", level), &level, |b, level| {
+            let mut compressor = Compressor::new(*level);
+            b.iter(|| {
+                packet
+                    .get_data_compressed(COMPRESSION_THRESHOLD as i32, &mut compressor)
+                    .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("new", level), &level, |b, level| {
+            b.iter(|| {
+                packet
+                    .get_data_compressed(COMPRESSION_THRESHOLD as i32, &mut Compressor::new(*level))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_pass_through,
+    bench_chunk_data,
+    bench_compress
+);
+criterion_main!(benches);
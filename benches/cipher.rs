@@ -0,0 +1,58 @@
+// Benchmarks for the encryption, with the data coming in like it does when chunks are being loaded.
+// Run with `cargo bench --bench cipher`.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use mc_proxy_v2::cipher::Cipher;
+
+const KEY: &[u8; 16] = b"0123456789abcdef";
+// About how much data a player gets when joining, mostly chunks.
+const STREAM_SIZE: usize = 2 * 1024 * 1024;
+
+fn bench_cipher(c: &mut Criterion) {
+    let stream: Vec<u8> = (0..STREAM_SIZE).map(|i| (i * 31 % 256) as u8).collect();
+    let mut group = c.benchmark_group("cfb8");
+    group.throughput(Throughput::Bytes(STREAM_SIZE as u64));
+    group.sample_size(20);
+
+    // The receiver reads up to 4096 bytes at a time, bigger reads happen when the data is sent by the proxy itself.
+    for read_size in [4096, 64 * 1024] {
+        group.bench_with_input(
+            BenchmarkId::new("decrypt", read_size),
+            &read_size,
+            |b, &read_size| {
+                b.iter_batched_ref(
+                    || stream.clone(),
+                    |data| {
+                        let mut cipher = Cipher::new();
+                        cipher.enable(KEY);
+                        for read in data.chunks_mut(read_size) {
+                            cipher.decrypt(read);
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("encrypt", read_size),
+            &read_size,
+            |b, &read_size| {
+                b.iter_batched_ref(
+                    || stream.clone(),
+                    |data| {
+                        let mut cipher = Cipher::new();
+                        cipher.enable(KEY);
+                        for read in data.chunks_mut(read_size) {
+                            cipher.encrypt(read);
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_cipher);
+criterion_main!(benches);
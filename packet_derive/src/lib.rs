@@ -0,0 +1,478 @@
+// #[derive(Packet)] generates the code to decode, encode and print a packet from its struct definition.
+//
+// On a struct, every field is decoded in order with the FieldCodec of its type, unless it has one of these attributes:
+//   #[varint], #[varlong]           an i32 or i64 that is sent as a varint or varlong
+//   #[nbt]                          anything that can be (de)serialized with serde, sent as NBT
+//   #[position]                     an (i32, i32, i32) that is sent packed in a long
+//   #[prefixed_array]               a Vec, with its length as a varint in front of it
+//   #[prefixed_array(short)]        the same, with the length as a short
+//   #[array(len = <expr>)]          a Vec, with the length from an earlier field (like `self.count`)
+//   #[rest]                         a Vec<u8> with the rest of the packet
+//   #[optional(if = <expr>)]        an Option, that is only sent if the expression is true (it has to be Some then)
+// The attributes for the items (varint, varlong, nbt, position) also work inside arrays and optionals.
+// #[packet(SomeFid)] on the struct sets the packet it is, structs without it can be used inside other packets.
+//
+// On an enum with only unit variants, #[packet(varint)], #[packet(byte)] or #[packet(ubyte)] sets how it is sent,
+// the discriminants are the values on the wire.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, Token,
+};
+
+#[proc_macro_derive(
+    Packet,
+    attributes(
+        packet,
+        varint,
+        varlong,
+        nbt,
+        position,
+        prefixed_array,
+        array,
+        rest,
+        optional
+    )
+)]
+pub fn derive_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let result = match &input.data {
+        Data::Struct(_) => derive_struct(&input),
+        Data::Enum(_) => derive_enum(&input),
+        Data::Union(_) => Err(Error::new_spanned(
+            &input.ident,
+            "Packet can't be derived for unions",
+        )),
+    };
+    result.unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+// How a single item (the field itself, or what's inside an array or optional) is sent.
+enum Item {
+    Codec,
+    Varint,
+    Varlong,
+    Nbt,
+    Position,
+}
+
+enum Array {
+    // True if the length is a short instead of a varint.
+    Prefixed(bool),
+    Length(Expr),
+    Rest,
+}
+
+struct FieldSpec {
+    name: Ident,
+    item: Item,
+    array: Option<Array>,
+    optional: Option<Expr>,
+}
+
+// Parses `if = <expr>` and `len = <expr>`.
+struct KeyValue<K> {
+    value: Expr,
+    _key: std::marker::PhantomData<K>,
+}
+
+impl Parse for KeyValue<Token![if]> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![if]>()?;
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            value: input.parse()?,
+            _key: std::marker::PhantomData,
+        })
+    }
+}
+
+struct Len;
+
+impl Parse for KeyValue<Len> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        if key != "len" {
+            return Err(Error::new_spanned(key, "expected `len = <expr>`"));
+        }
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            value: input.parse()?,
+            _key: std::marker::PhantomData,
+        })
+    }
+}
+
+fn field_spec(name: Ident, attrs: &[Attribute]) -> syn::Result<FieldSpec> {
+    let mut spec = FieldSpec {
+        name,
+        item: Item::Codec,
+        array: None,
+        optional: None,
+    };
+    for attr in attrs {
+        let path = attr.path();
+        let item = if path.is_ident("varint") {
+            Some(Item::Varint)
+        } else if path.is_ident("varlong") {
+            Some(Item::Varlong)
+        } else if path.is_ident("nbt") {
+            Some(Item::Nbt)
+        } else if path.is_ident("position") {
+            Some(Item::Position)
+        } else {
+            None
+        };
+        let array = if path.is_ident("prefixed_array") {
+            let short = match &attr.meta {
+                syn::Meta::Path(_) => false,
+                _ => {
+                    let kind: Ident = attr.parse_args()?;
+                    if kind == "short" {
+                        true
+                    } else if kind == "varint" {
+                        false
+                    } else {
+                        return Err(Error::new_spanned(kind, "expected `short` or `varint`"));
+                    }
+                }
+            };
+            Some(Array::Prefixed(short))
+        } else if path.is_ident("array") {
+            Some(Array::Length(attr.parse_args::<KeyValue<Len>>()?.value))
+        } else if path.is_ident("rest") {
+            Some(Array::Rest)
+        } else {
+            None
+        };
+
+        if let Some(item) = item {
+            if !matches!(spec.item, Item::Codec) {
+                return Err(Error::new_spanned(attr, "a field can only be sent one way"));
+            }
+            spec.item = item;
+        } else if let Some(array) = array {
+            if spec.array.is_some() {
+                return Err(Error::new_spanned(attr, "a field can only be one array"));
+            }
+            spec.array = Some(array);
+        } else if path.is_ident("optional") {
+            spec.optional = Some(attr.parse_args::<KeyValue<Token![if]>>()?.value);
+        }
+    }
+    if matches!(spec.array, Some(Array::Rest)) && !matches!(spec.item, Item::Codec) {
+        return Err(Error::new_spanned(
+            &spec.name,
+            "#[rest] is always a Vec<u8> of the remaining bytes",
+        ));
+    }
+    Ok(spec)
+}
+
+fn decode_item(item: &Item) -> TokenStream2 {
+    match item {
+        Item::Codec => quote! { crate::codec::FieldCodec::decode(packet)? },
+        Item::Varint => quote! { packet.decode_varint()? },
+        Item::Varlong => quote! { packet.decode_varlong()? },
+        Item::Nbt => quote! { packet.decode_nbt()? },
+        Item::Position => quote! { packet.decode_position()? },
+    }
+}
+
+fn encode_item(item: &Item, value: &TokenStream2) -> TokenStream2 {
+    match item {
+        Item::Codec => quote! { crate::codec::FieldCodec::encode(#value, packet)?; },
+        Item::Varint => quote! { packet.encode_varint(*#value); },
+        Item::Varlong => quote! { packet.encode_varlong(*#value); },
+        Item::Nbt => quote! { packet.encode_nbt(#value); },
+        Item::Position => quote! { packet.encode_position(*#value); },
+    }
+}
+
+fn decode_field(spec: &FieldSpec) -> TokenStream2 {
+    let item = decode_item(&spec.item);
+    let value = match &spec.array {
+        None => item,
+        Some(Array::Rest) => quote! { packet.read(packet.len())?.to_vec() },
+        Some(array) => {
+            let length = match array {
+                Array::Length(length) => quote! { (#length) as i64 },
+                Array::Prefixed(true) => quote! { packet.decode_short()? as i64 },
+                _ => quote! { packet.decode_varint()? as i64 },
+            };
+            quote! {{
+                let length = #length;
+                if length < 0 {
+                    return Err(packet.error(crate::parse_error::ParseErrorKind::InvalidLength(length)));
+                }
+                // The length isn't trusted for the capacity, every item needs at least one byte anyway.
+                let mut items = Vec::new();
+                for _ in 0..length {
+                    items.push(#item);
+                }
+                items
+            }}
+        }
+    };
+    let value = match &spec.optional {
+        Some(condition) => quote! {
+            if #condition {
+                Some(#value)
+            } else {
+                None
+            }
+        },
+        None => value,
+    };
+    let name = &spec.name;
+    let name_string = unraw(name);
+    quote! {
+        self.#name = (|| -> crate::parse_error::ParseResult<_> { Ok(#value) })()
+            .map_err(|error| error.in_field(#name_string))?;
+    }
+}
+
+fn encode_field(spec: &FieldSpec) -> TokenStream2 {
+    let value = quote! { value };
+    let item = quote! { item };
+    let encoded = match &spec.array {
+        None => encode_item(&spec.item, &value),
+        Some(Array::Rest) => quote! { packet.push_slice(#value); },
+        Some(array) => {
+            let length = match array {
+                // The length is in another field, so it has to match or the packet is wrong.
+                Array::Length(length) => quote! {
+                    if #value.len() as i64 != (#length) as i64 {
+                        return Err(());
+                    }
+                },
+                Array::Prefixed(true) => quote! { packet.encode_short(#value.len() as i16); },
+                _ => quote! { packet.encode_varint(#value.len() as i32); },
+            };
+            let encode_item = encode_item(&spec.item, &item);
+            quote! {
+                #length
+                for #item in #value.iter() {
+                    #encode_item
+                }
+            }
+        }
+    };
+    let name = &spec.name;
+    match &spec.optional {
+        // The condition has to agree with the value, like the length of an array, or the packet is wrong.
+        Some(condition) => quote! {
+            match (#condition, &self.#name) {
+                (true, Some(#value)) => {
+                    #encoded
+                }
+                (false, None) => {}
+                _ => return Err(()),
+            }
+        },
+        None => quote! {
+            let #value = &self.#name;
+            #encoded
+        },
+    }
+}
+
+fn printable_field(spec: &FieldSpec) -> TokenStream2 {
+    let name = &spec.name;
+    match (&spec.item, &spec.array, &spec.optional) {
+        (Item::Codec, None, None) => quote! { crate::codec::FieldCodec::printable(&self.#name) },
+        (Item::Varint, None, None) | (Item::Varlong, None, None) => {
+            quote! { self.#name.to_string() }
+        }
+        _ => quote! { format!("{:?}", self.#name) },
+    }
+}
+
+fn unraw(name: &Ident) -> String {
+    name.to_string().trim_start_matches("r#").to_string()
+}
+
+// Looks for #[packet(...)] and returns what's in it.
+fn packet_arg(input: &DeriveInput) -> syn::Result<Option<Ident>> {
+    let mut arg = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("packet") {
+            arg = Some(attr.parse_args::<Ident>()?);
+        }
+    }
+    Ok(arg)
+}
+
+fn derive_struct(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(fields) => {
+                return Err(Error::new_spanned(
+                    fields,
+                    "Packet can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => unreachable!(),
+    };
+    let specs = fields
+        .iter()
+        .map(|field| field_spec(field.ident.clone().unwrap(), &field.attrs))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let names = specs.iter().map(|spec| &spec.name).collect::<Vec<_>>();
+    let decode = specs.iter().map(decode_field);
+    let encode = specs.iter().map(encode_field);
+    let printable = specs.iter().map(printable_field);
+    let fid = match packet_arg(input)? {
+        Some(fid) => quote! { Some(crate::functions::Fid::#fid) },
+        None => quote! { None },
+    };
+    let construct = match fields.is_empty() {
+        true => quote! { Self {} },
+        false => quote! { Self { #(#names: Default::default()),* } },
+    };
+
+    Ok(quote! {
+        impl crate::parsable::PacketFields for #name {
+            fn default_fields() -> Self {
+                #construct
+            }
+
+            // Every field is decoded in a closure, so its errors can get the field name.
+            #[allow(unused_variables, clippy::needless_question_mark)]
+            fn decode_fields(
+                &mut self,
+                packet: &mut crate::raw_packet::RawPacket,
+            ) -> crate::parse_error::ParseResult<()> {
+                #(#decode)*
+                Ok(())
+            }
+
+            #[allow(unused_variables)]
+            fn encode_fields(&self, packet: &mut crate::raw_packet::RawPacket) -> Result<(), ()> {
+                #(#encode)*
+                Ok(())
+            }
+
+            fn printable_fields(&self) -> String {
+                let parts: Vec<String> = vec![#(#printable),*];
+                parts.join(" ")
+            }
+
+            fn fid(&self) -> Option<crate::functions::Fid> {
+                #fid
+            }
+        }
+
+        impl crate::codec::FieldCodec for #name {
+            fn decode(packet: &mut crate::raw_packet::RawPacket) -> crate::parse_error::ParseResult<Self> {
+                let mut value = <Self as crate::parsable::PacketFields>::default_fields();
+                crate::parsable::PacketFields::decode_fields(&mut value, packet)?;
+                Ok(value)
+            }
+
+            fn encode(&self, packet: &mut crate::raw_packet::RawPacket) -> Result<(), ()> {
+                crate::parsable::PacketFields::encode_fields(self, packet)
+            }
+
+            fn printable(&self) -> String {
+                crate::parsable::PacketFields::printable_fields(self)
+            }
+        }
+    })
+}
+
+fn derive_enum(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => unreachable!(),
+    };
+    let wire = packet_arg(input)?.ok_or_else(|| {
+        Error::new_spanned(
+            name,
+            "enums need #[packet(varint)], #[packet(byte)] or #[packet(ubyte)]",
+        )
+    })?;
+    let (decode, encode) = match wire.to_string().as_str() {
+        "varint" => ("decode_varint", "encode_varint"),
+        "byte" => ("decode_byte", "encode_byte"),
+        "ubyte" => ("decode_ubyte", "encode_ubyte"),
+        _ => {
+            return Err(Error::new_spanned(
+                wire,
+                "expected #[packet(varint)], #[packet(byte)] or #[packet(ubyte)]",
+            ))
+        }
+    };
+    let decode = format_ident!("{}", decode);
+    let encode = format_ident!("{}", encode);
+
+    // Variants without a discriminant are one more than the one before them, like in Rust itself.
+    let mut values = Vec::new();
+    let mut next: i64 = 0;
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "Packet can only be derived for enums without fields",
+            ));
+        }
+        if let Some((_, discriminant)) = &variant.discriminant {
+            next = match discriminant {
+                Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(int),
+                    ..
+                }) => int.base10_parse()?,
+                Expr::Unary(syn::ExprUnary {
+                    op: syn::UnOp::Neg(_),
+                    expr,
+                    ..
+                }) => match &**expr {
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(int),
+                        ..
+                    }) => -int.base10_parse::<i64>()?,
+                    _ => return Err(Error::new_spanned(discriminant, "expected a number")),
+                },
+                _ => return Err(Error::new_spanned(discriminant, "expected a number")),
+            };
+        }
+        values.push((&variant.ident, proc_macro2::Literal::i64_unsuffixed(next)));
+        next += 1;
+    }
+    let decode_arms = values
+        .iter()
+        .map(|(variant, value)| quote! { #value => Ok(#name::#variant), });
+    let encode_arms = values
+        .iter()
+        .map(|(variant, value)| quote! { #name::#variant => #value, });
+
+    Ok(quote! {
+        impl crate::codec::FieldCodec for #name {
+            fn decode(packet: &mut crate::raw_packet::RawPacket) -> crate::parse_error::ParseResult<Self> {
+                match packet.#decode()? {
+                    #(#decode_arms)*
+                    value => Err(packet.error(crate::parse_error::ParseErrorKind::InvalidValue(value as i64))),
+                }
+            }
+
+            fn encode(&self, packet: &mut crate::raw_packet::RawPacket) -> Result<(), ()> {
+                packet.#encode(match self {
+                    #(#encode_arms)*
+                });
+                Ok(())
+            }
+
+            fn printable(&self) -> String {
+                format!("{:?}", self)
+            }
+        }
+    })
+}
@@ -0,0 +1,208 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Serialize;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    conf::{self, SharedConfig},
+    metrics,
+    sessions::{PacketCounters, SessionInfo, Sessions},
+};
+
+#[derive(Serialize)]
+struct MessageResponse {
+    success: bool,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct SessionDetails {
+    #[serde(flatten)]
+    info: SessionInfo,
+    counters: PacketCounters,
+}
+
+#[derive(Serialize)]
+struct PluginInfo {
+    name: &'static str,
+    enabled: bool,
+}
+
+// The admin API, this is a small HTTP server with JSON endpoints to look at and manage the running connections.
+pub async fn admin_server(sessions: Sessions, shared_config: SharedConfig) {
+    let (address, token_set) = {
+        let config = shared_config.read();
+        (config.admin_address.clone(), !config.admin_token.is_empty())
+    };
+    if !token_set {
+        log::error!("Not starting the admin API because admin_token is not set");
+        return;
+    }
+    let address: SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            log::error!("Invalid admin address {}: {}", address, e);
+            return;
+        }
+    };
+
+    let make_service = make_service_fn(move |_| {
+        let sessions = sessions.clone();
+        let shared_config = shared_config.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let sessions = sessions.clone();
+                let shared_config = shared_config.clone();
+                async move { Ok::<_, Infallible>(handle_request(request, &sessions, &shared_config)) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&address) {
+        Ok(builder) => builder.serve(make_service),
+        Err(e) => {
+            log::error!("Could not start admin API on {}: {}", address, e);
+            return;
+        }
+    };
+    log::info!("Admin API listening on {}", address);
+    if let Err(e) = server.await {
+        log::error!("Admin API stopped: {}", e);
+    }
+}
+
+fn handle_request(
+    request: Request<Body>,
+    sessions: &Sessions,
+    shared_config: &SharedConfig,
+) -> Response<Body> {
+    if !is_authorized(&request, &shared_config.read().admin_token) {
+        return message(StatusCode::UNAUTHORIZED, false, "Missing or invalid token");
+    }
+
+    let path: Vec<&str> = request
+        .uri()
+        .path()
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    match (request.method(), path.as_slice()) {
+        (&Method::GET, ["sessions"]) => json(StatusCode::OK, &sessions.list()),
+        (&Method::GET, ["sessions", connection_id]) => match sessions.get(connection_id) {
+            Some(session) => json(
+                StatusCode::OK,
+                &SessionDetails {
+                    info: session.info(),
+                    counters: session.counters.lock().clone(),
+                },
+            ),
+            None => session_not_found(),
+        },
+        (&Method::GET, ["sessions", connection_id, "counters"]) => {
+            match sessions.get(connection_id) {
+                Some(session) => json(StatusCode::OK, &*session.counters.lock()),
+                None => session_not_found(),
+            }
+        }
+        (&Method::GET, ["sessions", connection_id, "tab_list"]) => {
+            match sessions.get(connection_id) {
+                Some(session) => json(StatusCode::OK, &*session.tab_list.lock()),
+                None => session_not_found(),
+            }
+        }
+        (&Method::GET, ["sessions", connection_id, "plugins"]) => {
+            match sessions.get(connection_id) {
+                Some(session) => json(
+                    StatusCode::OK,
+                    &session
+                        .plugin_settings
+                        .lock()
+                        .list()
+                        .into_iter()
+                        .map(|(name, enabled)| PluginInfo { name, enabled })
+                        .collect::<Vec<PluginInfo>>(),
+                ),
+                None => session_not_found(),
+            }
+        }
+        (&Method::POST, ["sessions", connection_id, "plugins", name, action])
+            if *action == "enable" || *action == "disable" =>
+        {
+            let session = match sessions.get(connection_id) {
+                Some(session) => session,
+                None => return session_not_found(),
+            };
+            let enabled = *action == "enable";
+            let result = session.plugin_settings.lock().set_enabled(name, enabled);
+            match result {
+                Ok(()) => message(
+                    StatusCode::OK,
+                    true,
+                    &format!("{} {}", name, if enabled { "enabled" } else { "disabled" }),
+                ),
+                Err(()) => message(StatusCode::NOT_FOUND, false, "Plugin not found"),
+            }
+        }
+        (&Method::GET, ["metrics"]) => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(Body::from(metrics::gather(sessions)))
+            .unwrap(),
+        (&Method::POST, ["config", "reload"]) => match conf::load_config() {
+            Ok(config) => {
+                *shared_config.write() = Arc::new(config);
+                log::info!("Config reloaded");
+                message(StatusCode::OK, true, "Config reloaded")
+            }
+            Err(e) => message(
+                StatusCode::BAD_REQUEST,
+                false,
+                &format!("Could not load config: {}", e),
+            ),
+        },
+        _ => message(StatusCode::NOT_FOUND, false, "Not found"),
+    }
+}
+
+fn is_authorized(request: &Request<Body>, token: &str) -> bool {
+    match request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        // Compared in constant time, so the token can't be guessed from how long the check takes.
+        Some(given_token) => {
+            !token.is_empty() && bool::from(given_token.as_bytes().ct_eq(token.as_bytes()))
+        }
+        None => false,
+    }
+}
+
+fn json<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string(value).unwrap()))
+        .unwrap()
+}
+
+fn message(status: StatusCode, success: bool, message: &str) -> Response<Body> {
+    json(
+        status,
+        &MessageResponse {
+            success,
+            message: message.to_string(),
+        },
+    )
+}
+
+fn session_not_found() -> Response<Body> {
+    message(StatusCode::NOT_FOUND, false, "Session not found")
+}
@@ -0,0 +1,368 @@
+// The block states of 1.16.5, from palette.json. Chunks and block changes only have the id of a state.
+// palette.json is generated from the blocks.json report of the vanilla data generator, with:
+//   mc-proxy-v2 generate-palette <path to blocks.json> [path to palette.json]
+// The checked-in one doesn't have the default states yet, until it is generated again partial states only work
+// if they match one state.
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+};
+
+const PALETTE: &str = include_str!("../palette.json");
+
+lazy_static! {
+    pub static ref BLOCKS: BlockRegistry = BlockRegistry::from_palette(PALETTE).unwrap();
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockState {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+    // The state a block gets when no properties are given, like in /setblock.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+}
+
+impl BlockState {
+    // Parses a state like minecraft:oak_stairs[facing=north,half=top], the namespace can be left out.
+    // Properties that aren't given aren't in the result.
+    pub fn parse(state: &str) -> Result<Self, ()> {
+        let (name, properties) = match state.find('[') {
+            Some(start) => {
+                let properties = state[start + 1..].strip_suffix(']').ok_or(())?;
+                (&state[..start], properties)
+            }
+            None => (state, ""),
+        };
+        if name.is_empty() {
+            return Err(());
+        }
+        let name = match name.contains(':') {
+            true => name.to_string(),
+            false => format!("minecraft:{}", name),
+        };
+        let properties = properties
+            .split(',')
+            .filter(|property| !property.trim().is_empty())
+            .map(|property| {
+                let (key, value) = property.split_once('=').ok_or(())?;
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            })
+            .collect::<Result<BTreeMap<String, String>, ()>>()?;
+        Ok(Self {
+            name,
+            properties,
+            default: false,
+        })
+    }
+}
+
+// Written the way parse() reads it, with the properties sorted by name.
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.properties.is_empty() {
+            let properties: Vec<String> = self
+                .properties
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BlockRegistry {
+    // Indexed by the state id.
+    states: Vec<BlockState>,
+    // The ids of all the states of a block, in order.
+    by_name: HashMap<String, Vec<i32>>,
+}
+
+impl BlockRegistry {
+    // The ids in the palette have to go from 0 to the last state without gaps.
+    pub fn from_palette(palette: &str) -> Result<Self, String> {
+        let palette: BTreeMap<usize, BlockState> =
+            serde_json::from_str(palette).map_err(|why| why.to_string())?;
+        let mut registry = Self::default();
+        for (id, state) in palette {
+            if id != registry.states.len() {
+                return Err(format!("State {} is missing", registry.states.len()));
+            }
+            registry
+                .by_name
+                .entry(state.name.clone())
+                .or_default()
+                .push(id as i32);
+            registry.states.push(state);
+        }
+        Ok(registry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn get(&self, state_id: i32) -> Option<&BlockState> {
+        self.states.get(usize::try_from(state_id).ok()?)
+    }
+
+    // The name of the block of a state, like minecraft:oak_stairs.
+    pub fn name(&self, state_id: i32) -> Option<&str> {
+        self.get(state_id).map(|state| state.name.as_str())
+    }
+
+    // The id of the state with these properties. Properties that aren't given are taken from the default state.
+    // Without a default state in the palette that only works if the given properties match one state, None otherwise.
+    pub fn id_of(&self, name: &str, properties: &BTreeMap<String, String>) -> Option<i32> {
+        let ids = self.by_name.get(name)?;
+        let mut wanted = ids
+            .iter()
+            .map(|id| &self.states[*id as usize])
+            .find(|state| state.default)
+            .map(|state| state.properties.clone())
+            .unwrap_or_default();
+        wanted.extend(properties.clone());
+        let mut matching = ids.iter().filter(|id| {
+            let state = &self.states[**id as usize];
+            wanted
+                .iter()
+                .all(|(key, value)| state.properties.get(key) == Some(value))
+        });
+        match (matching.next(), matching.next()) {
+            (Some(id), None) => Some(*id),
+            _ => None,
+        }
+    }
+
+    // The id of a state like minecraft:oak_stairs[facing=north,half=top].
+    pub fn parse_state(&self, state: &str) -> Option<i32> {
+        let state = BlockState::parse(state).ok()?;
+        self.id_of(&state.name, &state.properties)
+    }
+}
+
+// Shorthand for BLOCKS.name().
+pub fn block_name(state_id: i32) -> Option<&'static str> {
+    BLOCKS.name(state_id)
+}
+
+// This is called at startup, so a broken palette.json stops the proxy before the first chunk instead of during it.
+pub fn check_block_registry() -> Result<(), String> {
+    BlockRegistry::from_palette(PALETTE).map(|_| ())
+}
+
+#[derive(Deserialize)]
+struct ReportBlock {
+    states: Vec<ReportState>,
+}
+
+#[derive(Deserialize)]
+struct ReportState {
+    id: usize,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+    #[serde(default)]
+    default: bool,
+}
+
+// Turns the blocks.json report (java -cp server.jar net.minecraft.data.Main --reports) into palette.json.
+pub fn palette_from_report(report: &str) -> Result<String, String> {
+    let report: BTreeMap<String, ReportBlock> =
+        serde_json::from_str(report).map_err(|why| why.to_string())?;
+    let mut palette = BTreeMap::new();
+    for (name, block) in report {
+        for state in block.states {
+            let state_id = state.id;
+            let state = BlockState {
+                name: name.clone(),
+                properties: state.properties,
+                default: state.default,
+            };
+            if let Some(other) = palette.insert(state_id, state) {
+                return Err(format!(
+                    "{} and {} both have id {}",
+                    other.name, name, state_id
+                ));
+            }
+        }
+    }
+
+    let mut output = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+    palette
+        .serialize(&mut serializer)
+        .map_err(|why| why.to_string())?;
+    let output = String::from_utf8(output).map_err(|why| why.to_string())?;
+    // The generated palette has to be one the registry can load.
+    BlockRegistry::from_palette(&output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_name() {
+        assert_eq!(block_name(0), Some("minecraft:air"));
+        assert_eq!(block_name(1), Some("minecraft:stone"));
+        assert_eq!(block_name(8), Some("minecraft:grass_block"));
+        assert_eq!(block_name(17111), Some("minecraft:quartz_bricks"));
+        assert_eq!(block_name(17112), None);
+        assert_eq!(block_name(-1), None);
+        assert_eq!(BLOCKS.len(), 17112);
+    }
+
+    #[test]
+    fn test_parse_state() {
+        let state = BlockState::parse("oak_stairs[half=top, facing=north]").unwrap();
+        assert_eq!(state.name, "minecraft:oak_stairs");
+        assert_eq!(
+            state.to_string(),
+            "minecraft:oak_stairs[facing=north,half=top]"
+        );
+        assert!(BlockState::parse("minecraft:stone[").is_err());
+        assert!(BlockState::parse("[facing=north]").is_err());
+
+        let id = BLOCKS.parse_state(
+            "minecraft:oak_stairs[facing=north,half=top,shape=straight,waterlogged=false]",
+        );
+        let state = BLOCKS.get(id.unwrap()).unwrap();
+        assert_eq!(state.name, "minecraft:oak_stairs");
+        assert_eq!(state.properties["half"], "top");
+        assert_eq!(state.properties["waterlogged"], "false");
+        assert_eq!(BLOCKS.parse_state(&state.to_string()), id);
+
+        // palette.json doesn't have the default states yet, so this matches 1954 and 1955 (waterlogged=true/false).
+        // Once it's generated from the report this is Some(1955), like in test_vanilla_stairs.
+        assert_eq!(
+            BLOCKS.parse_state("minecraft:oak_stairs[facing=north,half=top]"),
+            None
+        );
+        assert_eq!(BLOCKS.parse_state("minecraft:stone"), Some(1));
+        assert_eq!(BLOCKS.parse_state("minecraft:stone[facing=north]"), None);
+        assert_eq!(BLOCKS.parse_state("minecraft:not_a_block"), None);
+    }
+
+    #[test]
+    fn test_palette_from_report() {
+        let report = r#"{
+            "minecraft:stone": {"states": [{"id": 1, "default": true}]},
+            "minecraft:air": {"states": [{"id": 0, "default": true}]},
+            "minecraft:lever": {
+                "properties": {"powered": ["true", "false"]},
+                "states": [
+                    {"id": 2, "properties": {"powered": "true"}},
+                    {"id": 3, "properties": {"powered": "false"}, "default": true}
+                ]
+            }
+        }"#;
+        let registry = BlockRegistry::from_palette(&palette_from_report(report).unwrap()).unwrap();
+        assert_eq!(registry.len(), 4);
+        assert_eq!(registry.name(0), Some("minecraft:air"));
+        // Without properties the default state is used.
+        assert_eq!(registry.parse_state("lever"), Some(3));
+        assert_eq!(registry.parse_state("lever[powered=true]"), Some(2));
+
+        // The same partial state, with and without the defaults of the report.
+        let stairs = r#"{
+            "minecraft:oak_stairs": {
+                "states": [
+                    {"id": 0, "properties": {"facing": "north", "half": "top", "waterlogged": "true"}},
+                    {"id": 1, "properties": {"facing": "north", "half": "top", "waterlogged": "false"}, "default": true},
+                    {"id": 2, "properties": {"facing": "south", "half": "top", "waterlogged": "false"}}
+                ]
+            }
+        }"#;
+        let palette = palette_from_report(stairs).unwrap();
+        let registry = BlockRegistry::from_palette(&palette).unwrap();
+        assert_eq!(
+            registry.parse_state("oak_stairs[facing=north,half=top]"),
+            Some(1)
+        );
+        let registry = BlockRegistry::from_palette(
+            &palette.replace(r#""default": true"#, r#""default": false"#),
+        )
+        .unwrap();
+        assert_eq!(
+            registry.parse_state("oak_stairs[facing=north,half=top]"),
+            None
+        );
+        assert_eq!(registry.parse_state("oak_stairs[facing=south]"), Some(2));
+
+        let gap = r#"{"minecraft:air": {"states": [{"id": 0}]}, "minecraft:stone": {"states": [{"id": 2}]}}"#;
+        assert!(palette_from_report(gap).is_err());
+    }
+
+    // The oak stairs the way they are in the 1.16.5 report: the states start at 1954, the last property changes
+    // the fastest and the default is north, bottom, straight, not waterlogged.
+    #[test]
+    fn test_vanilla_stairs() {
+        let mut states = Vec::new();
+        for facing in ["north", "south", "west", "east"] {
+            for half in ["top", "bottom"] {
+                for shape in [
+                    "straight",
+                    "inner_left",
+                    "inner_right",
+                    "outer_left",
+                    "outer_right",
+                ] {
+                    for waterlogged in ["true", "false"] {
+                        let id = 1954 + states.len();
+                        states.push(serde_json::json!({
+                            "id": id,
+                            "properties": {
+                                "facing": facing,
+                                "half": half,
+                                "shape": shape,
+                                "waterlogged": waterlogged
+                            },
+                            "default": id == 1965
+                        }));
+                    }
+                }
+            }
+        }
+        // Everything before the stairs, so there are no gaps.
+        let filler: Vec<_> = (0..1954)
+            .map(|id| serde_json::json!({ "id": id }))
+            .collect();
+        let report = serde_json::json!({
+            "minecraft:stone": { "states": filler },
+            "minecraft:oak_stairs": { "states": states }
+        });
+
+        let palette = palette_from_report(&report.to_string()).unwrap();
+        let registry = BlockRegistry::from_palette(&palette).unwrap();
+        assert_eq!(registry.len(), 2034);
+        assert_eq!(registry.parse_state("minecraft:oak_stairs"), Some(1965));
+        assert_eq!(
+            registry.parse_state("minecraft:oak_stairs[facing=north,half=top]"),
+            Some(1955)
+        );
+        assert_eq!(
+            registry.parse_state("minecraft:oak_stairs[facing=east,waterlogged=true]"),
+            Some(2024)
+        );
+        // The same states as the checked-in palette, only with the default.
+        for id in 1954..2034 {
+            assert_eq!(
+                registry.get(id).unwrap().properties,
+                BLOCKS.get(id).unwrap().properties
+            );
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// The JSON text format, used for chat, disconnect reasons and the server list description.
+// The children in extra (and the arguments in with) get the style of their parent, unless they set it themselves.
+// serde(remote = "Self") makes the derived (de)serializers inherent functions, the trait impls below wrap them,
+// because a component can also be a plain string or a list.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct TextComponent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<TextComponent>,
+    // A name like "red" or a hex color like "#ff0000".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(
+        default,
+        rename = "clickEvent",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub click_event: Option<ClickEvent>,
+    #[serde(
+        default,
+        rename = "hoverEvent",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hover_event: Option<HoverEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<TextComponent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClickEvent {
+    pub action: ClickAction,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    OpenUrl,
+    OpenFile,
+    RunCommand,
+    SuggestCommand,
+    ChangePage,
+    CopyToClipboard,
+}
+
+// 1.16 sends the hover content in contents, older servers use value. What's in them depends on the action,
+// so they are kept as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoverEvent {
+    pub action: HoverAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contents: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoverAction {
+    ShowText,
+    ShowItem,
+    ShowEntity,
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        TextComponent::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // A string is a component with only text, in a list the first one is the parent of the others.
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(text) => Ok(TextComponent::text(text)),
+            serde_json::Value::Array(list) => {
+                let mut list = list
+                    .into_iter()
+                    .map(|value| serde_json::from_value(value).map_err(de::Error::custom))
+                    .collect::<Result<Vec<TextComponent>, D::Error>>()?;
+                if list.is_empty() {
+                    return Err(de::Error::invalid_length(0, &"at least one component"));
+                }
+                let mut parent = list.remove(0);
+                parent.extra.extend(list);
+                Ok(parent)
+            }
+            value @ serde_json::Value::Object(_) => {
+                TextComponent::deserialize(value).map_err(de::Error::custom)
+            }
+            // Numbers and bools are shown as they are.
+            value => Ok(TextComponent::text(value.to_string())),
+        }
+    }
+}
+
+// The style of a piece of text after the styles of its parents are applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    color: Option<String>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl Style {
+    fn inherit(&self, component: &TextComponent) -> Style {
+        Style {
+            color: component.color.clone().or_else(|| self.color.clone()),
+            bold: component.bold.unwrap_or(self.bold),
+            italic: component.italic.unwrap_or(self.italic),
+            underlined: component.underlined.unwrap_or(self.underlined),
+            strikethrough: component.strikethrough.unwrap_or(self.strikethrough),
+            obfuscated: component.obfuscated.unwrap_or(self.obfuscated),
+        }
+    }
+
+    fn formats(&self) -> impl Iterator<Item = char> {
+        IntoIterator::into_iter([
+            (self.obfuscated, 'k'),
+            (self.bold, 'l'),
+            (self.strikethrough, 'm'),
+            (self.underlined, 'n'),
+            (self.italic, 'o'),
+        ])
+        .filter(|(set, _)| *set)
+        .map(|(_, code)| code)
+    }
+}
+
+const COLORS: [(char, &str); 16] = [
+    ('0', "black"),
+    ('1', "dark_blue"),
+    ('2', "dark_green"),
+    ('3', "dark_aqua"),
+    ('4', "dark_red"),
+    ('5', "dark_purple"),
+    ('6', "gold"),
+    ('7', "gray"),
+    ('8', "dark_gray"),
+    ('9', "blue"),
+    ('a', "green"),
+    ('b', "aqua"),
+    ('c', "red"),
+    ('d', "light_purple"),
+    ('e', "yellow"),
+    ('f', "white"),
+];
+
+// Only the translations the proxy runs into a lot, the others are shown as the key with the arguments.
+const TRANSLATIONS: [(&str, &str); 5] = [
+    ("chat.type.text", "<%s> %s"),
+    ("chat.type.announcement", "[%s] %s"),
+    ("chat.type.emote", "* %s %s"),
+    ("multiplayer.player.joined", "%s joined the game"),
+    ("multiplayer.player.left", "%s left the game"),
+];
+
+impl TextComponent {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn colored(text: impl Into<String>, color: &str) -> Self {
+        Self {
+            color: Some(color.to_string()),
+            ..Self::text(text)
+        }
+    }
+
+    // Reads a string with legacy formatting codes, like "§cred §lbold" (code is § or &).
+    // A color code resets the formatting before it, like it does in the game.
+    pub fn from_legacy(legacy: &str, code: char) -> Self {
+        let mut parts = vec![];
+        let mut style = Style::default();
+        let mut current = String::new();
+        let mut chars = legacy.chars().peekable();
+        while let Some(character) = chars.next() {
+            let format = match chars.peek() {
+                Some(format) if character == code => format.to_ascii_lowercase(),
+                _ => {
+                    current.push(character);
+                    continue;
+                }
+            };
+            let new_style = if let Some((_, color)) = COLORS.iter().find(|(c, _)| *c == format) {
+                Style {
+                    color: Some(color.to_string()),
+                    ..Default::default()
+                }
+            } else {
+                let mut new_style = style.clone();
+                match format {
+                    'k' => new_style.obfuscated = true,
+                    'l' => new_style.bold = true,
+                    'm' => new_style.strikethrough = true,
+                    'n' => new_style.underlined = true,
+                    'o' => new_style.italic = true,
+                    'r' => new_style = Style::default(),
+                    // Not a code, so it's just text.
+                    _ => {
+                        current.push(character);
+                        continue;
+                    }
+                }
+                new_style
+            };
+            chars.next();
+            if !current.is_empty() {
+                parts.push(Self::styled(std::mem::take(&mut current), &style));
+            }
+            style = new_style;
+        }
+        if !current.is_empty() {
+            parts.push(Self::styled(current, &style));
+        }
+
+        match parts.len() {
+            0 => Self::text(""),
+            1 => parts.remove(0),
+            _ => Self {
+                extra: parts,
+                ..Self::text("")
+            },
+        }
+    }
+
+    fn styled(text: String, style: &Style) -> Self {
+        // Only what is set is written, so it doesn't override the style of a parent.
+        let set = |value: bool| value.then_some(true);
+        Self {
+            color: style.color.clone(),
+            bold: set(style.bold),
+            italic: set(style.italic),
+            underlined: set(style.underlined),
+            strikethrough: set(style.strikethrough),
+            obfuscated: set(style.obfuscated),
+            ..Self::text(text)
+        }
+    }
+
+    // The other way around, the styles are written as codes in front of the text they apply to.
+    // Hex colors don't have a code, so text with them is written without a color.
+    pub fn to_legacy(&self, code: char) -> String {
+        let mut pieces = vec![];
+        self.pieces(&Style::default(), &mut pieces);
+
+        let mut legacy = String::new();
+        let mut current = Style::default();
+        for (text, style) in pieces {
+            if text.is_empty() {
+                continue;
+            }
+            if style != current {
+                let color = style
+                    .color
+                    .as_ref()
+                    .and_then(|color| COLORS.iter().find(|(_, name)| name == color));
+                legacy.push(code);
+                legacy.push(color.map_or('r', |(c, _)| *c));
+                for format in style.formats() {
+                    legacy.push(code);
+                    legacy.push(format);
+                }
+                current = style;
+            }
+            legacy.push_str(&text);
+        }
+        legacy
+    }
+
+    // Only the text, for logs and the console. Legacy codes inside the text are left out as well.
+    pub fn to_plain(&self) -> String {
+        let mut pieces = vec![];
+        self.pieces(&Style::default(), &mut pieces);
+        let text: String = pieces.into_iter().map(|(text, _)| text).collect();
+        strip_legacy(&text, '§')
+    }
+
+    fn pieces(&self, parent: &Style, pieces: &mut Vec<(String, Style)>) {
+        let style = parent.inherit(self);
+        pieces.push((self.own_text(), style.clone()));
+        for child in &self.extra {
+            child.pieces(&style, pieces);
+        }
+    }
+
+    fn own_text(&self) -> String {
+        let key = match &self.translate {
+            Some(key) => key,
+            None => return self.text.clone().unwrap_or_default(),
+        };
+        let arguments: Vec<String> = self.with.iter().map(|with| with.to_plain()).collect();
+        match TRANSLATIONS.iter().find(|(k, _)| k == key) {
+            Some((_, format)) => {
+                let mut arguments = arguments.into_iter();
+                format
+                    .split("%s")
+                    .enumerate()
+                    .map(|(i, part)| match i {
+                        0 => part.to_string(),
+                        _ => format!("{}{}", arguments.next().unwrap_or_default(), part),
+                    })
+                    .collect()
+            }
+            None if arguments.is_empty() => key.to_owned(),
+            None => format!("{}[{}]", key, arguments.join(", ")),
+        }
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl fmt::Display for TextComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_plain())
+    }
+}
+
+pub fn strip_legacy(text: &str, code: char) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(character) = chars.next() {
+        if character == code {
+            chars.next();
+        } else {
+            stripped.push(character);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_forms() {
+        let plain: TextComponent = serde_json::from_str("\"hi\"").unwrap();
+        assert_eq!(plain, TextComponent::text("hi"));
+
+        let list: TextComponent =
+            serde_json::from_str(r#"[{"text":"a","color":"red"},"b",{"text":"c"}]"#).unwrap();
+        assert_eq!(list.to_plain(), "abc");
+        assert_eq!(list.extra.len(), 2);
+
+        let translated: TextComponent = serde_json::from_str(
+            r#"{"translate":"chat.type.text","with":[{"text":"Steve","clickEvent":{"action":"suggest_command","value":"/tell Steve "}},"hello \"there\""]}"#,
+        )
+        .unwrap();
+        assert_eq!(translated.to_plain(), "<Steve> hello \"there\"");
+        assert_eq!(
+            translated.with[0].click_event,
+            Some(ClickEvent {
+                action: ClickAction::SuggestCommand,
+                value: "/tell Steve ".to_string(),
+            })
+        );
+
+        let unknown: TextComponent =
+            serde_json::from_str(r#"{"translate":"death.attack.lava","with":["Steve"]}"#).unwrap();
+        assert_eq!(unknown.to_plain(), "death.attack.lava[Steve]");
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let json = r#"{"text":"","extra":[{"text":"proxy","color":"red"},{"text":"> \"quoted\"","hoverEvent":{"action":"show_text","contents":"hi"}}]}"#;
+        let component: TextComponent = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&component).unwrap(), json);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let component = TextComponent::from_legacy("&cred &lbold&r plain &zno", '&');
+        assert_eq!(component.to_plain(), "red bold plain &zno");
+        assert_eq!(component.extra[1].color.as_deref(), Some("red"));
+        assert_eq!(component.extra[1].bold, Some(true));
+        assert_eq!(component.extra[2].color, None);
+        assert_eq!(component.to_legacy('&'), "&cred &c&lbold&r plain &zno");
+        assert_eq!(component.to_legacy('§'), "§cred §c§lbold§r plain &zno");
+
+        // Codes in the text itself are kept by to_legacy, but not by to_plain.
+        let raw = TextComponent::text("§d[§5§lF§d]§r now");
+        assert_eq!(raw.to_legacy('§'), "§d[§5§lF§d]§r now");
+        assert_eq!(raw.to_plain(), "[F] now");
+
+        assert_eq!(TextComponent::from_legacy("", '§'), TextComponent::text(""));
+        assert_eq!(
+            TextComponent::from_legacy("no codes", '§'),
+            TextComponent::text("no codes")
+        );
+    }
+
+    #[test]
+    fn test_rainbowfy() {
+        let rainbow = crate::utils::rainbowfy(" ab c");
+        assert_eq!(rainbow.to_legacy('&'), " &6a&eb &bc");
+        assert_eq!(rainbow.to_plain(), " ab c");
+    }
+}
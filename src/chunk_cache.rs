@@ -0,0 +1,168 @@
+// The chunks the client has loaded, so plugins can look up the blocks around the player.
+// It is fed by ChunkData, the block change packets and Unload Chunk, and cleared when the world changes.
+use crate::{
+    blocks,
+    chunk_section::ChunkSection,
+    functions::clientbound::play::ChunkData,
+    functions::Fid,
+    packet::Packet,
+    plugin::{first_reply, EventHandler},
+    Direction,
+};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct Chunk {
+    // From the bottom of the world up, None is a section with only air.
+    sections: Vec<Option<ChunkSection>>,
+}
+
+impl Chunk {
+    fn empty() -> Self {
+        Self {
+            sections: vec![None; 16],
+        }
+    }
+
+    fn block_at(&self, x: i32, y: i32, z: i32) -> i32 {
+        match &self.sections[(y >> 4) as usize] {
+            Some(section) => section.get(x, y, z),
+            None => 0,
+        }
+    }
+
+    fn set_block(&mut self, x: i32, y: i32, z: i32, state_id: i32) {
+        self.sections[(y >> 4) as usize]
+            .get_or_insert_with(ChunkSection::empty)
+            .set(x, y, z, state_id);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ChunkCache {
+    chunks: HashMap<(i32, i32), Chunk>,
+}
+
+impl ChunkCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn is_loaded(&self, chunk_x: i32, chunk_z: i32) -> bool {
+        self.chunks.contains_key(&(chunk_x, chunk_z))
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    // A full chunk replaces the old one, otherwise only the sections in the packet change.
+    pub fn load(&mut self, chunk_data: &ChunkData) {
+        let key = (chunk_data.chunk_x, chunk_data.chunk_z);
+        if chunk_data.full_chunk {
+            self.chunks.insert(key, Chunk::empty());
+        }
+        let chunk = match self.chunks.get_mut(&key) {
+            Some(chunk) => chunk,
+            // The client ignores sections of chunks it doesn't have.
+            None => return,
+        };
+        for (y, section) in chunk_data.data.iter().enumerate().take(16) {
+            if section.is_some() {
+                chunk.sections[y] = section.clone();
+            }
+        }
+    }
+
+    pub fn unload(&mut self, chunk_x: i32, chunk_z: i32) {
+        self.chunks.remove(&(chunk_x, chunk_z));
+    }
+
+    fn chunk_at(&self, x: i32, y: i32, z: i32) -> Option<&Chunk> {
+        if !(0..256).contains(&y) {
+            return None;
+        }
+        self.chunks.get(&(x >> 4, z >> 4))
+    }
+
+    // The global state id of the block, None if its chunk isn't loaded.
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<i32> {
+        self.chunk_at(x, y, z).map(|chunk| chunk.block_at(x, y, z))
+    }
+
+    // The name of the block, like minecraft:stone.
+    pub fn block_name_at(&self, x: i32, y: i32, z: i32) -> Option<&'static str> {
+        blocks::block_name(self.block_at(x, y, z)?)
+    }
+
+    // Changes in chunks that aren't loaded are ignored, like the client does.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state_id: i32) {
+        if !(0..256).contains(&y) {
+            return;
+        }
+        if let Some(chunk) = self.chunks.get_mut(&(x >> 4, z >> 4)) {
+            chunk.set_block(x, y, z, state_id);
+        }
+    }
+}
+
+// Shows the changed blocks to the plugins, the cache is already updated then.
+pub fn notify_plugins(
+    fid: Fid,
+    locations: &[(i32, i32, i32)],
+    chunks: &ChunkCache,
+    plugins: &mut [Box<dyn EventHandler + Send>],
+) -> Vec<(Packet, Direction)> {
+    first_reply(fid, plugins, |plugin| {
+        plugin.on_block_update(locations, chunks)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_section::SECTION_VOLUME;
+    use crate::parsable::PacketFields;
+
+    #[test]
+    fn test_chunk_cache() {
+        let mut chunks = ChunkCache::new();
+        let mut chunk_data = ChunkData::default_fields();
+        chunk_data.chunk_x = -1;
+        chunk_data.chunk_z = 2;
+        chunk_data.full_chunk = true;
+        chunk_data.primary_bit_mask = 0b10;
+        chunk_data.data = vec![
+            None,
+            Some(ChunkSection::from_blocks(&[1; SECTION_VOLUME]).unwrap()),
+        ];
+        chunks.load(&chunk_data);
+
+        // Block -16 is the first block of chunk -1.
+        assert_eq!(chunks.block_at(-16, 16, 32), Some(1));
+        assert_eq!(chunks.block_name_at(-1, 31, 47), Some("minecraft:stone"));
+        assert_eq!(chunks.block_at(-16, 0, 32), Some(0));
+        assert_eq!(chunks.block_at(0, 16, 32), None);
+        assert_eq!(chunks.block_at(-16, 256, 32), None);
+
+        chunks.set_block(-5, 70, 40, 8);
+        assert_eq!(
+            chunks.block_name_at(-5, 70, 40),
+            Some("minecraft:grass_block")
+        );
+        // Not loaded, so nothing happens.
+        chunks.set_block(5, 70, 40, 8);
+        assert_eq!(chunks.block_at(5, 70, 40), None);
+
+        chunks.unload(-1, 2);
+        assert!(chunks.is_empty());
+    }
+}
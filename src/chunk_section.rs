@@ -0,0 +1,390 @@
+// One 16x16x16 section of a chunk, in the 1.16 format: the number of blocks that aren't air, the bits per block,
+// the palette and the blocks packed in longs. Since 1.16 a block never spans two longs, so the highest bits of a long
+// are unused when the bits per block don't divide 64.
+use crate::{
+    blocks,
+    codec::FieldCodec,
+    parse_error::{ParseError, ParseErrorKind, ParseResult},
+    raw_packet::RawPacket,
+};
+use serde::Serialize;
+
+pub const SECTION_VOLUME: usize = 16 * 16 * 16;
+// Enough for every block state id of 1.16.5, the client always uses this many bits for global ids.
+pub const GLOBAL_BITS: u8 = 15;
+// Sections with fewer bits are sent with 4, sections with more than 8 use global ids without a palette.
+const MIN_BITS: u8 = 4;
+const MAX_PALETTE_BITS: u8 = 8;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ChunkSection {
+    block_count: i16,
+    // As it was sent.
+    bits_per_block: u8,
+    // Maps the values in the data to global state ids, None if the data has the global ids.
+    palette: Option<Vec<i32>>,
+    data: Vec<u64>,
+}
+
+// The bits the client uses for the values, whatever the server sent.
+fn storage_bits(bits_per_block: u8) -> u8 {
+    match bits_per_block {
+        0..=MIN_BITS => MIN_BITS,
+        bits if bits <= MAX_PALETTE_BITS => bits,
+        _ => GLOBAL_BITS,
+    }
+}
+
+fn long_count(bits: u8) -> usize {
+    let per_long = 64 / bits as usize;
+    SECTION_VOLUME.div_ceil(per_long)
+}
+
+fn is_air(state_id: i32) -> bool {
+    matches!(
+        blocks::block_name(state_id),
+        Some("minecraft:air" | "minecraft:cave_air" | "minecraft:void_air")
+    )
+}
+
+// The index of a block in the section, the blocks are ordered by y, then z, then x.
+fn block_index(x: i32, y: i32, z: i32) -> usize {
+    (((y & 15) << 8) | ((z & 15) << 4) | (x & 15)) as usize
+}
+
+impl ChunkSection {
+    // A section with only air, like the sections that aren't sent.
+    pub fn empty() -> Self {
+        Self {
+            block_count: 0,
+            bits_per_block: MIN_BITS,
+            palette: Some(vec![0]),
+            data: vec![0; long_count(MIN_BITS)],
+        }
+    }
+
+    // Packs the global state ids of all 4096 blocks, with the fewest bits the client accepts.
+    // The palette is sorted by state id.
+    pub fn from_blocks(blocks: &[i32]) -> Result<Self, ()> {
+        if blocks.len() != SECTION_VOLUME {
+            return Err(());
+        }
+        let mut palette = blocks.to_vec();
+        palette.sort_unstable();
+        palette.dedup();
+        let needed_bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as u8;
+        let mut section = Self {
+            block_count: blocks.iter().filter(|block| !is_air(**block)).count() as i16,
+            bits_per_block: storage_bits(needed_bits),
+            palette: None,
+            data: vec![],
+        };
+        if section.bits_per_block <= MAX_PALETTE_BITS {
+            section.palette = Some(palette);
+        }
+        section.pack(blocks);
+        Ok(section)
+    }
+
+    pub fn block_count(&self) -> i16 {
+        self.block_count
+    }
+
+    pub fn bits_per_block(&self) -> u8 {
+        self.bits_per_block
+    }
+
+    pub fn palette(&self) -> Option<&[i32]> {
+        self.palette.as_deref()
+    }
+
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    fn value(&self, index: usize) -> u64 {
+        let bits = storage_bits(self.bits_per_block) as usize;
+        let per_long = 64 / bits;
+        (self.data[index / per_long] >> (index % per_long * bits)) & ((1 << bits) - 1)
+    }
+
+    fn set_value(&mut self, index: usize, value: u64) {
+        let bits = storage_bits(self.bits_per_block) as usize;
+        let per_long = 64 / bits;
+        let shift = index % per_long * bits;
+        let long = &mut self.data[index / per_long];
+        *long = (*long & !(((1 << bits) - 1) << shift)) | (value << shift);
+    }
+
+    fn state_of(&self, value: u64) -> i32 {
+        match &self.palette {
+            // Decoding makes sure every value is in the palette.
+            Some(palette) => palette[value as usize],
+            None => value as i32,
+        }
+    }
+
+    // Writes the data again with the current bits and palette.
+    fn pack(&mut self, blocks: &[i32]) {
+        self.data = vec![0; long_count(storage_bits(self.bits_per_block))];
+        for (index, block) in blocks.iter().enumerate() {
+            let value = match &self.palette {
+                Some(palette) => palette.iter().position(|id| id == block).unwrap() as u64,
+                None => *block as u64,
+            };
+            self.set_value(index, value);
+        }
+    }
+
+    // The global state ids of all blocks, in the order of the section.
+    pub fn blocks(&self) -> Vec<i32> {
+        (0..SECTION_VOLUME)
+            .map(|index| self.state_of(self.value(index)))
+            .collect()
+    }
+
+    // The coordinates can be anywhere in the world, only the position in the section is used.
+    pub fn get(&self, x: i32, y: i32, z: i32) -> i32 {
+        self.state_of(self.value(block_index(x, y, z)))
+    }
+
+    // Sets a block, the palette gets more bits (or is dropped for global ids) when it is full.
+    pub fn set(&mut self, x: i32, y: i32, z: i32, state_id: i32) {
+        let index = block_index(x, y, z);
+        let old = self.state_of(self.value(index));
+        if old == state_id {
+            return;
+        }
+        self.block_count += is_air(old) as i16 - is_air(state_id) as i16;
+
+        let value = match &mut self.palette {
+            Some(palette) => match palette.iter().position(|id| *id == state_id) {
+                Some(position) => Some(position as u64),
+                None if palette.len() < 1 << storage_bits(self.bits_per_block) => {
+                    palette.push(state_id);
+                    Some(palette.len() as u64 - 1)
+                }
+                None => None,
+            },
+            None => Some(state_id as u64),
+        };
+        match value {
+            Some(value) => self.set_value(index, value),
+            None => {
+                let mut blocks = self.blocks();
+                blocks[index] = state_id;
+                self.resize(storage_bits(self.bits_per_block) + 1, blocks);
+            }
+        }
+    }
+
+    fn resize(&mut self, bits: u8, blocks: Vec<i32>) {
+        if bits > MAX_PALETTE_BITS {
+            self.bits_per_block = GLOBAL_BITS;
+            self.palette = None;
+        } else {
+            self.bits_per_block = bits;
+            let palette = self.palette.get_or_insert_with(Vec::new);
+            for block in &blocks {
+                if !palette.contains(block) {
+                    palette.push(*block);
+                }
+            }
+        }
+        self.pack(&blocks);
+    }
+}
+
+impl FieldCodec for ChunkSection {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        let block_count = packet.decode_short()?;
+        let bits_per_block = packet.decode_ubyte()?;
+        let palette = match bits_per_block <= MAX_PALETTE_BITS {
+            true => {
+                let length = packet.decode_varint()?;
+                Some(
+                    (0..length)
+                        .map(|_| packet.decode_varint())
+                        .collect::<ParseResult<Vec<i32>>>()?,
+                )
+            }
+            false => None,
+        };
+
+        // The client doesn't accept data with another length than it expects for the bits.
+        let start = packet.position();
+        let length = packet.decode_varint()?;
+        if length < 0 || length as usize != long_count(storage_bits(bits_per_block)) {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidValue(length as i64),
+                start,
+            ));
+        }
+        let data = (0..length)
+            .map(|_| packet.decode_ulong())
+            .collect::<ParseResult<Vec<u64>>>()?;
+
+        let section = Self {
+            block_count,
+            bits_per_block,
+            palette,
+            data,
+        };
+        if let Some(palette) = &section.palette {
+            if let Some(value) = (0..SECTION_VOLUME)
+                .map(|index| section.value(index))
+                .find(|value| *value as usize >= palette.len())
+            {
+                return Err(ParseError::new(
+                    ParseErrorKind::InvalidValue(value as i64),
+                    start,
+                ));
+            }
+        }
+        Ok(section)
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_short(self.block_count);
+        packet.encode_ubyte(self.bits_per_block);
+        if let Some(palette) = &self.palette {
+            packet.encode_varint(palette.len() as i32);
+            for id in palette {
+                packet.encode_varint(*id);
+            }
+        }
+        packet.encode_varint(self.data.len() as i32);
+        for long in &self.data {
+            packet.encode_ulong(*long);
+        }
+        Ok(())
+    }
+
+    fn printable(&self) -> String {
+        format!("{} blocks {} bits", self.block_count, self.bits_per_block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from the chunk format documentation: 5 bits per block, so 12 blocks in a long.
+    const FIRST_LONGS: [u64; 2] = [0x0020_8631_4841_8841, 0x0101_8A72_60F6_8C87];
+    const FIRST_VALUES: [u64; 24] = [
+        1, 2, 2, 3, 4, 4, 5, 6, 6, 4, 8, 0, 7, 4, 3, 13, 15, 16, 9, 14, 10, 12, 0, 2,
+    ];
+
+    fn encoded(section: &ChunkSection) -> Vec<u8> {
+        let mut packet = RawPacket::new();
+        section.encode(&mut packet).unwrap();
+        packet.get_vec()
+    }
+
+    #[test]
+    fn test_compacted_longs() {
+        let palette: Vec<i32> = (0..17).map(|id| id * 10).collect();
+        let mut blocks: Vec<i32> = FIRST_VALUES
+            .iter()
+            .map(|value| palette[*value as usize])
+            .collect();
+        // 11 isn't in the first two longs, the palette needs it to have all 17 entries.
+        blocks.push(110);
+        blocks.resize(SECTION_VOLUME, 160);
+        let section = ChunkSection::from_blocks(&blocks).unwrap();
+        assert_eq!(section.bits_per_block(), 5);
+        assert_eq!(section.data().len(), 342);
+        assert_eq!(&section.data()[..2], &FIRST_LONGS);
+
+        // Block count (all but the two air blocks), bits, the palette, the number of longs and the longs.
+        let bytes = encoded(&section);
+        let mut expected = vec![0x0F, 0xFE, 5, 17];
+        for id in &palette {
+            let mut varint = RawPacket::new();
+            varint.encode_varint(*id);
+            expected.extend(varint.get_vec());
+        }
+        expected.extend(&[0xD6, 0x02]);
+        expected.extend(&FIRST_LONGS[0].to_be_bytes());
+        expected.extend(&FIRST_LONGS[1].to_be_bytes());
+        assert_eq!(&bytes[..expected.len()], &expected[..]);
+        // The last long has 4 blocks, 342 * 12 is 4104.
+        assert_eq!(bytes.len(), expected.len() + 340 * 8);
+
+        let decoded = ChunkSection::decode(&mut RawPacket::from(bytes.clone())).unwrap();
+        assert_eq!(decoded, section);
+        assert_eq!(decoded.blocks(), blocks);
+        assert_eq!(encoded(&decoded), bytes);
+    }
+
+    #[test]
+    fn test_global_ids() {
+        // More than 256 different blocks don't fit in a palette.
+        let blocks: Vec<i32> = (0..SECTION_VOLUME as i32)
+            .map(|index| index % 300)
+            .collect();
+        let section = ChunkSection::from_blocks(&blocks).unwrap();
+        assert_eq!(section.bits_per_block(), GLOBAL_BITS);
+        assert_eq!(section.palette(), None);
+        assert_eq!(section.data().len(), 1024);
+
+        let bytes = encoded(&section);
+        let decoded = ChunkSection::decode(&mut RawPacket::from(bytes.clone())).unwrap();
+        assert_eq!(decoded.blocks(), blocks);
+        assert_eq!(encoded(&decoded), bytes);
+    }
+
+    #[test]
+    fn test_set_and_resize() {
+        let mut section = ChunkSection::empty();
+        assert_eq!(section.get(3, 4, 5), 0);
+
+        section.set(3, 4, 5, 1);
+        assert_eq!(section.get(3, 4, 5), 1);
+        assert_eq!(section.get(-13, 20, 21), 1);
+        assert_eq!(section.block_count(), 1);
+        assert_eq!(section.bits_per_block(), 4);
+
+        // 16 different blocks fit in 4 bits, the 17th needs 5.
+        for id in 2..17 {
+            section.set(id % 16, 0, id / 16, id);
+        }
+        assert_eq!(section.bits_per_block(), 5);
+        assert_eq!(section.palette().unwrap().len(), 17);
+        assert_eq!(section.get(15, 0, 0), 15);
+        assert_eq!(section.get(0, 0, 1), 16);
+        assert_eq!(section.get(3, 4, 5), 1);
+
+        for id in 17..300 {
+            section.set(id % 16, 8 + id / 256, id / 16 % 16, id);
+        }
+        assert_eq!(section.bits_per_block(), GLOBAL_BITS);
+        assert_eq!(section.get(299 % 16, 9, 299 / 16 % 16), 299);
+        assert_eq!(section.get(3, 4, 5), 1);
+
+        section.set(3, 4, 5, 0);
+        let count = section
+            .blocks()
+            .iter()
+            .filter(|block| !is_air(**block))
+            .count();
+        assert_eq!(section.block_count() as usize, count);
+    }
+
+    #[test]
+    fn test_invalid_sections() {
+        let mut section = encoded(&ChunkSection::empty());
+        // One long less than 4 bits need.
+        section[5] = 0xFF;
+        section[6] = 0x01;
+        let error = ChunkSection::decode(&mut RawPacket::from(section)).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue(255));
+
+        // A value of 1 with only one block in the palette.
+        let mut section = encoded(&ChunkSection::empty());
+        let last = section.len() - 1;
+        section[last] = 1;
+        let error = ChunkSection::decode(&mut RawPacket::from(section)).unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue(1));
+    }
+}
@@ -1,16 +1,11 @@
-// use aes::cipher::AsyncStreamCipher;
-use aes::Aes128;
-mod cfb8;
-use cfb8::{
-    cipher::{AsyncStreamCipher, NewCipher},
-    Cfb8,
-};
+use std::convert::TryInto;
 
-type AesCfb8 = Cfb8<Aes128>;
+mod cfb8;
+use cfb8::Cfb8;
 
 #[derive(Clone)]
 pub struct Cipher {
-    pub encryptor: Option<AesCfb8>,
+    pub encryptor: Option<Cfb8>,
 }
 
 impl Cipher {
@@ -18,29 +13,27 @@ impl Cipher {
         Self { encryptor: None }
     }
 
-    pub fn decrypt(&mut self, mut data: Vec<u8>) -> Vec<u8> {
-        match &mut self.encryptor {
-            Some(encryptor) => {
-                encryptor.decrypt(data.as_mut_slice());
-                data
-            }
-            None => data,
+    // Both of these work in place, so the data doesn't have to be copied when encryption is off.
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.decrypt(data);
         }
     }
 
-    pub fn encrypt(&mut self, mut data: Vec<u8>) -> Vec<u8> {
-        match &mut self.encryptor {
-            Some(encryptor) => {
-                encryptor.encrypt(data.as_mut_slice());
-                data
-            }
-            None => data,
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(data);
         }
     }
 
+    pub fn is_enabled(&self) -> bool {
+        self.encryptor.is_some()
+    }
+
     pub fn enable(&mut self, key: &[u8]) {
-        let cipher = AesCfb8::new_from_slices(key, key).unwrap();
-        self.encryptor = Some(cipher);
+        // Minecraft uses the shared secret as both the key and the IV.
+        let key = key.try_into().unwrap();
+        self.encryptor = Some(Cfb8::new(key, key));
     }
 
     pub fn disable(&mut self) {
@@ -1,101 +1,151 @@
-//! Generic [8-bit Cipher Feedback (CFB8)][1] mode implementation.
-//!
-//! This crate implements CFB8 as a [self-synchronizing stream cipher][2].
-//!
-//! # Security Warning
-//! This crate does not ensure ciphertexts are authentic! Thus ciphertext integrity
-//! is not verified, which can lead to serious vulnerabilities!
-//!
-//! # Examples
-//! ```
-//! use aes::Aes128;
-//! use cfb8::Cfb8;
-//! use cfb8::cipher::{NewCipher, AsyncStreamCipher};
-//! use hex_literal::hex;
-//!
-//! type AesCfb8 = Cfb8<Aes128>;
-//!
-//! let key = b"very secret key.";
-//! let iv = b"unique init vect";
-//! let plaintext = b"The quick brown fox jumps over the lazy dog.";
-//! let ciphertext = hex!("
-//!     8fb603d8 66a1181c 08506c75 37ee9cad
-//!     35be8ff8 e0c79526 9d735d04 c0a93017
-//!     b1a748e0 25146b68 23fc9ad3
-//! ");
-//!
-//! let mut data = plaintext.to_vec();
-//! // encrypt plaintext
-//! AesCfb8::new_from_slices(key, iv).unwrap().encrypt(&mut data);
-//! assert_eq!(data, &ciphertext[..]);
-//! // and decrypt it back
-//! AesCfb8::new_from_slices(key, iv).unwrap().decrypt(&mut data);
-//! assert_eq!(data, &plaintext[..]);
-//!
-//! // CFB mode can be used with streaming messages
-//! let mut cipher = AesCfb8::new_from_slices(key, iv).unwrap();
-//! for chunk in data.chunks_mut(3) {
-//!     cipher.encrypt(chunk);
-//! }
-//! assert_eq!(data, &ciphertext[..]);
-//! ```
-//!
-//! [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#CFB
-//! [2]: https://en.wikipedia.org/wiki/Stream_cipher#Self-synchronizing_stream_ciphers
-
-#![deny(unsafe_code)]
-#![warn(missing_docs)]
-
-pub use cipher;
-
-use cipher::{
-    generic_array::GenericArray, AsyncStreamCipher, BlockCipher, BlockEncrypt, FromBlockCipher,
+use aes::{
+    cipher::{generic_array::GenericArray, BlockEncrypt, NewBlockCipher},
+    Aes128, Block,
 };
 
-/// CFB self-synchronizing stream cipher instance.
+const BLOCK_SIZE: usize = 16;
+// How many bytes are decrypted at once. The blocks for all of them are encrypted in one go,
+// that lets AES-NI work on multiple blocks at the same time.
+const BATCH_SIZE: usize = 512;
+
+// AES-128 in CFB8 mode, which is what minecraft uses for encryption.
+// Every byte takes a whole AES block: the last 16 bytes of ciphertext (starting with the IV) are encrypted,
+// and the first byte of that is XORed with the data.
 #[derive(Clone)]
-pub struct Cfb8<C: BlockCipher + BlockEncrypt> {
-    cipher: C,
-    iv: GenericArray<u8, C::BlockSize>,
+pub struct Cfb8 {
+    cipher: Aes128,
+    // The last 16 bytes of ciphertext.
+    iv: Block,
+    // Reused between batches when decrypting, so nothing gets allocated for every read.
+    window: Vec<u8>,
+    blocks: Vec<Block>,
 }
 
-impl<C: BlockCipher + BlockEncrypt> FromBlockCipher for Cfb8<C> {
-    type BlockCipher = C;
-    type NonceSize = C::BlockSize;
-
-    fn from_block_cipher(cipher: C, iv: &GenericArray<u8, Self::NonceSize>) -> Self {
+impl Cfb8 {
+    pub fn new(key: &[u8; BLOCK_SIZE], iv: &[u8; BLOCK_SIZE]) -> Self {
         Self {
-            cipher,
-            iv: iv.clone(),
+            cipher: Aes128::new(GenericArray::from_slice(key)),
+            iv: *Block::from_slice(iv),
+            window: Vec::with_capacity(BLOCK_SIZE + BATCH_SIZE),
+            blocks: Vec::with_capacity(BATCH_SIZE),
+        }
+    }
+
+    // Every block depends on the ciphertext of the byte before it, so encrypting can only be done one byte at a time.
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let mut block = self.iv;
+            self.cipher.encrypt_block(&mut block);
+            *byte ^= block[0];
+            self.iv.copy_within(1.., 0);
+            self.iv[BLOCK_SIZE - 1] = *byte;
+        }
+    }
+
+    // For decrypting, all the ciphertext is already known, so the blocks of a whole batch can be encrypted together.
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for batch in data.chunks_mut(BATCH_SIZE) {
+            self.window.clear();
+            self.window.extend_from_slice(&self.iv);
+            self.window.extend_from_slice(batch);
+            self.blocks.clear();
+            self.blocks.extend(
+                self.window
+                    .windows(BLOCK_SIZE)
+                    .take(batch.len())
+                    .map(|iv| *Block::from_slice(iv)),
+            );
+            self.cipher.encrypt_blocks(&mut self.blocks);
+            for (byte, block) in batch.iter_mut().zip(&self.blocks) {
+                *byte ^= block[0];
+            }
+            // The next IV is the last 16 bytes of ciphertext.
+            self.iv
+                .copy_from_slice(&self.window[self.window.len() - BLOCK_SIZE..]);
         }
     }
 }
 
-impl<C: BlockCipher + BlockEncrypt> AsyncStreamCipher for Cfb8<C> {
-    fn encrypt(&mut self, data: &mut [u8]) {
-        let mut iv = self.iv.clone();
-        let n = iv.len();
-        for b in data.iter_mut() {
-            let iv_copy = iv.clone();
-            self.cipher.encrypt_block(&mut iv);
-            *b ^= iv[0];
-            iv[..n - 1].clone_from_slice(&iv_copy[1..]);
-            iv[n - 1] = *b;
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    // The way it was done before, one byte at a time with the IV shifted for every byte.
+    fn reference_encrypt(key: &[u8; 16], data: &mut [u8]) {
+        let cipher = Aes128::new(GenericArray::from_slice(key));
+        let mut iv = *key;
+        for byte in data.iter_mut() {
+            let mut block = *Block::from_slice(&iv);
+            cipher.encrypt_block(&mut block);
+            *byte ^= block[0];
+            iv.copy_within(1.., 0);
+            iv[15] = *byte;
+        }
+    }
+
+    fn test_vector(key: &[u8], iv: &[u8], plaintext: &[u8], ciphertext: &str) {
+        let key = key.try_into().unwrap();
+        let iv = iv.try_into().unwrap();
+        let ciphertext = hex::decode(ciphertext.replace(' ', "")).unwrap();
+
+        let mut data = plaintext.to_vec();
+        Cfb8::new(key, iv).encrypt(&mut data);
+        assert_eq!(data, ciphertext);
+        Cfb8::new(key, iv).decrypt(&mut data);
+        assert_eq!(data, plaintext);
+
+        // It works the same when the data comes in a few bytes at a time.
+        let mut cfb8 = Cfb8::new(key, iv);
+        for chunk in data.chunks_mut(3) {
+            cfb8.encrypt(chunk);
         }
-        self.iv = iv;
+        assert_eq!(data, ciphertext);
+        let mut cfb8 = Cfb8::new(key, iv);
+        for chunk in data.chunks_mut(5) {
+            cfb8.decrypt(chunk);
+        }
+        assert_eq!(data, plaintext);
+    }
+
+    #[test]
+    fn test_nist_vectors() {
+        // CFB8-AES128 from NIST SP 800-38A, F.3.7 and F.3.8.
+        test_vector(
+            &hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap(),
+            &hex::decode("000102030405060708090a0b0c0d0e0f").unwrap(),
+            &hex::decode("6bc1bee22e409f96e93d7e117393172aae2d").unwrap(),
+            "3b79424c9c0dd436bace9e0ed4586a4f32b9",
+        );
+        // From the documentation of the cfb8 crate.
+        test_vector(
+            b"very secret key.",
+            b"unique init vect",
+            b"The quick brown fox jumps over the lazy dog.",
+            "8fb603d8 66a1181c 08506c75 37ee9cad 35be8ff8 e0c79526 9d735d04 c0a93017 b1a748e0 25146b68 23fc9ad3",
+        );
     }
 
-    fn decrypt(&mut self, data: &mut [u8]) {
-        let mut iv = self.iv.clone();
-        let n = iv.len();
-        for b in data.iter_mut() {
-            let iv_copy = iv.clone();
-            self.cipher.encrypt_block(&mut iv);
-            let t = *b;
-            *b ^= iv[0];
-            iv[..n - 1].clone_from_slice(&iv_copy[1..]);
-            iv[n - 1] = t;
+    #[test]
+    fn test_large_data() {
+        // More than a few batches, with chunks that don't line up with them, compared to the simple way of doing it.
+        let key = b"0123456789abcdef";
+        let plaintext: Vec<u8> = (0..10_000).map(|i| (i * 7 % 251) as u8).collect();
+        let mut expected = plaintext.clone();
+        reference_encrypt(key, &mut expected);
+
+        let mut data = plaintext.clone();
+        let mut cfb8 = Cfb8::new(key, key);
+        for chunk in data.chunks_mut(1500) {
+            cfb8.encrypt(chunk);
+        }
+        assert_eq!(data, expected);
+
+        let mut cfb8 = Cfb8::new(key, key);
+        for chunk in data.chunks_mut(4096) {
+            cfb8.decrypt(chunk);
         }
-        self.iv = iv;
+        assert_eq!(data, plaintext);
     }
 }
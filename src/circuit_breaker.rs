@@ -0,0 +1,106 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{functions::Fid, Direction};
+
+// After this many failures in a row, a packet type isn't parsed anymore for the rest of the session.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+// Keeps track of the packet types that keep failing to parse, edit or encode in one direction of a connection.
+// Packets that fail are sent on unchanged, and packet types that keep failing get passed through without parsing them.
+pub struct CircuitBreaker {
+    direction: Direction,
+    failures: HashMap<Fid, u32>,
+    demoted: HashSet<Fid>,
+}
+
+impl CircuitBreaker {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            failures: HashMap::new(),
+            demoted: HashSet::new(),
+        }
+    }
+
+    pub fn is_demoted(&self, fid: &Fid) -> bool {
+        self.demoted.contains(fid)
+    }
+
+    pub fn record_success(&mut self, fid: &Fid) {
+        self.failures.remove(fid);
+    }
+
+    pub fn record_failure(&mut self, fid: &Fid, reason: &str) {
+        let failures = self.failures.entry(*fid).or_insert(0);
+        *failures += 1;
+        if *failures >= MAX_CONSECUTIVE_FAILURES {
+            log::warn!(
+                "{} {} failed {} times in a row (last error: {}), it will be passed through unchanged for the rest of this session",
+                self.direction,
+                fid,
+                failures,
+                reason
+            );
+            self.failures.remove(fid);
+            self.demoted.insert(*fid);
+        }
+    }
+
+    // Logs which packets were passed through, this is done when the connection closes.
+    pub fn log_summary(&self) {
+        if !self.demoted.is_empty() {
+            let mut demoted: Vec<String> = self.demoted.iter().map(|fid| fid.to_string()).collect();
+            demoted.sort();
+            log::warn!(
+                "{} packets that were passed through because they kept failing: {}",
+                self.direction,
+                demoted.join(", ")
+            );
+        }
+    }
+}
+
+// Gets the message out of a panic that was caught.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demotion() {
+        let mut breaker = CircuitBreaker::new(Direction::Clientbound);
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            breaker.record_failure(&Fid::ChunkData, "test");
+        }
+        assert!(!breaker.is_demoted(&Fid::ChunkData));
+        breaker.record_failure(&Fid::ChunkData, "test");
+        assert!(breaker.is_demoted(&Fid::ChunkData));
+        // Only the packet type that kept failing is demoted.
+        assert!(!breaker.is_demoted(&Fid::EntityMetadata));
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let mut breaker = CircuitBreaker::new(Direction::Serverbound);
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            breaker.record_failure(&Fid::ChatMessageServerbound, "test");
+        }
+        breaker.record_success(&Fid::ChatMessageServerbound);
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            breaker.record_failure(&Fid::ChatMessageServerbound, "test");
+        }
+        assert!(!breaker.is_demoted(&Fid::ChatMessageServerbound));
+    }
+}
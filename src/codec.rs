@@ -0,0 +1,240 @@
+use crate::{
+    chat::TextComponent,
+    parse_error::ParseResult,
+    raw_packet::RawPacket,
+    types::{Metadata, Slot, Uuid},
+};
+
+// How a type is decoded from and encoded to a packet, it's used by #[derive(Packet)] for every field without attributes.
+// Structs and enums that derive Packet implement it too, so they can be used inside other packets.
+pub trait FieldCodec: Sized {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self>;
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()>;
+
+    // How the value is shown in the packet log.
+    fn printable(&self) -> String;
+}
+
+macro_rules! field_codec {
+    ($type:ty, $decode:ident, $encode:ident) => {
+        impl FieldCodec for $type {
+            fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+                packet.$decode()
+            }
+
+            fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+                packet.$encode(self.to_owned());
+                Ok(())
+            }
+
+            fn printable(&self) -> String {
+                self.to_string()
+            }
+        }
+    };
+}
+
+field_codec!(bool, decode_bool, encode_bool);
+field_codec!(i8, decode_byte, encode_byte);
+field_codec!(u8, decode_ubyte, encode_ubyte);
+field_codec!(i16, decode_short, encode_short);
+field_codec!(u16, decode_ushort, encode_ushort);
+field_codec!(i32, decode_int, encode_int);
+field_codec!(i64, decode_long, encode_long);
+field_codec!(f32, decode_float, encode_float);
+field_codec!(f64, decode_double, encode_double);
+field_codec!(String, decode_string, encode_string);
+
+// An optional value with a bool in front of it that says if it's there.
+// For values that depend on another field, #[optional(if = ...)] is used instead.
+impl<T: FieldCodec> FieldCodec for Option<T> {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        Ok(match packet.decode_bool()? {
+            true => Some(T::decode(packet)?),
+            false => None,
+        })
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_bool(self.is_some());
+        match self {
+            Some(value) => value.encode(packet),
+            None => Ok(()),
+        }
+    }
+
+    fn printable(&self) -> String {
+        match self {
+            Some(value) => value.printable(),
+            None => "None".to_string(),
+        }
+    }
+}
+
+impl FieldCodec for Uuid {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        packet.decode_uuid()
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_uuid(self.value());
+        Ok(())
+    }
+
+    fn printable(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FieldCodec for Slot {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        packet.decode_slot()
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_slot(self.to_owned());
+        Ok(())
+    }
+
+    fn printable(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl FieldCodec for TextComponent {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        packet.decode_chat()
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_chat(self);
+        Ok(())
+    }
+
+    fn printable(&self) -> String {
+        self.to_plain()
+    }
+}
+
+impl FieldCodec for Metadata {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        packet.decode_entity_metadata()
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_entity_metadata(self);
+        Ok(())
+    }
+
+    fn printable(&self) -> String {
+        format!("{:?}", self.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsable::{Packet, PacketFields};
+    use crate::raw_packet::RawPacket;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Default, PartialEq, Packet)]
+    #[packet(byte)]
+    enum Mode {
+        #[default]
+        Off,
+        On = 3,
+        Other,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Tag {
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, Packet)]
+    struct Everything {
+        #[varint]
+        id: i32,
+        #[varlong]
+        big: i64,
+        flag: bool,
+        mode: Mode,
+        #[position]
+        location: (i32, i32, i32),
+        #[nbt]
+        tag: Tag,
+        #[prefixed_array]
+        #[varint]
+        list: Vec<i32>,
+        count: u8,
+        #[array(len = self.count)]
+        names: Vec<String>,
+        #[optional(if = self.flag)]
+        extra: Option<f32>,
+        #[rest]
+        data: Vec<u8>,
+    }
+
+    #[test]
+    fn test_derive_round_trip() {
+        let value = Everything {
+            id: 300,
+            big: -1,
+            flag: true,
+            mode: Mode::Other,
+            location: (-10, 64, 2000),
+            tag: Tag {
+                name: "tag".to_string(),
+            },
+            list: vec![1, -1, 1 << 20],
+            count: 2,
+            names: vec!["a".to_string(), "bc".to_string()],
+            extra: Some(1.5),
+            data: vec![1, 2, 3],
+        };
+        let mut packet = RawPacket::new();
+        value.encode_fields(&mut packet).unwrap();
+        assert_eq!(&packet.get_vec()[..2], &[0xAC, 0x02]);
+
+        let mut decoded = Everything::default_fields();
+        decoded.decode_fields(&mut packet).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(packet.len(), 0);
+    }
+
+    #[test]
+    fn test_derive_errors() {
+        // The length has to match the field it comes from.
+        let value = Everything {
+            count: 3,
+            ..Everything::default_fields()
+        };
+        assert!(value.encode_fields(&mut RawPacket::new()).is_err());
+
+        // The same goes for an optional and its condition, both ways.
+        let value = Everything {
+            flag: true,
+            extra: None,
+            ..Everything::default_fields()
+        };
+        assert!(value.encode_fields(&mut RawPacket::new()).is_err());
+        let value = Everything {
+            flag: false,
+            extra: Some(1.5),
+            ..Everything::default_fields()
+        };
+        assert!(value.encode_fields(&mut RawPacket::new()).is_err());
+
+        // 2 isn't a Mode.
+        let mut packet = RawPacket::new();
+        packet.encode_varint(1);
+        packet.encode_varint(1);
+        packet.encode_bool(false);
+        packet.encode_byte(2);
+        let error = Everything::default_fields()
+            .decode_fields(&mut packet)
+            .unwrap_err();
+        assert!(error.to_string().contains("mode"), "{}", error);
+    }
+}
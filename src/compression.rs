@@ -0,0 +1,167 @@
+use miniz_oxide::{
+    deflate::core::{compress, CompressorOxide, TDEFLFlush, TDEFLStatus},
+    DataFormat,
+};
+
+use crate::{conf::Configuration, Direction};
+
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+// Compresses the packets the proxy sends itself (injected or edited ones).
+// There is one per connection, so the deflate state and the output buffer don't have to be allocated for every packet.
+pub struct Compressor {
+    // This is pretty big, so it's kept on the heap.
+    compressor: Box<CompressorOxide>,
+    level: u8,
+    buffer: Vec<u8>,
+    // The lowest thresholds the proxy uses for each direction, the server's threshold is used if it is higher.
+    min_threshold_clientbound: u32,
+    min_threshold_serverbound: u32,
+}
+
+impl Compressor {
+    pub fn new(level: u8) -> Self {
+        let mut compressor = Box::<CompressorOxide>::default();
+        compressor.set_format_and_level(DataFormat::Zlib, level);
+        Self {
+            compressor,
+            level,
+            buffer: Vec::new(),
+            min_threshold_clientbound: 0,
+            min_threshold_serverbound: 0,
+        }
+    }
+
+    pub fn from_config(config: &Configuration) -> Self {
+        let mut compressor = Self::new(config.compression_level);
+        compressor.configure(config);
+        compressor
+    }
+
+    // Takes over the settings of a (reloaded) config.
+    pub fn configure(&mut self, config: &Configuration) {
+        self.set_level(config.compression_level);
+        self.min_threshold_clientbound = config.compression_threshold_clientbound;
+        self.min_threshold_serverbound = config.compression_threshold_serverbound;
+    }
+
+    pub fn set_level(&mut self, level: u8) {
+        if level != self.level {
+            self.compressor
+                .set_format_and_level(DataFormat::Zlib, level);
+            self.level = level;
+        }
+    }
+
+    // The threshold to use for a packet the proxy sends. Packets under the threshold are sent uncompressed,
+    // which the client and server accept no matter how big the packet is. Compression can't be turned on this way though,
+    // so 0 stays 0.
+    pub fn threshold(&self, server_threshold: u32, direction: Direction) -> u32 {
+        if server_threshold == 0 {
+            return 0;
+        }
+        let minimum = match direction {
+            Direction::Clientbound => self.min_threshold_clientbound,
+            Direction::Serverbound => self.min_threshold_serverbound,
+        };
+        server_threshold.max(minimum)
+    }
+
+    // Compresses the parts after each other with a zlib wrapper, so they don't have to be copied together first.
+    // The result is only valid until the next call.
+    pub fn compress(&mut self, parts: &[&[u8]]) -> Result<&[u8], ()> {
+        self.compressor.reset();
+        self.buffer.clear();
+        let total: usize = parts.iter().map(|part| part.len()).sum();
+        self.buffer.resize(std::cmp::max(total / 2, 64), 0);
+
+        let mut out_pos = 0;
+        for (i, part) in parts.iter().enumerate() {
+            let flush = if i == parts.len() - 1 {
+                TDEFLFlush::Finish
+            } else {
+                TDEFLFlush::None
+            };
+            let mut in_pos = 0;
+            loop {
+                let (status, bytes_in, bytes_out) = compress(
+                    &mut self.compressor,
+                    &part[in_pos..],
+                    &mut self.buffer[out_pos..],
+                    flush,
+                );
+                in_pos += bytes_in;
+                out_pos += bytes_out;
+
+                match status {
+                    TDEFLStatus::Done => break,
+                    // All of this part has been taken in, the rest comes with the next part.
+                    TDEFLStatus::Okay if flush == TDEFLFlush::None && in_pos == part.len() => break,
+                    TDEFLStatus::Okay => {
+                        // The output buffer is full, this is the same amount of room miniz_oxide leaves.
+                        if self.buffer.len() - out_pos < 30 {
+                            self.buffer.resize(self.buffer.len() * 2, 0);
+                        }
+                    }
+                    _ => return Err(()),
+                }
+            }
+        }
+        self.buffer.truncate(out_pos);
+        Ok(&self.buffer)
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION_LEVEL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    use super::*;
+
+    #[test]
+    fn test_compress_reuse() {
+        let mut compressor = Compressor::default();
+        let big: Vec<u8> = (0..100_000).map(|i| (i * 7 % 251) as u8).collect();
+        let small = b"hello hello hello hello".to_vec();
+
+        // The state of one packet doesn't leak into the next one.
+        for data in [&big, &small, &big, &small] {
+            let compressed = compressor.compress(&[data]).unwrap().to_vec();
+            assert_eq!(decompress_to_vec_zlib(&compressed).unwrap(), *data);
+        }
+
+        // Parts are compressed like they were one piece of data.
+        let compressed = compressor
+            .compress(&[&small, &[], &big, &small])
+            .unwrap()
+            .to_vec();
+        assert_eq!(
+            decompress_to_vec_zlib(&compressed).unwrap(),
+            [&small[..], &big, &small].concat()
+        );
+
+        for level in [0, 1, 9] {
+            compressor.set_level(level);
+            let compressed = compressor.compress(&[&big]).unwrap().to_vec();
+            assert_eq!(decompress_to_vec_zlib(&compressed).unwrap(), big);
+        }
+    }
+
+    #[test]
+    fn test_threshold() {
+        let compressor = Compressor {
+            min_threshold_clientbound: 1024,
+            ..Compressor::default()
+        };
+        assert_eq!(compressor.threshold(256, Direction::Clientbound), 1024);
+        assert_eq!(compressor.threshold(2048, Direction::Clientbound), 2048);
+        assert_eq!(compressor.threshold(256, Direction::Serverbound), 256);
+        assert_eq!(compressor.threshold(0, Direction::Clientbound), 0);
+    }
+}
@@ -1,6 +1,12 @@
-use config::{Config, File, FileFormat};
+use std::sync::Arc;
+
+use config::{Config, ConfigError, File, FileFormat};
+use parking_lot::RwLock;
 use serde::Deserialize;
 
+// The config that is currently in use, it gets swapped out when the config is reloaded.
+pub type SharedConfig = Arc<RwLock<Arc<Configuration>>>;
+
 pub struct Configuration {
     pub logging_packets: Vec<String>,
     pub player_uuid: String,
@@ -8,9 +14,18 @@ pub struct Configuration {
     pub print_buffer: usize,
     pub ws_url: String,
     pub ws_enabled: bool,
+    pub ws_control_enabled: bool,
     pub listen_address: String,
     pub ws_secret: String,
     pub domain_suffix: String,
+    pub admin_enabled: bool,
+    pub admin_address: String,
+    pub admin_token: String,
+    pub log_json: bool,
+    pub save_failed_packets: bool,
+    pub compression_level: u8,
+    pub compression_threshold_clientbound: u32,
+    pub compression_threshold_serverbound: u32,
 }
 
 #[derive(Deserialize)]
@@ -20,20 +35,44 @@ pub struct ReadConfiguration {
     pub player_auth_token: String,
     pub ws_url: String,
     pub ws_enabled: bool,
+    #[serde(default)]
+    pub ws_control_enabled: bool,
     pub listen_address: String,
     pub ws_secret: String,
     pub domain_suffix: String,
+    #[serde(default)]
+    pub admin_enabled: bool,
+    #[serde(default = "default_admin_address")]
+    pub admin_address: String,
+    #[serde(default)]
+    pub admin_token: String,
+    #[serde(default)]
+    pub log_json: bool,
+    #[serde(default)]
+    pub save_failed_packets: bool,
+    #[serde(default = "default_compression_level")]
+    pub compression_level: u8,
+    #[serde(default)]
+    pub compression_threshold_clientbound: u32,
+    #[serde(default)]
+    pub compression_threshold_serverbound: u32,
 }
 
-pub fn get_config() -> Configuration {
+fn default_admin_address() -> String {
+    "127.0.0.1:25580".to_string()
+}
+
+fn default_compression_level() -> u8 {
+    crate::compression::DEFAULT_COMPRESSION_LEVEL
+}
+
+pub fn load_config() -> Result<Configuration, ConfigError> {
     let mut settings = Config::new();
 
-    settings
-        .merge(File::new("config", FileFormat::Yaml))
-        .unwrap();
+    settings.merge(File::new("config", FileFormat::Yaml))?;
 
-    let config: ReadConfiguration = settings.try_into().unwrap();
-    Configuration {
+    let config: ReadConfiguration = settings.try_into()?;
+    Ok(Configuration {
         logging_packets: config.logging_packets.clone(),
         player_uuid: config.player_uuid,
         player_auth_token: config.player_auth_token,
@@ -46,8 +85,22 @@ pub fn get_config() -> Configuration {
         }),
         ws_url: config.ws_url,
         ws_enabled: config.ws_enabled,
+        ws_control_enabled: config.ws_control_enabled,
         listen_address: config.listen_address,
         ws_secret: config.ws_secret,
         domain_suffix: config.domain_suffix,
-    }
+        admin_enabled: config.admin_enabled,
+        admin_address: config.admin_address,
+        admin_token: config.admin_token,
+        log_json: config.log_json,
+        save_failed_packets: config.save_failed_packets,
+        // zlib doesn't go higher than 9, miniz_oxide has a 10 but that is really slow.
+        compression_level: config.compression_level.min(9),
+        compression_threshold_clientbound: config.compression_threshold_clientbound,
+        compression_threshold_serverbound: config.compression_threshold_serverbound,
+    })
+}
+
+pub fn get_config() -> Configuration {
+    load_config().unwrap()
 }
@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{net::TcpStream, time::timeout};
+use tokio_tungstenite::{tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::{
+    chat::TextComponent,
+    functions::{
+        clientbound::play::{ChatMessageClientbound, ChatMessagePosition, DisconnectPlay},
+        serverbound::play::ChatMessageServerbound,
+    },
+    parsable::Parsable,
+    sessions::{SessionInfo, Sessions},
+    tab_list::TabList,
+    types::Uuid,
+    utils::generate_message_packet,
+    Direction, State, SHUTDOWN_CHECK_TIMEOUT,
+};
+
+// The websocket that was used to authenticate a login.
+pub type ControlLink = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// Chat messages sent by the client can't be longer than this many characters, the server kicks the player otherwise.
+const MAX_CHAT_LENGTH: usize = 256;
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum ControlCommand {
+    ListSessions,
+    TabList,
+    SendChat { text: String },
+    Notify { text: String },
+    InjectChat { message: String },
+    Kick { reason: Option<String> },
+}
+
+#[derive(Deserialize, Debug)]
+struct ControlRequest {
+    id: Option<serde_json::Value>,
+    connection_id: Option<String>,
+    username: Option<String>,
+    #[serde(flatten)]
+    command: ControlCommand,
+}
+
+#[derive(Serialize, Debug)]
+struct ControlResponse {
+    id: Option<serde_json::Value>,
+    success: bool,
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sessions: Option<Vec<SessionInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tab_list: Option<TabList>,
+}
+
+impl ControlResponse {
+    fn ok(id: Option<serde_json::Value>) -> Self {
+        Self {
+            id,
+            success: true,
+            message: None,
+            sessions: None,
+            tab_list: None,
+        }
+    }
+
+    fn error(id: Option<serde_json::Value>, message: &str) -> Self {
+        Self {
+            id,
+            success: false,
+            message: Some(message.to_string()),
+            sessions: None,
+            tab_list: None,
+        }
+    }
+}
+
+// After a login is authenticated its websocket stays open, so the ws server can send commands over the same link.
+// Commands without a connection ID or username are for the session that logged in with the link.
+// This runs until the ws server closes the link or the session is closed.
+pub async fn serve(mut link: ControlLink, sessions: Sessions, connection_id: String) {
+    log::debug!("Auth link kept open for control messages.");
+    loop {
+        let message =
+            match timeout(Duration::from_millis(SHUTDOWN_CHECK_TIMEOUT), link.next()).await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(_) => {
+                    if sessions.get(&connection_id).is_none() {
+                        let _ = link.close(None).await;
+                        break;
+                    }
+                    continue;
+                }
+            };
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(e) => {
+                log::error!("Control link error: {}", e);
+                break;
+            }
+        };
+        log::debug!("Control message: {}", text);
+        let response = handle_message(&sessions, &connection_id, &text);
+        if let Err(e) = link
+            .send(Message::text(serde_json::to_string(&response).unwrap()))
+            .await
+        {
+            log::error!("Could not send control response: {}", e);
+            break;
+        }
+    }
+    log::debug!("Control link closed.");
+}
+
+fn handle_message(sessions: &Sessions, own_connection_id: &str, text: &str) -> ControlResponse {
+    let request: ControlRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return ControlResponse::error(None, &format!("Invalid request: {}", e)),
+    };
+    let id = request.id;
+
+    if let ControlCommand::ListSessions = request.command {
+        return ControlResponse {
+            sessions: Some(sessions.list()),
+            ..ControlResponse::ok(id)
+        };
+    }
+
+    let connection_id = match (&request.connection_id, &request.username) {
+        (None, None) => Some(own_connection_id),
+        (connection_id, _) => connection_id.as_deref(),
+    };
+    let session = match sessions.find(connection_id, request.username.as_deref()) {
+        Some(session) => session,
+        None => return ControlResponse::error(id, "Session not found"),
+    };
+    let state = session.state();
+
+    if let ControlCommand::TabList = request.command {
+        return ControlResponse {
+            tab_list: Some(session.tab_list.lock().clone()),
+            ..ControlResponse::ok(id)
+        };
+    }
+
+    let result = match request.command {
+        ControlCommand::ListSessions | ControlCommand::TabList => unreachable!(),
+        ControlCommand::SendChat { text } => {
+            if state != State::Play {
+                return ControlResponse::error(id, "Player is not in game");
+            }
+            ChatMessageClientbound {
+                data: TextComponent::text(text),
+                position: ChatMessagePosition::SystemMessage,
+                sender: Uuid::from(0),
+            }
+            .encode_packet()
+            .and_then(|packet| session.send_packet(packet, Direction::Clientbound))
+        }
+        ControlCommand::Notify { text } => {
+            if state != State::Play {
+                return ControlResponse::error(id, "Player is not in game");
+            }
+            generate_message_packet(&text)
+                .and_then(|packet| session.send_packet(packet, Direction::Clientbound))
+        }
+        ControlCommand::InjectChat { message } => {
+            if state != State::Play {
+                return ControlResponse::error(id, "Player is not in game");
+            }
+            if message.chars().count() > MAX_CHAT_LENGTH {
+                return ControlResponse::error(id, "Message is too long");
+            }
+            ChatMessageServerbound { message }
+                .encode_packet()
+                .and_then(|packet| session.send_packet(packet, Direction::Serverbound))
+        }
+        ControlCommand::Kick { reason } => {
+            let result = if state == State::Play {
+                DisconnectPlay {
+                    reason: TextComponent::text(
+                        reason.unwrap_or_else(|| "Kicked by proxy".to_string()),
+                    ),
+                }
+                .encode_packet()
+                .and_then(|packet| session.send_packet(packet, Direction::Clientbound))
+            } else {
+                Ok(())
+            };
+            session.close();
+            result
+        }
+    };
+
+    match result {
+        Ok(()) => ControlResponse::ok(id),
+        Err(()) => ControlResponse::error(id, "Could not send packet"),
+    }
+}
@@ -0,0 +1,234 @@
+// Keeps track of the entities the client knows about, so plugins can look them up.
+// It is fed by the clientbound spawn, movement, metadata and destroy packets, and cleared on Respawn.
+use crate::{
+    functions::Fid,
+    packet::Packet,
+    plugin::{first_reply, EventHandler},
+    types::{Metadata, MetadataValue, Uuid},
+    Direction,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum EntityType {
+    // The id in the minecraft:entity_type registry, sent by SpawnEntity and SpawnLivingEntity.
+    Registry(i32),
+    Player,
+    ExperienceOrb,
+    Painting,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entity {
+    pub r#type: EntityType,
+    pub uuid: Option<Uuid>,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    // In 1/256 of a full turn, like on the wire.
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+    // In blocks per tick.
+    pub velocity: (f64, f64, f64),
+    pub passengers: Vec<i32>,
+    // Only the latest value of every index.
+    pub metadata: BTreeMap<u8, MetadataValue>,
+}
+
+impl Entity {
+    pub fn new(r#type: EntityType, uuid: Option<Uuid>, x: f64, y: f64, z: f64) -> Self {
+        Self {
+            r#type,
+            uuid,
+            x,
+            y,
+            z,
+            yaw: 0,
+            pitch: 0,
+            on_ground: false,
+            velocity: (0.0, 0.0, 0.0),
+            passengers: vec![],
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    pub fn distance_to(&self, x: f64, y: f64, z: f64) -> f64 {
+        ((self.x - x).powi(2) + (self.y - y).powi(2) + (self.z - z).powi(2)).sqrt()
+    }
+}
+
+// The player itself isn't in here, the client never gets a spawn packet for it.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Entities {
+    entities: HashMap<i32, Entity>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, entity_id: i32) -> Option<&Entity> {
+        self.entities.get(&entity_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&i32, &Entity)> {
+        self.entities.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    // The entities within `distance` blocks of the point, the closest first.
+    pub fn near(&self, x: f64, y: f64, z: f64, distance: f64) -> Vec<(i32, &Entity)> {
+        let mut near: Vec<(i32, &Entity)> = self
+            .entities
+            .iter()
+            .filter(|(_, entity)| entity.distance_to(x, y, z) <= distance)
+            .map(|(id, entity)| (*id, entity))
+            .collect();
+        near.sort_by(|(_, a), (_, b)| {
+            a.distance_to(x, y, z)
+                .partial_cmp(&b.distance_to(x, y, z))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        near
+    }
+
+    // A spawn with an id that is already used replaces the old entity, like it does in the client.
+    pub fn spawn(&mut self, entity_id: i32, entity: Entity) {
+        self.entities.insert(entity_id, entity);
+    }
+
+    pub fn remove(&mut self, entity_id: i32) -> Option<Entity> {
+        self.entities.remove(&entity_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    // The deltas of Entity Position are in 1/4096 of a block.
+    pub fn move_by(&mut self, entity_id: i32, delta: (i16, i16, i16), on_ground: bool) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.x += delta.0 as f64 / 4096.0;
+            entity.y += delta.1 as f64 / 4096.0;
+            entity.z += delta.2 as f64 / 4096.0;
+            entity.on_ground = on_ground;
+        }
+    }
+
+    pub fn rotate(&mut self, entity_id: i32, yaw: u8, pitch: u8) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.yaw = yaw;
+            entity.pitch = pitch;
+        }
+    }
+
+    pub fn teleport(&mut self, entity_id: i32, x: f64, y: f64, z: f64, on_ground: bool) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.x = x;
+            entity.y = y;
+            entity.z = z;
+            entity.on_ground = on_ground;
+        }
+    }
+
+    // The velocity on the wire is in 1/8000 of a block per tick.
+    pub fn set_velocity(&mut self, entity_id: i32, velocity: (i16, i16, i16)) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.velocity = (
+                velocity.0 as f64 / 8000.0,
+                velocity.1 as f64 / 8000.0,
+                velocity.2 as f64 / 8000.0,
+            );
+        }
+    }
+
+    pub fn set_passengers(&mut self, entity_id: i32, passengers: &[i32]) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.passengers = passengers.to_vec();
+        }
+    }
+
+    pub fn update_metadata(&mut self, entity_id: i32, metadata: &Metadata) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            for entry in &metadata.entries {
+                entity.metadata.insert(entry.index, entry.value.clone());
+            }
+        }
+    }
+}
+
+// Shows the changed entities to the plugins, the tracker is already updated then. Destroyed entities aren't
+// in the tracker anymore.
+pub fn notify_plugins(
+    fid: Fid,
+    entity_ids: &[i32],
+    entities: &Entities,
+    plugins: &mut [Box<dyn EventHandler + Send>],
+) -> Vec<(Packet, Direction)> {
+    first_reply(fid, plugins, |plugin| {
+        entity_ids
+            .iter()
+            .find_map(|entity_id| plugin.on_entity_update(*entity_id, entities))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MetadataEntry;
+
+    #[test]
+    fn test_entity_updates() {
+        let mut entities = Entities::new();
+        entities.spawn(
+            7,
+            Entity::new(EntityType::Registry(11), None, 10.0, 64.0, 0.0),
+        );
+        entities.spawn(8, Entity::new(EntityType::Player, None, 100.0, 64.0, 0.0));
+
+        entities.move_by(7, (4096, -2048, 512), true);
+        entities.set_velocity(7, (8000, 0, -4000));
+        entities.set_passengers(7, &[8]);
+        entities.update_metadata(
+            7,
+            &Metadata {
+                entries: vec![MetadataEntry {
+                    index: 0,
+                    value: MetadataValue::Byte(0x20),
+                }],
+            },
+        );
+        // Updates for unknown entities are ignored.
+        entities.move_by(9, (1, 1, 1), false);
+
+        let cow = entities.get(7).unwrap();
+        assert_eq!((cow.x, cow.y, cow.z), (11.0, 63.5, 0.125));
+        assert!(cow.on_ground);
+        assert_eq!(cow.velocity, (1.0, 0.0, -0.5));
+        assert_eq!(cow.passengers, vec![8]);
+        assert_eq!(cow.metadata.get(&0), Some(&MetadataValue::Byte(0x20)));
+
+        let near: Vec<i32> = entities
+            .near(0.0, 64.0, 0.0, 200.0)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(near, vec![7, 8]);
+        assert_eq!(entities.near(0.0, 64.0, 0.0, 20.0).len(), 1);
+
+        entities.remove(8);
+        assert!(entities.get(8).is_none());
+        entities.clear();
+        assert!(entities.is_empty());
+    }
+}
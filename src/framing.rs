@@ -0,0 +1,416 @@
+use std::fmt;
+
+use bytes::{Bytes, BytesMut};
+
+use miniz_oxide::inflate::{
+    core::{
+        decompress,
+        inflate_flags::{TINFL_FLAG_PARSE_ZLIB_HEADER, TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF},
+        DecompressorOxide,
+    },
+    TINFLStatus,
+};
+
+use crate::{
+    parse_error::{ParseContext, ParseError, ParseResult},
+    raw_packet::RawPacket,
+    Direction,
+};
+
+// The length of a frame is a varint of at most 3 bytes, so it can't be longer than this (same as vanilla).
+pub const MAX_FRAME_LENGTH: usize = 2097151;
+const MAX_FRAME_LENGTH_BYTES: usize = 3;
+
+// The largest uncompressed packets the vanilla server and client accept.
+pub const MAX_SERVERBOUND_DATA_LENGTH: usize = 2 * 1024 * 1024;
+pub const MAX_CLIENTBOUND_DATA_LENGTH: usize = 8 * 1024 * 1024;
+
+// Everything that can be wrong with a frame, the connection gets closed when one of these happens.
+#[derive(Debug, PartialEq)]
+pub enum FramingError {
+    LengthTooLong,
+    InvalidLength(i32),
+    InvalidDataLength(i32),
+    DataLengthTooBig {
+        data_length: usize,
+        max: usize,
+    },
+    DataLengthBelowThreshold {
+        data_length: usize,
+        threshold: u32,
+    },
+    DecompressFailed(TINFLStatus),
+    SizeMismatch {
+        declared: usize,
+        actual: Option<usize>,
+    },
+    Parse(ParseError),
+}
+
+impl fmt::Display for FramingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramingError::LengthTooLong => write!(f, "frame length is more than 3 bytes"),
+            FramingError::InvalidLength(length) => write!(f, "invalid frame length {}", length),
+            FramingError::InvalidDataLength(data_length) => {
+                write!(f, "invalid data length {}", data_length)
+            }
+            FramingError::DataLengthTooBig { data_length, max } => write!(
+                f,
+                "data length {} is more than the maximum of {}",
+                data_length, max
+            ),
+            FramingError::DataLengthBelowThreshold {
+                data_length,
+                threshold,
+            } => write!(
+                f,
+                "data length {} is below the compression threshold of {}",
+                data_length, threshold
+            ),
+            FramingError::DecompressFailed(status) => {
+                write!(f, "could not decompress: {:?}", status)
+            }
+            FramingError::SizeMismatch {
+                declared,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "declared data length {} but it decompressed to {} bytes",
+                declared, actual
+            ),
+            FramingError::SizeMismatch {
+                declared,
+                actual: None,
+            } => write!(
+                f,
+                "declared data length {} but it decompressed to more than that",
+                declared
+            ),
+            FramingError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ParseError> for FramingError {
+    fn from(e: ParseError) -> Self {
+        FramingError::Parse(e)
+    }
+}
+
+// A whole frame as it was received, with the length in front of it.
+// The data is shared with the receive buffer, so a frame that isn't changed can be sent on without copying it.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    data: Bytes,
+    length_bytes: usize,
+}
+
+impl Frame {
+    // The frame without the length in front of it.
+    pub fn body(&self) -> Bytes {
+        self.data.slice(self.length_bytes..)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.data
+    }
+}
+
+// Takes the next frame out of the buffer, this doesn't copy it.
+// None is returned if the whole frame hasn't been received yet, the buffer is left as is then.
+pub fn read_frame(buffer: &mut RawPacket) -> Result<Option<Frame>, FramingError> {
+    let (length, length_bytes) = match peek_frame_length(buffer.get_slice())? {
+        Some(length) => length,
+        None => return Ok(None),
+    };
+    if buffer.len() < length_bytes + length {
+        return Ok(None);
+    }
+    Ok(Some(Frame {
+        data: buffer.read(length_bytes + length)?.freeze(),
+        length_bytes,
+    }))
+}
+
+// Splits the packet ID off the (uncompressed) data of a packet.
+pub fn split_packet_id(data: Bytes) -> ParseResult<(i32, Bytes)> {
+    split_varint(data).field("packet_id")
+}
+
+// Only the varint is put in a RawPacket, the rest of the data is sliced off without copying it.
+fn split_varint(data: Bytes) -> ParseResult<(i32, Bytes)> {
+    let mut varint = RawPacket::from(data.slice(..data.len().min(5)));
+    let value = varint.decode_varint()?;
+    Ok((value, data.slice(varint.position()..)))
+}
+
+// Reads the length of a frame, and how many bytes that length took up.
+// This doesn't take anything out of the data, so nothing has to be put back if the frame isn't complete.
+fn peek_frame_length(data: &[u8]) -> Result<Option<(usize, usize)>, FramingError> {
+    let mut result: i32 = 0;
+    for (i, byte) in data.iter().take(MAX_FRAME_LENGTH_BYTES).enumerate() {
+        result |= ((byte & 0x7F) as i32) << (7 * i);
+        if byte & 0x80 == 0 {
+            // A 3 byte varint can't be negative, but this is checked in case the limit ever changes.
+            if result < 0 || result as usize > MAX_FRAME_LENGTH {
+                return Err(FramingError::InvalidLength(result));
+            }
+            return Ok(Some((result as usize, i + 1)));
+        }
+    }
+    if data.len() >= MAX_FRAME_LENGTH_BYTES {
+        Err(FramingError::LengthTooLong)
+    } else {
+        Ok(None)
+    }
+}
+
+// Takes the data length off a frame when compression is on, and decompresses it if it's compressed.
+// The declared data length has to be within the limits, and has to match what it actually decompresses to.
+pub fn decompress_frame(
+    frame: Bytes,
+    threshold: u32,
+    direction: Direction,
+) -> Result<Bytes, FramingError> {
+    if threshold == 0 {
+        return Ok(frame);
+    }
+
+    let (data_length, frame) = split_varint(frame).field("data_length")?;
+    if data_length == 0 {
+        // The packet is not compressed.
+        return Ok(frame);
+    }
+    if data_length < 0 {
+        return Err(FramingError::InvalidDataLength(data_length));
+    }
+
+    let data_length = data_length as usize;
+    let max = match direction {
+        Direction::Serverbound => MAX_SERVERBOUND_DATA_LENGTH,
+        Direction::Clientbound => MAX_CLIENTBOUND_DATA_LENGTH,
+    };
+    if data_length > max {
+        return Err(FramingError::DataLengthTooBig { data_length, max });
+    }
+    if data_length < threshold as usize {
+        return Err(FramingError::DataLengthBelowThreshold {
+            data_length,
+            threshold,
+        });
+    }
+
+    // It is decompressed into a buffer of exactly the declared length, so it can never take more memory than that.
+    // (decompress_to_vec_zlib_with_limit can't be used, it fails on some packets that do fit in the limit.)
+    let mut data = BytesMut::zeroed(data_length);
+    let (status, _, decompressed_length) = decompress(
+        &mut Box::<DecompressorOxide>::default(),
+        &frame,
+        &mut data,
+        0,
+        TINFL_FLAG_PARSE_ZLIB_HEADER | TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+    );
+    match status {
+        TINFLStatus::Done if decompressed_length == data_length => Ok(data.freeze()),
+        TINFLStatus::Done => Err(FramingError::SizeMismatch {
+            declared: data_length,
+            actual: Some(decompressed_length),
+        }),
+        TINFLStatus::HasMoreOutput => Err(FramingError::SizeMismatch {
+            declared: data_length,
+            actual: None,
+        }),
+        status => Err(FramingError::DecompressFailed(status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+
+    use super::*;
+
+    fn compressed_frame(data_length: i32, data: &[u8]) -> Bytes {
+        let mut frame = RawPacket::new();
+        frame.encode_varint(data_length);
+        frame.push_vec(compress_to_vec_zlib(data, 6));
+        frame.into_bytes()
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let mut buffer = RawPacket::from(vec![0x02, 0x00, 0x01, 0x01, 0x00]);
+        assert_eq!(
+            read_frame(&mut buffer).unwrap().unwrap().body(),
+            vec![0x00, 0x01]
+        );
+        assert_eq!(
+            read_frame(&mut buffer).unwrap().unwrap().into_bytes(),
+            vec![0x01, 0x00]
+        );
+        assert!(read_frame(&mut buffer).unwrap().is_none());
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_frame_is_not_copied() {
+        let data = vec![0x03, 0x05, 0x01, 0x02, 0x00];
+        let start = data.as_ptr();
+        let mut buffer = RawPacket::from(data);
+        let frame = read_frame(&mut buffer).unwrap().unwrap();
+        assert_eq!(frame.body().as_ptr(), start.wrapping_add(1));
+
+        let (packet_id, packet_data) = split_packet_id(frame.body()).unwrap();
+        assert_eq!(packet_id, 5);
+        assert_eq!(packet_data.as_ptr(), start.wrapping_add(2));
+        assert_eq!(frame.into_bytes(), vec![0x03, 0x05, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_incomplete_frame() {
+        // The length isn't complete yet.
+        let mut buffer = RawPacket::from(vec![0x80, 0x80]);
+        assert!(read_frame(&mut buffer).unwrap().is_none());
+        assert_eq!(buffer.len(), 2);
+
+        // The data isn't complete yet.
+        let mut buffer = RawPacket::from(vec![0x80, 0x01, 0x00, 0x00]);
+        assert!(read_frame(&mut buffer).unwrap().is_none());
+        assert_eq!(buffer.get_vec(), vec![0x80, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_frame_length_limit() {
+        // The biggest length that fits in 3 bytes is allowed, even before the data is there.
+        let mut buffer = RawPacket::from(vec![0xff, 0xff, 0x7f]);
+        assert!(read_frame(&mut buffer).unwrap().is_none());
+
+        // A length of 4 bytes is not.
+        let mut buffer = RawPacket::from(vec![0x80, 0x80, 0x80, 0x01]);
+        assert_eq!(
+            read_frame(&mut buffer).unwrap_err(),
+            FramingError::LengthTooLong
+        );
+
+        // This also fails when the 4th byte hasn't been received yet.
+        let mut buffer = RawPacket::from(vec![0xff, 0xff, 0xff]);
+        assert_eq!(
+            read_frame(&mut buffer).unwrap_err(),
+            FramingError::LengthTooLong
+        );
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let frame = Bytes::from(vec![0x00, 0x01, 0x02]);
+        assert_eq!(
+            decompress_frame(frame.clone(), 0, Direction::Clientbound).unwrap(),
+            vec![0x00, 0x01, 0x02]
+        );
+        // With compression on, a data length of 0 means the data is not compressed.
+        assert_eq!(
+            decompress_frame(frame, 256, Direction::Clientbound).unwrap(),
+            vec![0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_compressed() {
+        let data = vec![0x21; 300];
+        let frame = compressed_frame(300, &data);
+        assert_eq!(
+            decompress_frame(frame, 256, Direction::Serverbound).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_data_length_mismatch() {
+        let data = vec![0x21; 300];
+        assert_eq!(
+            decompress_frame(compressed_frame(400, &data), 256, Direction::Serverbound)
+                .unwrap_err(),
+            FramingError::SizeMismatch {
+                declared: 400,
+                actual: Some(300)
+            }
+        );
+        assert_eq!(
+            decompress_frame(compressed_frame(280, &data), 256, Direction::Serverbound)
+                .unwrap_err(),
+            FramingError::SizeMismatch {
+                declared: 280,
+                actual: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_data_length_limits() {
+        // A zip bomb, a few KB that inflates to more than both limits.
+        // It is only compressed once, because that is slow in debug builds.
+        let bomb_length = MAX_CLIENTBOUND_DATA_LENGTH + 1;
+        let bomb = compress_to_vec_zlib(&vec![0; bomb_length], 1);
+        let bomb_frame = |data_length: usize| {
+            let mut frame = RawPacket::new();
+            frame.encode_varint(data_length as i32);
+            frame.push_slice(&bomb);
+            frame.into_bytes()
+        };
+
+        for (direction, max) in [
+            (Direction::Serverbound, MAX_SERVERBOUND_DATA_LENGTH),
+            (Direction::Clientbound, MAX_CLIENTBOUND_DATA_LENGTH),
+        ] {
+            assert_eq!(
+                decompress_frame(bomb_frame(bomb_length), 256, direction).unwrap_err(),
+                FramingError::DataLengthTooBig {
+                    data_length: bomb_length,
+                    max
+                }
+            );
+            // Lying about the length doesn't work either.
+            assert_eq!(
+                decompress_frame(bomb_frame(max), 256, direction).unwrap_err(),
+                FramingError::SizeMismatch {
+                    declared: max,
+                    actual: None
+                }
+            );
+        }
+
+        assert_eq!(
+            decompress_frame(compressed_frame(-1, &[0; 300]), 256, Direction::Clientbound)
+                .unwrap_err(),
+            FramingError::InvalidDataLength(-1)
+        );
+        assert_eq!(
+            decompress_frame(compressed_frame(10, &[0; 10]), 256, Direction::Clientbound)
+                .unwrap_err(),
+            FramingError::DataLengthBelowThreshold {
+                data_length: 10,
+                threshold: 256
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_zlib() {
+        let mut frame = RawPacket::new();
+        frame.encode_varint(300);
+        frame.push_vec(vec![0x12, 0x34, 0x56]);
+        assert!(matches!(
+            decompress_frame(frame.into_bytes(), 256, Direction::Clientbound),
+            Err(FramingError::DecompressFailed(_))
+        ));
+    }
+}
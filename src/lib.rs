@@ -0,0 +1,45 @@
+// Everything the proxy uses is in this library, main.rs only has the connection handling.
+// This way the benchmarks can use the same code.
+// Errors that don't need more information are Result<_, ()> everywhere, so that is allowed here as well.
+#![allow(clippy::result_unit_err)]
+
+pub mod admin;
+pub mod blocks;
+pub mod chat;
+pub mod chunk_cache;
+pub mod chunk_section;
+pub mod cipher;
+pub mod circuit_breaker;
+pub mod codec;
+pub mod compression;
+pub mod conf;
+pub mod control;
+pub mod entities;
+pub mod framing;
+pub mod log_output;
+pub mod logging;
+pub mod metrics;
+pub mod packet;
+pub mod parsable;
+pub mod parse_error;
+pub mod plugin;
+pub mod plugins;
+pub mod protocol;
+pub mod raw_packet;
+pub mod session_actor;
+pub mod sessions;
+pub mod tab_list;
+pub mod types;
+pub mod utils;
+pub mod wire;
+
+pub use crate::{
+    plugin::EventHandler,
+    raw_packet::RawPacket,
+    types::{Ciphers, Direction, SharedState, State},
+};
+
+pub use crate::protocol::v754 as functions;
+
+// How often (in ms) the tasks of a connection check if it was closed while they're waiting.
+pub const SHUTDOWN_CHECK_TIMEOUT: u64 = 100;
@@ -0,0 +1,231 @@
+use std::{
+    fmt::{self, Write as _},
+    time::Instant,
+};
+
+use colored::*;
+use env_logger::Logger;
+use log::Log;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Record},
+    Event, Id, Level, Subscriber,
+};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    fmt::format::FmtSpan,
+    layer::{Context, SubscriberExt},
+    registry::{LookupSpan, SpanRef},
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+// Sets up the output for both the `log` and `tracing` macros.
+// Everything is shown with the env_logger format, unless JSON output is turned on.
+pub fn init(logger: Logger, json: bool) {
+    let (pretty, json) = if json {
+        (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_span_events(FmtSpan::CLOSE),
+            ),
+        )
+    } else {
+        (Some(EnvLoggerLayer { logger }), None)
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(pretty)
+        .with(json)
+        .init();
+}
+
+// A tracing layer that passes every event on to env_logger, with the fields of the spans it's in put in front of it.
+// When a span closes it logs how long it was open, so the phases of a connection can be timed from the logs.
+pub struct EnvLoggerLayer {
+    logger: Logger,
+}
+
+// The fields of a span, they are kept in the span so they don't have to be formatted for every event.
+struct SpanFields(Vec<(&'static str, String)>);
+
+// When the span was created.
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for EnvLoggerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = SpanFields(Vec::new());
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            extensions.insert(fields);
+            extensions.insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let elapsed = match span.extensions().get::<SpanStart>() {
+            Some(start) => start.0.elapsed(),
+            None => return,
+        };
+        let metadata = span.metadata();
+        let prefix = span_prefix(span.scope().from_root());
+        self.log(
+            metadata,
+            to_log_level(metadata.level()),
+            format_args!("{}closed after {:?}", prefix.dimmed(), elapsed),
+        );
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // Events that came from the log macros have their original metadata put back.
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let level = to_log_level(metadata.level());
+        if !self.enabled(metadata, level) {
+            return;
+        }
+
+        let prefix = match ctx.event_scope(event) {
+            Some(scope) => span_prefix(scope.from_root()),
+            None => String::new(),
+        };
+
+        let mut message = EventMessage::default();
+        event.record(&mut message);
+
+        self.log(
+            metadata,
+            level,
+            format_args!("{}{}", prefix.dimmed(), message.0),
+        );
+    }
+}
+
+impl EnvLoggerLayer {
+    fn enabled(&self, metadata: &tracing::Metadata<'_>, level: log::Level) -> bool {
+        self.logger.enabled(
+            &log::Metadata::builder()
+                .level(level)
+                .target(metadata.target())
+                .build(),
+        )
+    }
+
+    fn log(&self, metadata: &tracing::Metadata<'_>, level: log::Level, args: fmt::Arguments<'_>) {
+        if !self.enabled(metadata, level) {
+            return;
+        }
+        self.logger.log(
+            &log::Record::builder()
+                .level(level)
+                .target(metadata.target())
+                .args(args)
+                .module_path(metadata.module_path())
+                .file(metadata.file())
+                .line(metadata.line())
+                .build(),
+        );
+    }
+}
+
+fn to_log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+// The names and fields of the spans, from the outermost one in, like `connection{connection_id=1}:login: `.
+fn span_prefix<'a, S, I>(spans: I) -> String
+where
+    S: LookupSpan<'a> + 'a,
+    I: Iterator<Item = SpanRef<'a, S>>,
+{
+    let mut prefix = String::new();
+    for span in spans {
+        let mut name = span.name().to_string();
+        if let Some(fields) = span.extensions().get::<SpanFields>() {
+            name.push_str(&fields.to_string());
+        }
+        write!(prefix, "{}:", name).unwrap();
+    }
+    if !prefix.is_empty() {
+        prefix.push(' ');
+    }
+    prefix
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_value(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_value(field, format!("{:?}", value));
+    }
+}
+
+impl SpanFields {
+    fn record_value(&mut self, field: &Field, value: String) {
+        match self.0.iter_mut().find(|(name, _)| *name == field.name()) {
+            Some((_, old_value)) => *old_value = value,
+            None => self.0.push((field.name(), value)),
+        }
+    }
+}
+
+impl fmt::Display for SpanFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let fields: Vec<String> = self
+            .0
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "{{{}}}", fields.join(" "))
+    }
+}
+
+// The message of an event, any other fields are put after it.
+#[derive(Default)]
+struct EventMessage(String);
+
+impl Visit for EventMessage {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        // These are added by tracing-log, they are already in the metadata.
+        if field.name().starts_with("log.") {
+            return;
+        }
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            write!(self.0, " {}={:?}", field.name(), value).unwrap();
+        }
+    }
+}
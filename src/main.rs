@@ -1,7 +1,6 @@
-#![allow(where_clauses_object_safety)]
-
 use std::{
     io::Write,
+    panic::{self, AssertUnwindSafe},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -15,63 +14,58 @@ use tokio::{
         tcp::{OwnedReadHalf, OwnedWriteHalf},
         TcpListener, TcpStream,
     },
+    sync::{mpsc, oneshot},
     time::timeout,
 };
 
+use bytes::BytesMut;
 use colored::*;
 use env_logger::Builder;
 use log::LevelFilter;
-use miniz_oxide::inflate::decompress_to_vec_zlib;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
+use tracing::{field::Empty, Instrument};
 use trust_dns_resolver::{config::*, TokioAsyncResolver};
 
-use crate::{
-    logging::LogQueue,
+use mc_proxy_v2::{
+    admin, blocks,
+    circuit_breaker::{panic_message, CircuitBreaker},
+    compression::Compressor,
+    conf::{self, SharedConfig},
+    framing::{decompress_frame, read_frame, split_packet_id, FramingError},
+    functions, log_output,
+    logging::{self, LogQueue},
+    metrics,
     parsable::Parsable,
+    parse_error::{ParseContext, ParseResult},
+    plugin::Plugins,
+    plugins,
     raw_packet::RawPacket,
+    session_actor::{SessionActor, SessionEvent},
+    sessions::{send_data, PacketCounters, SessionHandle, Sessions},
     types::{DataQueue, Queues},
+    utils, Ciphers, Direction, SharedState, SHUTDOWN_CHECK_TIMEOUT,
 };
 
-pub use crate::{
-    plugin::EventHandler,
-    types::{Ciphers, Direction, SharedState, State},
-};
-
-mod cipher;
-mod conf;
-mod logging;
-mod packet;
-mod parsable;
-mod plugin;
-mod plugins;
-mod protocol;
-mod raw_packet;
-mod types;
-mod utils;
-
-pub use crate::protocol::v754 as functions;
-
-const SHUTDOWN_CHECK_TIMEOUT: u64 = 100;
-
-// This function puts all received packets (in chunks of 4096 bytes) in the receiving queue.
+// This function puts all received packets (in chunks of up to 4096 bytes) in the receiving queue.
 async fn receiver(
     mut rx: OwnedReadHalf,
     queue: Arc<DataQueue>,
     socket_name: &str,
     is_closed: Arc<AtomicBool>,
 ) {
-    // This buffer is continually reused
-    let mut buf = [0; 4096];
+    // The received data is split off this buffer, so it isn't copied when it's put in the queue.
+    let mut buf = BytesMut::with_capacity(4096);
     loop {
+        buf.reserve(4096);
         // The timeout is there to close the socket and thread if the connection is closed
         let n = match timeout(
             Duration::from_millis(SHUTDOWN_CHECK_TIMEOUT),
-            rx.read(&mut buf),
+            rx.read_buf(&mut buf),
         )
         .await
         {
             Ok(v) => match v {
-                Ok(n) if n == 0 => {
+                Ok(0) => {
                     log::warn!("Socket closed: {}", socket_name);
                     is_closed.store(true, Ordering::Release);
                     return;
@@ -91,7 +85,7 @@ async fn receiver(
                 }
             }
         };
-        queue.push(buf[0..n].to_vec());
+        queue.push(buf.split_to(n).freeze());
     }
 }
 
@@ -121,21 +115,27 @@ async fn sender(mut tx: OwnedWriteHalf, queue: Arc<DataQueue>, is_closed: Arc<At
 }
 
 async fn parser(
-    queues: Queues,
-    shared_status: Arc<Mutex<SharedState>>,
-    ciphers: Arc<Mutex<Ciphers>>,
+    session: SessionHandle,
     direction: Direction,
-    is_closed: Arc<AtomicBool>,
-    plugins: Arc<Mutex<Vec<Box<dyn EventHandler + Send>>>>,
     log_queue: Arc<LogQueue>,
-) -> Result<(), ()> {
+    shared_config: SharedConfig,
+) -> ParseResult<()> {
+    let SessionHandle {
+        ciphers,
+        queues,
+        is_closed,
+        events,
+        plugin_settings,
+        counters,
+        ..
+    } = session.clone();
     let mut unprocessed_data = RawPacket::new();
     // functions is a list of all the packets that can be parsed
     let functions = functions::get_functions();
-    let config = conf::get_config();
+    let mut breaker = CircuitBreaker::new(direction);
 
     // If this loop ever breaks, the thread is closed.
-    loop {
+    'receiving: loop {
         // This is the only place this function can hang, so it has a timeout to fix that.
         let new_data = match timeout(
             Duration::from_millis(SHUTDOWN_CHECK_TIMEOUT),
@@ -157,74 +157,59 @@ async fn parser(
         };
 
         // Data from the server (clientbound) needs to be decrypted, that is done here.
-        unprocessed_data.push_vec({
-            if direction == Direction::Clientbound {
-                ciphers.lock().sp_cipher.decrypt(new_data)
-            } else {
-                new_data
-            }
-        });
+        let mut new_data = BytesMut::from(new_data);
+        if direction == Direction::Clientbound {
+            ciphers.sp_cipher.lock().decrypt(&mut new_data);
+        }
+        unprocessed_data.push_bytes(new_data);
 
         // Sometimes multiple packets will be sent at once, so this keeps running until all the packets are dealth with.
-        while unprocessed_data.len() > 0 {
-            // A backup of the packet is made, so if the packet is incomplete it will be reset to that.
-            let original_data = unprocessed_data.get_vec();
-
-            let packet_length = match unprocessed_data.decode_varint() {
-                Ok(packet_length) => packet_length as usize,
-                Err(_) => {
-                    unprocessed_data.set(original_data);
-                    break;
+        while !unprocessed_data.is_empty() {
+            // A whole frame is taken out, if it isn't complete yet it waits for more data.
+            // Frames that are too big close the connection, so the proxy can't be made to use unlimited memory.
+            let frame = match read_frame(&mut unprocessed_data) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) => {
+                    disconnect_invalid(&session, direction, &e);
+                    break 'receiving;
                 }
             };
 
-            // If there is not enough data in the unprocessed_data variable, it will restore the backup and wait for more data.
-            if unprocessed_data.len() < packet_length {
-                unprocessed_data.set(original_data);
-                break;
-            }
-
-            // This packet will always have the exact amount of data to contain the packet, no more no less.
-            let mut packet =
-                raw_packet::RawPacket::from(unprocessed_data.read(packet_length).unwrap());
-
-            // A copy of the packet is made, this is to not have to recreate it if it doesn't get parsed or edited.
-            let mut original_packet = RawPacket::new();
-            original_packet.encode_varint(packet_length as i32);
-            original_packet.push_vec(packet.get_vec());
-
-            // Uncompress if needed
-            if shared_status.lock().compress > 0 {
-                let data_length = packet.decode_varint()?;
-                if data_length > 0 {
-                    let decompressed_packet = match decompress_to_vec_zlib(&packet.get_vec()) {
-                        Ok(decompressed_packet) => decompressed_packet,
-                        Err(why) => {
-                            log::error!("Decompress error: {:?} {}", why, direction);
-                            break;
-                        }
-                    };
-                    packet.set(decompressed_packet);
+            // Uncompress if needed, the frame itself is kept so it doesn't have to be recreated if it doesn't get edited.
+            let compress = session.compress();
+            let decoded = decompress_frame(frame.body(), compress, direction)
+                .and_then(|data| split_packet_id(data).map_err(FramingError::from));
+            let (packet_id, packet) = match decoded {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    disconnect_invalid(&session, direction, &e);
+                    break 'receiving;
                 }
-            }
-
-            let packet_id = packet.decode_varint()?;
+            };
 
             // Get the Fid of the current packet, if it doesn't get parsed set it to Unparsable
-            let func_id =
-                match functions.get_name(&direction, &shared_status.lock().state, &packet_id) {
-                    Some(func_name) => func_name,
-                    None => &functions::Fid::Unparsable,
-                };
+            let func_id = match functions.get_name(&direction, &session.state(), &packet_id) {
+                Some(func_name) => func_name,
+                None => &functions::Fid::Unparsable,
+            };
 
-            let mut out_data = original_packet.get_vec();
-            let mut to_direction = direction;
+            counters.lock().record(direction, func_id, frame.len());
+            metrics::record_packet(direction, func_id, frame.len());
 
-            if func_id == &functions::Fid::Unparsable {
-                // Encrypt the data if it wont get parsed. Othwerise, ecryption is done later.
-                if direction == Direction::Serverbound {
-                    out_data = ciphers.lock().ps_cipher.encrypt(out_data)
-                }
+            // The config is gotten for every packet, so reloading it also changes running connections.
+            let config = shared_config.read().clone();
+
+            let logged = config.logging_packets.contains(&func_id.to_string())
+                || config.logging_packets.contains(&"*".to_string());
+            // Packets nobody looks at aren't parsed, that saves a lot of time for big packets like ChunkData.
+            let wanted = logged
+                || functions.always_parse(func_id)
+                || plugin_settings.lock().is_wanted(func_id);
+
+            if func_id == &functions::Fid::Unparsable || !wanted || breaker.is_demoted(func_id) {
+                // The data is sent as is if it wont get parsed.
+                send_data(&queues, &ciphers, frame.into_bytes(), direction);
             } else {
                 // This arm runs if the data will get parsed.
                 // This gets the actual functions to parse the packet
@@ -235,124 +220,127 @@ async fn parser(
 
                 // The success variable is used becase some code needs to be executed regardless of if the packet parsed correct
                 // otherwise the connection would fail as soon as one packet doesn't get parsed correctly.
-                let success = if parsed_packet.parse_packet(packet).is_ok() {
-                    if config.logging_packets.contains(&func_id.to_string())
-                        || config.logging_packets.contains(&"*".to_string())
-                    {
-                        // The 3:4$ makes sure there is a consistant amount of spaces between the ] and the start of the packet info
-                        log::info!(
-                            "{} [{}]{3:4$} {}",
-                            direction.to_string().yellow(),
-                            func_id.to_string().blue(),
-                            parsed_packet.get_printable(),
-                            "",
-                            config.print_buffer - func_id.to_string().len()
-                        );
-                    }
-                    // This is for the JSON logging
-                    log_queue.push(parsed_packet.clone());
-                    true
-                } else {
-                    log::error!("Could not parse packet!");
-                    false
+                // A panic while parsing is caught, so a bug in one packet doesn't take down the connection.
+                let parse_result = match panic::catch_unwind(AssertUnwindSafe(|| {
+                    parsed_packet.parse_packet(RawPacket::from(packet))
+                })) {
+                    Ok(parse_result) => parse_result.map_err(|e| e.to_string()),
+                    Err(panic) => Err(format!("panicked: {}", panic_message(&*panic))),
                 };
-
-                if success {
-                    parsed_packet
-                        .update_status(&mut shared_status.lock())
-                        .unwrap();
-
-                    // Packet editing takes a lot more time, so it only gets executed if it is needed.
-                    if parsed_packet.packet_editing() {
-                        let mut shared_status_copy = shared_status.lock().clone();
-                        let mut shared_plugins = plugins.lock().clone();
-                        match parsed_packet
-                            .edit_packet(&mut shared_status_copy, &mut shared_plugins, &config)
-                            .await
-                        {
-                            Ok(packet_vec) => {
-                                if packet_vec.len() > 1 {
-                                    // When multiple packet get sent back
-                                    for (packet, new_direction) in packet_vec {
-                                        let out_d = if shared_status_copy.compress == 0 {
-                                            packet.get_data_uncompressed().unwrap()
-                                        } else {
-                                            packet
-                                                .get_data_compressed(
-                                                    shared_status_copy.compress as i32,
-                                                )
-                                                .unwrap()
-                                        };
-                                        match new_direction {
-                                            Direction::Serverbound => {
-                                                let out_d = ciphers.lock().ps_cipher.encrypt(out_d);
-                                                queues.proxy_server.push(out_d);
-                                            }
-                                            Direction::Clientbound => {
-                                                queues.proxy_client.push(out_d)
-                                            }
-                                        }
-                                    }
-                                    // Make sure the original data doesn't get sent anymore
-                                    out_data.clear();
-                                } else if packet_vec.is_empty() {
-                                    // Send the original packet.
-                                } else {
-                                    // One packet
-                                    let (packet, new_direction) = &packet_vec[0];
-                                    to_direction = new_direction.to_owned();
-                                    out_data = if shared_status_copy.compress == 0 {
-                                        packet.get_data_uncompressed().unwrap()
-                                    } else {
-                                        packet
-                                            .get_data_compressed(shared_status_copy.compress as i32)
-                                            .unwrap()
-                                    };
+                match parse_result {
+                    Ok(()) => {
+                        if logged {
+                            // The 3:4$ makes sure there is a consistant amount of spaces between the ] and the start of the packet info
+                            log::info!(
+                                "{} [{}]{3:4$} {}",
+                                direction.to_string().yellow(),
+                                func_id.to_string().blue(),
+                                parsed_packet.get_printable(),
+                                "",
+                                config.print_buffer - func_id.to_string().len()
+                            );
+                        }
+                        // This is for the JSON logging
+                        log_queue.push(parsed_packet.clone());
+
+                        // The rest is done by the session actor, which also sends the packet.
+                        // This waits until it's done, so the packets of this direction stay in order.
+                        let (reply, result) = oneshot::channel();
+                        let event = SessionEvent::Packet {
+                            packet: parsed_packet,
+                            fid: *func_id,
+                            direction,
+                            frame: frame.into_bytes(),
+                            config,
+                            reply,
+                        };
+                        if events.send(event).is_err() {
+                            break 'receiving;
+                        }
+                        match result.await {
+                            Ok(Ok(())) => breaker.record_success(func_id),
+                            Ok(Err(failure)) => {
+                                breaker.record_failure(func_id, &failure.reason);
+                                // If the actor couldn't use the packet at all, it's forwarded like one that didn't parse.
+                                if let Some(frame) = failure.unsent {
+                                    send_data(&queues, &ciphers, frame, direction);
                                 }
-                                shared_status.lock().set(shared_status_copy);
-                                let mut locked_plugins = plugins.lock();
-                                // This should probably be optimized?
-                                locked_plugins.clear();
-                                locked_plugins.append(&mut shared_plugins);
                             }
-                            Err(_) => {
-                                panic!("This should never happen");
+                            // The actor is gone, so the connection was closed.
+                            Err(_) => break 'receiving,
+                        }
+                    }
+                    Err(e) => {
+                        // The packet is decompressed again to show it, so it doesn't have to be kept for every packet.
+                        // The packet ID is in front of it, the offset in the error counts from there.
+                        let packet_data =
+                            decompress_frame(frame.body(), compress, direction).unwrap_or_default();
+                        log::error!(
+                            "Could not parse {} {} (id {:#04x}, {} bytes, {:?}): {}",
+                            direction,
+                            func_id,
+                            packet_id,
+                            packet_data.len(),
+                            session.state(),
+                            e
+                        );
+                        log::error!("Packet data: {}", utils::hex_dump(&packet_data, 64));
+                        if config.save_failed_packets {
+                            match utils::save_failed_packet(direction, func_id, &packet_data) {
+                                Ok(path) => log::info!("Saved packet to {}", path),
+                                Err(e) => log::error!("Could not save packet: {}", e),
                             }
-                        };
+                        }
+                        metrics::record_parse_failure(direction, func_id);
+                        breaker.record_failure(func_id, &e);
+                        send_data(&queues, &ciphers, frame.into_bytes(), direction);
                     }
                 }
-
-                if to_direction == Direction::Serverbound {
-                    out_data = ciphers.lock().ps_cipher.encrypt(out_data)
-                }
-
-                if success
-                    && parsed_packet
-                        .post_send_update(&mut ciphers.lock(), &shared_status.lock())
-                        .is_err()
-                {
-                    panic!("Post send update failed, panicing.")
-                }
             };
-
-            match to_direction {
-                Direction::Serverbound => queues.proxy_server.push(out_data),
-                Direction::Clientbound => queues.proxy_client.push(out_data),
-            }
         }
     }
+    breaker.log_summary();
     Ok(())
 }
 
+// Closes the connection because the client or server sent a frame that isn't allowed.
+// If it was the server, the client is told why.
+fn disconnect_invalid(session: &SessionHandle, direction: Direction, error: &FramingError) {
+    let sender = match direction {
+        Direction::Serverbound => "client",
+        Direction::Clientbound => "server",
+    };
+    log::error!(
+        "The {} sent an invalid packet ({}), closing the connection",
+        sender,
+        error
+    );
+    if direction == Direction::Clientbound {
+        let state = session.state();
+        if let Some(packet) =
+            utils::generate_disconnect_packet(state, "The server sent an invalid packet")
+        {
+            // The connection gets closed anyway, so it doesn't matter if this fails.
+            let _ = session.send_packet(packet, Direction::Clientbound);
+        }
+    }
+    session.close();
+}
+
 async fn handle_connection(
     mut client_stream: TcpStream,
     user_ip: String,
     connection_id: String,
+    sessions: Sessions,
+    shared_config: SharedConfig,
 ) -> Result<(), ()> {
-    let config = conf::get_config();
+    let config = shared_config.read().clone();
+
+    // The connection is counted from here, until its session is registered.
+    let pending = sessions.accept(&connection_id);
 
     // This shared state stores all *mutable* data that is needed in more than one thread.
-    let shared_ciphers: Arc<Mutex<Ciphers>> = Arc::new(Mutex::new(Ciphers::new()));
+    let shared_ciphers = Arc::new(Ciphers::new());
 
     // This part reads data from the client (the first packet) into a buffer
     let mut buffer = Vec::new();
@@ -360,27 +348,41 @@ async fn handle_connection(
 
     // It tries to parse the first packet
     let mut initial_data = RawPacket::from(buffer);
-    let packet_length = initial_data.decode_varint()?;
-    let mut raw_first_packet = RawPacket::from(match initial_data.read(packet_length as usize) {
-        Ok(p) => p,
-        Err(_) => return Ok(()),
-    });
-    let packet_id = raw_first_packet.decode_varint()?;
+    let first_packet = initial_data
+        .decode_varint()
+        .field("length")
+        .and_then(|packet_length| initial_data.read(packet_length as usize).field("data"))
+        .and_then(|data| {
+            let mut raw_first_packet = RawPacket::from(data);
+            let packet_id = raw_first_packet.decode_varint().field("packet_id")?;
+            Ok((packet_id, raw_first_packet))
+        });
+    let (packet_id, raw_first_packet) = match first_packet {
+        Ok(first_packet) => first_packet,
+        Err(e) => {
+            log::error!("Invalid first packet: {}", e);
+            metrics::FAILED_CONNECTIONS.inc();
+            return Ok(());
+        }
+    };
 
     // If the packet ID is not 0, it is not a valid minecraft packet.
     if packet_id != 0 {
         log::error!("Packet ID did not match handshaking packet, terminating connection...");
+        metrics::FAILED_CONNECTIONS.inc();
         // It returns OK because the connection was dealth with successfully, not because everything went like it should have.
         return Ok(());
     };
 
     // It then continues to parse the packet like it is a handshaking packet.
     let mut handshaking_packet = functions::serverbound::handshaking::Handshake::default();
-    if handshaking_packet.parse_packet(raw_first_packet).is_err() {
-        log::error!("Invalid handshake packet! Closing connection...");
+    if let Err(e) = handshaking_packet.parse_packet(raw_first_packet) {
+        log::error!("Invalid handshake packet ({})! Closing connection...", e);
+        metrics::FAILED_CONNECTIONS.inc();
         // Again, returning OK because everything was dealt with, no loose ends.
         return Ok(());
     };
+    pending.set_state(handshaking_packet.next_state.into());
 
     // It then gets the IP address of the actual server to connect to, minus the domain suffix.
     let ip = match handshaking_packet
@@ -393,16 +395,21 @@ async fn handle_connection(
                 "Could not strip suffix of {}",
                 handshaking_packet.server_address
             );
+            metrics::FAILED_CONNECTIONS.inc();
             return Ok(());
         }
     }
     .to_string();
+    tracing::Span::current().record("server", ip.as_str());
 
     // It looks if there is an SRV record present on the domain, if there is it uses that.
     log::debug!("Resolving SRV recrod for ip: {}", ip);
     let resolver =
         TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default()).unwrap();
-    let lookup = resolver.srv_lookup(format!("_minecraft._tcp.{}", ip)).await;
+    let lookup = resolver
+        .srv_lookup(format!("_minecraft._tcp.{}", ip))
+        .instrument(tracing::info_span!("srv_lookup"))
+        .await;
 
     let address = match lookup {
         Ok(response) => {
@@ -420,7 +427,7 @@ async fn handle_connection(
     };
 
     // It converts the updated data back to a packet.
-    let mut new_packet = functions::serverbound::handshaking::Handshake {
+    let new_packet = functions::serverbound::handshaking::Handshake {
         protocol_version: handshaking_packet.protocol_version,
         server_address: address.clone(),
         server_port: 25565,
@@ -430,14 +437,24 @@ async fn handle_connection(
     .get_data_uncompressed()?;
 
     // It adds the remaining data that was sent in the first packet, to make sure no data gets lost.
-    new_packet.append(&mut initial_data.get_vec());
+    let mut new_packet = BytesMut::from(new_packet);
+    new_packet.extend_from_slice(initial_data.get_slice());
 
     // It connects to the server, for now the port 25565 is hardcoded.
     log::info!("Connecting to IP {}", &address);
-    let server_stream = match TcpStream::connect(&format!("{}:{}", &address, 25565)).await {
-        Ok(stream) => stream,
+    let timer = metrics::UPSTREAM_CONNECT_SECONDS.start_timer();
+    let server_stream = match TcpStream::connect(&format!("{}:{}", &address, 25565))
+        .instrument(tracing::info_span!("upstream_connect"))
+        .await
+    {
+        Ok(stream) => {
+            timer.observe_duration();
+            stream
+        }
         Err(err) => {
             log::error!("Could not connect to ip: {}", err);
+            timer.stop_and_discard();
+            metrics::FAILED_CONNECTIONS.inc();
             // As always, returning OK because nothing unhandled happend.
             return Ok(());
         }
@@ -458,30 +475,66 @@ async fn handle_connection(
 
     // The data that might have been left over from the first packet is added to the queue.
     // This is done here because there is no need to create the queues when the server might never connect.
-    queues.client_proxy.push(new_packet);
+    queues.client_proxy.push(new_packet.freeze());
 
-    // It creates a shared status where all data that is mutable or request specific is kept.
-    let shared_status: Arc<Mutex<SharedState>> = Arc::new(Mutex::new(SharedState {
+    // It creates the status where all data that is mutable or request specific is kept.
+    // The status and the plugins are owned by the session actor, the rest of the proxy only sees its info.
+    let status = SharedState {
         // These values might not get used.
-        access_token: config.player_auth_token,
-        uuid: config.player_uuid,
+        access_token: config.player_auth_token.clone(),
+        uuid: config.player_uuid.clone(),
         server_ip: address,
-        connection_id,
+        connection_id: connection_id.clone(),
         user_ip,
+        sessions: sessions.clone(),
         ..SharedState::new()
-    }));
+    };
+    let plugins = Plugins::new(plugins::get_plugins());
+    let plugin_settings = plugins.settings();
+    let compressor = Arc::new(Mutex::new(Compressor::from_config(&config)));
+    let actor = SessionActor::new(
+        status,
+        plugins,
+        shared_ciphers.clone(),
+        compressor.clone(),
+        queues.clone(),
+    );
+    let (events, event_receiver) = mpsc::unbounded_channel();
 
     // These variables are set here, this is after something could have gone wrong,
     //    so they don't get created if they don't need to.
     let log_queue = Arc::new(LogQueue::new());
     let is_closed = Arc::new(AtomicBool::new(false));
-    let plugins = Arc::new(Mutex::new(plugins::get_plugins()));
+
+    // Everything the parsers need is kept in the session handle.
+    let session = SessionHandle {
+        info: actor.info(),
+        ciphers: shared_ciphers,
+        compressor,
+        queues: queues.clone(),
+        is_closed: is_closed.clone(),
+        events,
+        plugin_settings,
+        counters: Arc::new(Mutex::new(PacketCounters::default())),
+        tab_list: actor.tab_list(),
+    };
+
+    // The session gets registered, so it can be found by the control link and the admin API.
+    sessions.insert(connection_id.clone(), session.clone());
+
+    // All the threads of this connection run in its span.
+    let span = tracing::Span::current();
+
+    tokio::spawn({
+        let is_closed = is_closed.clone();
+        async move { actor.run(event_receiver, is_closed).await }.instrument(span.clone())
+    });
 
     // Start a thread for logging the packets
     tokio::spawn({
-        let log_path = format!("./logs/{}.txt", &shared_status.lock().connection_id);
+        let log_path = format!("./logs/{}.txt", &connection_id);
         let log_queue = log_queue.clone();
-        async move { logging::logger(&log_path, log_queue).await }
+        async move { logging::logger(&log_path, log_queue).await }.instrument(span.clone())
     });
 
     // It then starts two threads to put all the received data from the RX channels into the queues
@@ -489,100 +542,143 @@ async fn handle_connection(
         let client_proxy_queue = queues.client_proxy.clone();
         let is_closed = is_closed.clone();
         async move { receiver(crx, client_proxy_queue, "client", is_closed).await }
+            .instrument(span.clone())
     });
     tokio::spawn({
         let server_proxy_queue = queues.server_proxy.clone();
         let is_closed = is_closed.clone();
         async move { receiver(srx, server_proxy_queue, "server", is_closed).await }
+            .instrument(span.clone())
     });
 
     // And it also starts two to put the queued data into the TX channels
     tokio::spawn({
         let proxy_client_queue = queues.proxy_client.clone();
         let is_closed = is_closed.clone();
-        async move { sender(ctx, proxy_client_queue, is_closed).await }
+        async move { sender(ctx, proxy_client_queue, is_closed).await }.instrument(span.clone())
     });
     tokio::spawn({
         let proxy_server_queue = queues.proxy_server.clone();
         let is_closed = is_closed.clone();
-        async move { sender(stx, proxy_server_queue, is_closed).await }
+        async move { sender(stx, proxy_server_queue, is_closed).await }.instrument(span.clone())
     });
 
     // It then starts two parsers, one for each of the directions.
     // These parsers make sure the data is sent both ways and possibly edited and/or logged.
     tokio::spawn({
-        let shared_status = shared_status.clone();
-        let shared_ciphers = shared_ciphers.clone();
-        let queues = queues.clone();
-        let is_closed = is_closed.clone();
-        let plugins = plugins.clone();
+        let session = session.clone();
         let log_queue = log_queue.clone();
-        async move {
-            parser(
-                queues,
-                shared_status,
-                shared_ciphers,
-                Direction::Serverbound,
-                is_closed,
-                plugins,
-                log_queue,
-            )
-            .await
-        }
+        let shared_config = shared_config.clone();
+        async move { parser(session, Direction::Serverbound, log_queue, shared_config).await }
+            .instrument(span.clone())
     });
     tokio::spawn({
-        async move {
-            parser(
-                queues,
-                shared_status,
-                shared_ciphers,
-                Direction::Clientbound,
-                is_closed,
-                plugins,
-                log_queue,
-            )
-            .await
-        }
+        async move { parser(session, Direction::Clientbound, log_queue, shared_config).await }
+            .instrument(span)
     });
 
     Ok(())
 }
 
+// mc-proxy-v2 generate-palette <path to blocks.json> [path to palette.json]
+// The palette is compiled into the proxy, so it has to be built again after this.
+fn generate_palette(args: &[String]) -> std::io::Result<()> {
+    let report_path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: mc-proxy-v2 generate-palette <blocks.json> [palette.json]");
+            std::process::exit(1);
+        }
+    };
+    let palette_path = args.get(1).map(String::as_str).unwrap_or("palette.json");
+    let report = std::fs::read_to_string(report_path)?;
+    match blocks::palette_from_report(&report) {
+        Ok(palette) => {
+            std::fs::write(palette_path, palette)?;
+            println!("Wrote {}", palette_path);
+            Ok(())
+        }
+        Err(why) => {
+            eprintln!("Invalid blocks.json: {}", why);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("generate-palette") {
+        return generate_palette(&args[1..]);
+    }
+
+    // Try to load config to make sure it works
+    let shared_config: SharedConfig = Arc::new(RwLock::new(Arc::new(conf::get_config())));
+    let config = shared_config.read().clone();
+
     // Load the logger, it has a fancy format with colours and it's spaced.
-    Builder::from_default_env()
+    let logger = Builder::from_default_env()
         .format(|buf, record| {
             let formatted_level = buf.default_styled_level(record.level());
             writeln!(buf, "{:<5} {}", formatted_level, record.args())
         })
         .filter_level(LevelFilter::Info)
         .parse_default_env()
-        .init();
+        .build();
+    log_output::init(logger, config.log_json);
 
-    // Try to load config to make sure it works
-    let config = conf::get_config();
+    if let Err(why) = functions::check_packet_ids() {
+        panic!("Invalid packets.json: {}", why);
+    }
+    if let Err(why) = blocks::check_block_registry() {
+        panic!("Invalid palette.json: {}", why);
+    }
 
     log::info!("Starting listener...");
     // Start listening on the ip waiting for new connections
-    let mc_client_listener = match TcpListener::bind(config.listen_address).await {
+    let mc_client_listener = match TcpListener::bind(&config.listen_address).await {
         Ok(listener) => listener,
         Err(err) => panic!("Could not connect to server: {}", err),
     };
 
+    let sessions = Sessions::new();
+
+    // The admin API is a local HTTP server, it is separate from the minecraft listener.
+    if config.admin_enabled {
+        tokio::spawn({
+            let sessions = sessions.clone();
+            let shared_config = shared_config.clone();
+            async move { admin::admin_server(sessions, shared_config).await }
+        });
+    }
+
     loop {
         // If this continues, a new client is connected.
         let next_connection_id = utils::generate_connection_id();
         let (socket, socket_addr) = mc_client_listener.accept().await?;
         let ip = socket_addr.ip().to_string();
+        metrics::ACCEPTED_CONNECTIONS.inc();
         log::info!(
             "Client connected, connection ID: {} IP: {}",
             next_connection_id,
             ip
         );
         // Start the client-handling thread (this will complete quickly)
-        handle_connection(socket, ip, next_connection_id)
-            .await
-            .unwrap();
+        let span = tracing::info_span!(
+            "connection",
+            connection_id = %next_connection_id,
+            username = Empty,
+            server = Empty
+        );
+        handle_connection(
+            socket,
+            ip,
+            next_connection_id,
+            sessions.clone(),
+            shared_config.clone(),
+        )
+        .instrument(span)
+        .await
+        .unwrap();
     }
 }
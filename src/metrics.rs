@@ -0,0 +1,110 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge_vec, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, TextEncoder,
+};
+
+use crate::{functions::Fid, sessions::Sessions, Direction, State};
+
+// All metrics are registered in the default prometheus registry, they are shown on /metrics of the admin API.
+lazy_static! {
+    pub static ref ACTIVE_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "mc_proxy_active_connections",
+        "Connections that are currently open, by state",
+        &["state"]
+    )
+    .unwrap();
+    pub static ref ACCEPTED_CONNECTIONS: IntCounter = register_int_counter!(
+        "mc_proxy_accepted_connections_total",
+        "Connections accepted from clients"
+    )
+    .unwrap();
+    pub static ref FAILED_CONNECTIONS: IntCounter = register_int_counter!(
+        "mc_proxy_failed_connections_total",
+        "Connections that were closed before they could be proxied"
+    )
+    .unwrap();
+    pub static ref BYTES: IntCounterVec = register_int_counter_vec!(
+        "mc_proxy_bytes_total",
+        "Bytes of packet data that went through the proxy",
+        &["direction"]
+    )
+    .unwrap();
+    pub static ref PACKETS: IntCounterVec = register_int_counter_vec!(
+        "mc_proxy_packets_total",
+        "Packets that went through the proxy",
+        &["direction"]
+    )
+    .unwrap();
+    pub static ref PACKETS_BY_TYPE: IntCounterVec = register_int_counter_vec!(
+        "mc_proxy_packets_by_type_total",
+        "Packets that went through the proxy, by packet type",
+        &["direction", "packet"]
+    )
+    .unwrap();
+    pub static ref PARSE_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "mc_proxy_parse_failures_total",
+        "Packets that could not be parsed, by packet type",
+        &["direction", "packet"]
+    )
+    .unwrap();
+    pub static ref EDIT_PACKET_SECONDS: HistogramVec = register_histogram_vec!(
+        "mc_proxy_edit_packet_seconds",
+        "Time the plugins spent editing a packet, by packet type and plugin",
+        &["packet", "plugin"],
+        vec![0.00001, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0]
+    )
+    .unwrap();
+    pub static ref WS_AUTH_SECONDS: Histogram = register_histogram!(
+        "mc_proxy_ws_auth_seconds",
+        "Time it took to authenticate a login through the ws server"
+    )
+    .unwrap();
+    pub static ref UPSTREAM_CONNECT_SECONDS: Histogram = register_histogram!(
+        "mc_proxy_upstream_connect_seconds",
+        "Time it took to connect to the server"
+    )
+    .unwrap();
+}
+
+pub fn record_packet(direction: Direction, fid: &Fid, length: usize) {
+    let direction = direction.to_string();
+    BYTES.with_label_values(&[&direction]).inc_by(length as u64);
+    PACKETS.with_label_values(&[&direction]).inc();
+    PACKETS_BY_TYPE
+        .with_label_values(&[&direction, &fid.to_string()])
+        .inc();
+}
+
+// Runs the hook of one plugin and adds the time it took to the histogram of that plugin.
+pub fn time_plugin<T>(fid: &Fid, plugin: &str, hook: impl FnOnce() -> T) -> T {
+    let _timer = EDIT_PACKET_SECONDS
+        .with_label_values(&[&fid.to_string(), plugin])
+        .start_timer();
+    hook()
+}
+
+pub fn record_parse_failure(direction: Direction, fid: &Fid) {
+    PARSE_FAILURES
+        .with_label_values(&[&direction.to_string(), &fid.to_string()])
+        .inc();
+}
+
+// Returns all the metrics in the prometheus text format.
+pub fn gather(sessions: &Sessions) -> String {
+    // The active connections are counted when the metrics are gathered, so they are never out of sync.
+    let states = sessions.states();
+    for state in [State::Handshaking, State::Status, State::Login, State::Play] {
+        let count = states.iter().filter(|other| **other == state).count();
+        ACTIVE_CONNECTIONS
+            .with_label_values(&[&format!("{:?}", state)])
+            .set(count as i64);
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}
@@ -1,6 +1,6 @@
-use miniz_oxide::deflate::compress_to_vec_zlib;
+use bytes::Bytes;
 
-use crate::RawPacket;
+use crate::{compression::Compressor, RawPacket};
 
 pub struct Packet {
     raw_packet: RawPacket,
@@ -22,49 +22,68 @@ impl Packet {
         }
     }
 
-    pub fn get_data_uncompressed(&self) -> Result<Vec<u8>, ()> {
+    // Gets the data in the format that is used on the connection, compress is the threshold (0 is no compression).
+    pub fn get_data(&self, compress: u32, compressor: &mut Compressor) -> Result<Bytes, ()> {
+        if compress == 0 {
+            self.get_data_uncompressed()
+        } else {
+            self.get_data_compressed(compress as i32, compressor)
+        }
+    }
+
+    pub fn get_data_uncompressed(&self) -> Result<Bytes, ()> {
         let mut pid_encoded = RawPacket::new();
         match self.pid {
             Some(pid) => pid_encoded.encode_varint(pid),
             None => return Err(()),
         }
 
-        let mut data = RawPacket::new();
-        data.encode_varint((self.raw_packet.len() + pid_encoded.len()) as i32);
-        data.push_vec(pid_encoded.get_vec());
-        data.push_vec(self.raw_packet.get_vec());
+        // A varint is at most 5 bytes, so this never has to grow.
+        let length = self.raw_packet.len() + pid_encoded.len();
+        let mut data = RawPacket::with_capacity(length + 5);
+        data.encode_varint(length as i32);
+        data.push_slice(pid_encoded.get_slice());
+        data.push_slice(self.raw_packet.get_slice());
 
-        Ok(data.get_vec())
+        Ok(data.into_bytes())
     }
 
-    pub fn get_data_compressed(&self, compression_threshold: i32) -> Result<Vec<u8>, ()> {
+    pub fn get_data_compressed(
+        &self,
+        compression_threshold: i32,
+        compressor: &mut Compressor,
+    ) -> Result<Bytes, ()> {
         let mut pid_encoded = RawPacket::new();
         match self.pid {
             Some(pid) => pid_encoded.encode_varint(pid),
             None => return Err(()),
         }
 
-        let mut data = RawPacket::new();
-        data.push_vec(pid_encoded.get_vec());
-        data.push_vec(self.raw_packet.get_vec());
+        let data_length = pid_encoded.len() + self.raw_packet.len();
+        if data_length < compression_threshold as usize {
+            // A data length of 0 means the packet isn't compressed.
+            let length = 1 + data_length;
+            let mut return_data = RawPacket::with_capacity(length + 5);
+            return_data.encode_varint(length as i32);
+            return_data.encode_varint(0);
+            return_data.push_slice(pid_encoded.get_slice());
+            return_data.push_slice(self.raw_packet.get_slice());
+            return Ok(return_data.into_bytes());
+        }
 
-        let data_length = if data.len() >= compression_threshold as usize {
-            let dl = data.len();
-            data.set(compress_to_vec_zlib(data.get_slice(), 6));
-            dl
-        } else {
-            0
-        };
+        let compressed =
+            compressor.compress(&[pid_encoded.get_slice(), self.raw_packet.get_slice()])?;
 
         let mut data_length_encoded = RawPacket::new();
         data_length_encoded.encode_varint(data_length as i32);
 
-        let mut return_data = RawPacket::new();
-        return_data.encode_varint((data_length_encoded.len() + data.len()) as i32);
-        return_data.push_vec(data_length_encoded.get_vec());
-        return_data.push_vec(data.get_vec());
+        let length = data_length_encoded.len() + compressed.len();
+        let mut return_data = RawPacket::with_capacity(length + 5);
+        return_data.encode_varint(length as i32);
+        return_data.push_slice(data_length_encoded.get_slice());
+        return_data.push_slice(compressed);
 
-        Ok(return_data.get_vec())
+        Ok(return_data.into_bytes())
     }
 }
 
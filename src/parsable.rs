@@ -1,30 +1,71 @@
 use crate::{
-    conf::Configuration, packet::Packet, plugin::EventHandler, raw_packet::RawPacket, Ciphers,
-    Direction, SharedState,
+    conf::Configuration,
+    functions::{fid_to_pid, Fid},
+    packet::Packet,
+    parse_error::ParseResult,
+    plugin::EventHandler,
+    raw_packet::RawPacket,
+    Ciphers, Direction, SharedState,
 };
 use async_trait::async_trait;
 use dyn_clone::DynClone;
 use erased_serde::serialize_trait_object;
+pub use packet_derive::Packet;
+
+// The fields of a packet, this is implemented with #[derive(Packet)] (see packet_derive for the attributes).
+// Parsable uses it for decoding, encoding and printing, unless a packet does that itself.
+pub trait PacketFields {
+    fn default_fields() -> Self
+    where
+        Self: Sized;
+
+    fn decode_fields(&mut self, packet: &mut RawPacket) -> ParseResult<()>;
+
+    fn encode_fields(&self, packet: &mut RawPacket) -> Result<(), ()>;
+
+    fn printable_fields(&self) -> String;
+
+    // None for structs that are only used inside packets.
+    fn fid(&self) -> Option<Fid>;
+}
 
 #[async_trait]
-pub trait Parsable: erased_serde::Serialize + DynClone {
+pub trait Parsable: PacketFields + erased_serde::Serialize + DynClone {
     fn default() -> Self
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        Self::default_fields()
+    }
 
-    fn parse_packet(&mut self, packet: RawPacket) -> Result<(), ()>;
+    fn parse_packet(&mut self, mut packet: RawPacket) -> ParseResult<()> {
+        self.decode_fields(&mut packet)
+    }
 
     fn encode_packet(&self) -> Result<Packet, ()> {
-        unimplemented!()
+        let fid = self.fid().ok_or(())?;
+        let mut packet = RawPacket::new();
+        self.encode_fields(&mut packet)?;
+        Ok(Packet::from(packet, fid_to_pid(fid)))
     }
 
-    fn get_printable(&self) -> String;
+    fn get_printable(&self) -> String {
+        self.printable_fields()
+    }
 
     #[allow(unused_variables)]
     fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
         Ok(())
     }
 
+    // The proxy needs this packet itself (to keep track of the state, or because it edits it without any plugin),
+    // so it is parsed even if no plugin is interested in it and it isn't logged.
+    fn always_parse(&self) -> bool {
+        false
+    }
+
+    // Plugins and the proxy can only change the packet in edit_packet if this is true.
+    // An empty Vec from edit_packet means nothing changed, the original bytes are sent then.
     fn packet_editing(&self) -> bool {
         false
     }
@@ -40,7 +81,7 @@ pub trait Parsable: erased_serde::Serialize + DynClone {
     }
 
     #[allow(unused_variables)]
-    fn post_send_update(&self, ciphers: &mut Ciphers, status: &SharedState) -> Result<(), ()> {
+    fn post_send_update(&self, ciphers: &Ciphers, status: &SharedState) -> Result<(), ()> {
         Ok(())
     }
 }
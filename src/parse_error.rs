@@ -0,0 +1,85 @@
+use std::fmt;
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+// The error that is returned when decoding a packet fails.
+// It keeps track of the field that was being decoded and how far into the packet that was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub field: Option<String>,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    NotEnoughData { needed: usize, available: usize },
+    InvalidBool(u8),
+    VarIntTooLong,
+    InvalidLength(i64),
+    InvalidUtf8,
+    InvalidNbt(String),
+    InvalidJson(String),
+    InvalidValue(i64),
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            field: None,
+            offset,
+        }
+    }
+
+    // Fields that are inside other fields (like the NBT of a slot) get the outer name in front.
+    pub fn in_field(mut self, name: &str) -> Self {
+        self.field = Some(match self.field {
+            Some(inner) => format!("{}.{}", name, inner),
+            None => name.to_string(),
+        });
+        self
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::NotEnoughData { needed, available } => write!(
+                f,
+                "not enough data, needed {} bytes but only {} are left",
+                needed, available
+            ),
+            ParseErrorKind::InvalidBool(value) => write!(f, "invalid bool {:#04x}", value),
+            ParseErrorKind::VarIntTooLong => write!(f, "varint is too long"),
+            ParseErrorKind::InvalidLength(length) => write!(f, "invalid length {}", length),
+            ParseErrorKind::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            ParseErrorKind::InvalidNbt(why) => write!(f, "invalid NBT: {}", why),
+            ParseErrorKind::InvalidJson(why) => write!(f, "invalid JSON: {}", why),
+            ParseErrorKind::InvalidValue(value) => write!(f, "invalid value {}", value),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{} in `{}` at byte {}", self.kind, field, self.offset),
+            None => write!(f, "{} at byte {}", self.kind, self.offset),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Adds the name of the field that was being decoded to an error, like this:
+// self.username = packet.decode_string().field("username")?;
+pub trait ParseContext<T> {
+    fn field(self, name: &str) -> ParseResult<T>;
+}
+
+impl<T> ParseContext<T> for ParseResult<T> {
+    fn field(self, name: &str) -> ParseResult<T> {
+        self.map_err(|error| error.in_field(name))
+    }
+}
@@ -1,16 +1,35 @@
 use crate::{
-    functions::clientbound::play::{EntityEffect, JoinGame, PlayerAbilities},
+    chunk_cache::ChunkCache,
+    entities::Entities,
+    functions::clientbound::play::{EntityEffect, JoinGame, PlayerAbilities, Respawn},
     functions::serverbound::play::ChatMessageServerbound,
+    functions::Fid,
+    metrics,
     packet::Packet,
+    tab_list::TabList,
+    types::Location,
     Direction,
 };
 use dyn_clone::DynClone;
+use parking_lot::Mutex;
+use std::{collections::HashSet, sync::Arc};
 
 pub trait EventHandler: DynClone {
     fn new() -> Self
     where
         Self: Sized + Clone;
 
+    // The name is used to enable or disable a plugin, it defaults to the name of the struct.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>().rsplit("::").next().unwrap()
+    }
+
+    // The packets the plugin has hooks for. Packets no enabled plugin is interested in only get parsed
+    // if the proxy needs them itself or if they are logged. None means the plugin wants every packet.
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        None
+    }
+
     #[allow(unused_variables)]
     fn on_message(&mut self, message: &ChatMessageServerbound) -> Option<Vec<(Packet, Direction)>> {
         None
@@ -21,6 +40,12 @@ pub trait EventHandler: DynClone {
         None
     }
 
+    // Called when the server moves the player, with the location after the teleport.
+    #[allow(unused_variables)]
+    fn on_teleport(&mut self, location: &Location) -> Option<Vec<(Packet, Direction)>> {
+        None
+    }
+
     #[allow(unused_variables)]
     fn on_player_abilities(
         &mut self,
@@ -37,9 +62,221 @@ pub trait EventHandler: DynClone {
         None
     }
 
+    // Called after an entity was spawned, moved, changed or destroyed, with all the entities the client knows.
+    #[allow(unused_variables)]
+    fn on_entity_update(
+        &mut self,
+        entity_id: i32,
+        entities: &Entities,
+    ) -> Option<Vec<(Packet, Direction)>> {
+        None
+    }
+
+    // Called after blocks changed, with their locations. Use chunks.block_at() to look at the blocks around them.
+    #[allow(unused_variables)]
+    fn on_block_update(
+        &mut self,
+        locations: &[(i32, i32, i32)],
+        chunks: &ChunkCache,
+    ) -> Option<Vec<(Packet, Direction)>> {
+        None
+    }
+
+    // Called after a player was added to, changed in or removed from the tab list, or its header or footer changed.
+    #[allow(unused_variables)]
+    fn on_tab_list_update(&mut self, tab_list: &TabList) -> Option<Vec<(Packet, Direction)>> {
+        None
+    }
+
     #[allow(unused_variables)]
     fn edit_join_game(&mut self, join_game: &JoinGame) -> Option<JoinGame> {
         None
     }
+
+    // Called when the player changes dimension or world, the new world is already in the session state.
+    #[allow(unused_variables)]
+    fn on_respawn(&mut self, respawn: &Respawn) -> Option<Vec<(Packet, Direction)>> {
+        None
+    }
 }
 dyn_clone::clone_trait_object!(EventHandler);
+
+// Calls a hook on the plugins in order, the packets of the first plugin that returns some replace the original packet.
+// An empty vec means no plugin changed anything, the original packet is sent then.
+// Every plugin is timed on its own, by the packet it was called for.
+pub fn first_reply<F>(
+    fid: Fid,
+    plugins: &mut [Box<dyn EventHandler + Send>],
+    mut hook: F,
+) -> Vec<(Packet, Direction)>
+where
+    F: FnMut(&mut (dyn EventHandler + Send)) -> Option<Vec<(Packet, Direction)>>,
+{
+    plugins
+        .iter_mut()
+        .find_map(|plugin| {
+            let name = plugin.name();
+            metrics::time_plugin(&fid, name, || hook(plugin.as_mut()))
+        })
+        .unwrap_or_default()
+}
+
+// All the plugins of one connection, they are owned by the session actor so they are changed in place.
+pub struct Plugins {
+    list: Vec<Box<dyn EventHandler + Send>>,
+    settings: Arc<Mutex<PluginSettings>>,
+}
+
+impl Plugins {
+    pub fn new(list: Vec<Box<dyn EventHandler + Send>>) -> Self {
+        let settings = PluginSettings {
+            names: list.iter().map(|plugin| plugin.name()).collect(),
+            interests: list.iter().map(|plugin| plugin.interested_in()).collect(),
+            disabled: HashSet::new(),
+        };
+        Self {
+            list,
+            settings: Arc::new(Mutex::new(settings)),
+        }
+    }
+
+    // The settings can be changed from outside the session (by the admin API), while the plugins are in use.
+    pub fn settings(&self) -> Arc<Mutex<PluginSettings>> {
+        self.settings.clone()
+    }
+
+    // Takes the enabled plugins out, in order, without copying them. They have to be given back with put_back().
+    pub fn take_enabled(&mut self) -> Vec<Box<dyn EventHandler + Send>> {
+        let settings = self.settings.lock();
+        let (enabled, disabled) = std::mem::take(&mut self.list)
+            .into_iter()
+            .partition(|plugin| settings.is_enabled(plugin.name()));
+        self.list = disabled;
+        enabled
+    }
+
+    pub fn put_back(&mut self, plugins: Vec<Box<dyn EventHandler + Send>>) {
+        self.list.extend(plugins);
+        let settings = self.settings.lock();
+        self.list.sort_by_key(|plugin| {
+            settings
+                .names
+                .iter()
+                .position(|name| *name == plugin.name())
+        });
+    }
+}
+
+// Which plugins of a connection are enabled, plugins can be disabled without losing their state.
+pub struct PluginSettings {
+    names: Vec<&'static str>,
+    // In the same order as the names.
+    interests: Vec<Option<&'static [Fid]>>,
+    disabled: HashSet<&'static str>,
+}
+
+impl PluginSettings {
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), ()> {
+        let name = match self.names.iter().find(|plugin_name| **plugin_name == name) {
+            Some(name) => *name,
+            None => return Err(()),
+        };
+        if enabled {
+            self.disabled.remove(name);
+        } else {
+            self.disabled.insert(name);
+        }
+        Ok(())
+    }
+
+    // Whether any enabled plugin wants to see this packet.
+    pub fn is_wanted(&self, fid: &Fid) -> bool {
+        self.names
+            .iter()
+            .zip(&self.interests)
+            .filter(|(name, _)| self.is_enabled(name))
+            .any(|(_, interests)| interests.is_none_or(|fids| fids.contains(fid)))
+    }
+
+    // The names of all the plugins, with whether they are enabled.
+    pub fn list(&self) -> Vec<(&'static str, bool)> {
+        self.names
+            .iter()
+            .map(|name| (*name, self.is_enabled(name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(plugins: &[Box<dyn EventHandler + Send>]) -> Vec<&'static str> {
+        plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    #[test]
+    fn test_take_enabled() {
+        let mut plugins = Plugins::new(crate::plugins::get_plugins());
+        let all = names(&plugins.list);
+        plugins
+            .settings()
+            .lock()
+            .set_enabled("Rainbowify", false)
+            .unwrap();
+
+        let enabled = plugins.take_enabled();
+        assert_eq!(enabled.len(), all.len() - 1);
+        assert!(!names(&enabled).contains(&"Rainbowify"));
+
+        // Everything is back in the original order.
+        plugins.put_back(enabled);
+        assert_eq!(names(&plugins.list), all);
+        assert!(plugins
+            .settings()
+            .lock()
+            .set_enabled("Unknown", true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_is_wanted() {
+        let plugins = Plugins::new(crate::plugins::get_plugins());
+        let settings = plugins.settings();
+        let mut settings = settings.lock();
+        assert!(settings.is_wanted(&Fid::JoinGame));
+        assert!(!settings.is_wanted(&Fid::ChunkData));
+
+        // JoinGameTest is the only plugin that edits JoinGame.
+        settings.set_enabled("JoinGameTest", false).unwrap();
+        assert!(!settings.is_wanted(&Fid::JoinGame));
+        assert!(settings.is_wanted(&Fid::ChatMessageServerbound));
+    }
+
+    #[test]
+    fn test_first_reply_timing() {
+        let mut plugins = crate::plugins::get_plugins();
+        let samples = |plugin: &str| {
+            metrics::EDIT_PACKET_SECONDS
+                .with_label_values(&["SpawnXpOrb", plugin])
+                .get_sample_count()
+        };
+        let before: Vec<u64> = names(&plugins).iter().map(|name| samples(name)).collect();
+
+        // The second plugin replies, so the ones after it aren't called (or timed).
+        let mut calls = 0;
+        let reply = first_reply(Fid::SpawnXpOrb, &mut plugins, |_| {
+            calls += 1;
+            (calls == 2).then(Vec::new)
+        });
+        assert!(reply.is_empty());
+        let after: Vec<u64> = names(&plugins).iter().map(|name| samples(name)).collect();
+        let timed: Vec<u64> = after.iter().zip(&before).map(|(a, b)| a - b).collect();
+        assert_eq!(timed[..2], [1, 1]);
+        assert!(timed[2..].iter().all(|count| *count == 0));
+    }
+}
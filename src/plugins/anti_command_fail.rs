@@ -1,4 +1,10 @@
-use crate::{functions, packet::Packet, plugin, utils::generate_message_packet, Direction};
+use crate::{
+    functions::{self, Fid},
+    packet::Packet,
+    plugin,
+    utils::generate_message_packet,
+    Direction,
+};
 
 #[derive(Clone)]
 pub struct Acf {}
@@ -8,6 +14,10 @@ impl plugin::EventHandler for Acf {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
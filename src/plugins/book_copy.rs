@@ -1,4 +1,9 @@
-use crate::{functions, packet::Packet, plugin, raw_packet::RawPacket, Direction};
+use crate::{
+    functions::{self, serverbound::play::UseItem, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin, Direction,
+};
 
 #[derive(Clone)]
 pub struct CopyBook {}
@@ -8,6 +13,10 @@ impl plugin::EventHandler for CopyBook {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -15,9 +24,8 @@ impl plugin::EventHandler for CopyBook {
         let mut return_vec = vec![];
         if message.message.starts_with(".click ") {
             for _ in 0..message.message.split(' ').nth(1).unwrap().parse().unwrap() {
-                let mut raw_packet = RawPacket::new();
-                raw_packet.encode_varint(0);
-                return_vec.push((Packet::from(raw_packet, 0x2F), Direction::Serverbound));
+                let use_item = UseItem { hand: 0 };
+                return_vec.push((use_item.encode_packet().ok()?, Direction::Serverbound));
             }
 
             Some(return_vec)
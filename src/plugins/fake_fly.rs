@@ -1,11 +1,11 @@
 use crate::{
-    functions::{clientbound::play::PlayerAbilities, fid_to_pid},
+    functions::{clientbound::play::PlayerAbilities, Fid},
     packet::Packet,
-    plugin,
-    raw_packet::RawPacket,
-    Direction,
+    parsable::Parsable,
+    plugin, Direction,
 };
 
+#[allow(dead_code)]
 #[derive(Clone)]
 pub struct FakeFly {}
 
@@ -14,21 +14,20 @@ impl plugin::EventHandler for FakeFly {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::PlayerAbilities])
+    }
+
     fn on_player_abilities(
         &mut self,
         player_abilities: &PlayerAbilities,
     ) -> Option<Vec<(Packet, Direction)>> {
+        let abilities = PlayerAbilities {
+            flags: player_abilities.flags | 0x04,
+            ..player_abilities.clone()
+        };
         Some(vec![(
-            {
-                let mut new_packet = RawPacket::new();
-                new_packet.encode_ubyte(player_abilities.flags | 0x04);
-                new_packet.encode_float(player_abilities.flying_speed);
-                new_packet.encode_float(player_abilities.fov_modifier);
-                Packet::from(
-                    new_packet,
-                    fid_to_pid(crate::functions::Fid::PlayerAbilities),
-                )
-            },
+            abilities.encode_packet().ok()?,
             Direction::Clientbound,
         )])
     }
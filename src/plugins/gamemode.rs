@@ -1,4 +1,9 @@
-use crate::{functions, packet::Packet, plugin, raw_packet::RawPacket, Direction};
+use crate::{
+    functions::{self, clientbound::play::ChangeGameState, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin, Direction,
+};
 
 #[derive(Clone)]
 pub struct Gamemode {}
@@ -8,26 +13,25 @@ impl plugin::EventHandler for Gamemode {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
     ) -> Option<Vec<(Packet, Direction)>> {
         if message.message.starts_with(".gm") {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_ubyte(3);
-            if message.message == ".gmc" {
-                raw_packet.encode_float(1f32);
-            } else if message.message == ".gms" {
-                raw_packet.encode_float(0f32);
-            } else if message.message == ".gma" {
-                raw_packet.encode_float(2f32);
-            } else if message.message == ".gmsp" {
-                raw_packet.encode_float(3f32);
-            } else {
-                return None;
-            }
+            let value = match message.message.as_str() {
+                ".gmc" => 1f32,
+                ".gms" => 0f32,
+                ".gma" => 2f32,
+                ".gmsp" => 3f32,
+                _ => return None,
+            };
+            let gamemode = ChangeGameState { reason: 3, value };
             Some(vec![(
-                Packet::from(raw_packet, 0x1D),
+                gamemode.encode_packet().ok()?,
                 Direction::Clientbound,
             )])
         } else {
@@ -1,4 +1,7 @@
-use crate::{functions::clientbound::play::JoinGame, plugin};
+use crate::{
+    functions::{clientbound::play::JoinGame, Fid},
+    plugin,
+};
 
 #[derive(Clone)]
 pub struct JoinGameTest {}
@@ -8,6 +11,10 @@ impl plugin::EventHandler for JoinGameTest {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::JoinGame])
+    }
+
     fn edit_join_game(&mut self, _join_game: &JoinGame) -> Option<JoinGame> {
         let mut join_game: JoinGame = _join_game.to_owned();
         for biome_entry in join_game.dimension_codec.biome_registry.value.iter_mut() {
@@ -1,5 +1,10 @@
 use crate::{
-    functions, packet::Packet, plugin, raw_packet::RawPacket, utils::generate_message_packet,
+    functions::{self, serverbound::play::PlayerPosition, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin,
+    types::Location,
+    utils::generate_message_packet,
     Direction,
 };
 
@@ -21,6 +26,15 @@ impl plugin::EventHandler for JumpTrain {
         }
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[
+            Fid::ChatMessageServerbound,
+            Fid::PlayerPosition,
+            Fid::PlayerPositionRotation,
+            Fid::PlayerPositionAndLook,
+        ])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -28,28 +42,29 @@ impl plugin::EventHandler for JumpTrain {
         let mut return_vec = vec![];
         if message.message.starts_with(".jump ") {
             for _ in 0..message.message.split(' ').nth(1).unwrap().parse().unwrap() {
-                let mut raw_packet = RawPacket::new();
-                raw_packet.encode_double(self.x);
-                raw_packet.encode_double(self.y + 0.41999998688698);
-                raw_packet.encode_double(self.z);
-                raw_packet.encode_bool(false);
-                return_vec.push((Packet::from(raw_packet, 0x12), Direction::Serverbound));
-                let mut raw_packet = RawPacket::new();
-                raw_packet.encode_double(self.x);
-                raw_packet.encode_double(self.y);
-                raw_packet.encode_double(self.z);
-                raw_packet.encode_bool(true);
-                return_vec.push((Packet::from(raw_packet, 0x12), Direction::Serverbound));
+                let up = PlayerPosition {
+                    x: self.x,
+                    feet_y: self.y + 0.41999998688698,
+                    z: self.z,
+                    on_ground: false,
+                };
+                let down = PlayerPosition {
+                    feet_y: self.y,
+                    on_ground: true,
+                    ..up.clone()
+                };
+                return_vec.push((up.encode_packet().ok()?, Direction::Serverbound));
+                return_vec.push((down.encode_packet().ok()?, Direction::Serverbound));
             }
             log::info!("Jumped at {} {} {}", self.x, self.y, self.z);
 
             Some(return_vec)
         } else if message.message == ".startjump" {
             self.enabled = !self.enabled;
-            return Some(vec![(
+            Some(vec![(
                 generate_message_packet(&format!("Switched jumping to {}!", self.enabled)).unwrap(),
                 Direction::Clientbound,
-            )]);
+            )])
         } else {
             None
         }
@@ -64,18 +79,19 @@ impl plugin::EventHandler for JumpTrain {
             {
                 let mut return_vec = vec![];
                 for _ in 0..35 {
-                    let mut raw_packet = RawPacket::new();
-                    raw_packet.encode_double(self.x);
-                    raw_packet.encode_double(self.y + 0.41999998688698);
-                    raw_packet.encode_double(self.z);
-                    raw_packet.encode_bool(false);
-                    return_vec.push((Packet::from(raw_packet, 0x12), Direction::Serverbound));
-                    let mut raw_packet = RawPacket::new();
-                    raw_packet.encode_double(self.x);
-                    raw_packet.encode_double(self.y);
-                    raw_packet.encode_double(self.z);
-                    raw_packet.encode_bool(true);
-                    return_vec.push((Packet::from(raw_packet, 0x12), Direction::Serverbound));
+                    let up = PlayerPosition {
+                        x: self.x,
+                        feet_y: self.y + 0.41999998688698,
+                        z: self.z,
+                        on_ground: false,
+                    };
+                    let down = PlayerPosition {
+                        feet_y: self.y,
+                        on_ground: true,
+                        ..up.clone()
+                    };
+                    return_vec.push((up.encode_packet().ok()?, Direction::Serverbound));
+                    return_vec.push((down.encode_packet().ok()?, Direction::Serverbound));
                 }
                 log::info!("Jumped at {} {} {}", self.x, self.y, self.z);
                 return Some(return_vec);
@@ -87,4 +103,12 @@ impl plugin::EventHandler for JumpTrain {
         self.z = z;
         None
     }
+
+    // Otherwise the next moves would be sent from where the player was before the teleport.
+    fn on_teleport(&mut self, location: &Location) -> Option<Vec<(Packet, Direction)>> {
+        self.x = location.x;
+        self.y = location.y;
+        self.z = location.z;
+        None
+    }
 }
@@ -1,5 +1,10 @@
 use crate::{
-    functions, packet::Packet, plugin, raw_packet::RawPacket, utils::rainbowfy, Direction,
+    functions::{self, serverbound::play::ChatMessageServerbound, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin,
+    utils::rainbowfy,
+    Direction,
 };
 use regex::{Captures, Regex};
 
@@ -11,6 +16,10 @@ impl plugin::EventHandler for Rainbowify {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -20,19 +29,18 @@ impl plugin::EventHandler for Rainbowify {
             .replace_all(&message.message, |caps: &Captures| {
                 log::debug!(
                     "Running on message for rainbowify: {}",
-                    caps.get(1).unwrap().as_str().to_string()
+                    caps.get(1).unwrap().as_str()
                 );
-                rainbowfy(caps.get(1).unwrap().as_str().to_string())
+                // Chat from the client is a plain string, so the colors are sent as & codes.
+                rainbowfy(caps.get(1).unwrap().as_str()).to_legacy('&')
             })
             .to_string();
         if new_message != message.message {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_string(new_message);
+            let message = ChatMessageServerbound {
+                message: new_message,
+            };
             Some(vec![(
-                Packet::from(
-                    raw_packet,
-                    functions::fid_to_pid(functions::Fid::ChatMessageServerbound),
-                ),
+                message.encode_packet().ok()?,
                 Direction::Serverbound,
             )])
         } else {
@@ -1,8 +1,12 @@
 use crate::{
-    functions::{self, fid_to_pid},
+    functions::{
+        self,
+        clientbound::play::{EntityEffect, RemoveEntityEffect},
+        Fid,
+    },
     packet::Packet,
+    parsable::Parsable,
     plugin,
-    raw_packet::RawPacket,
     utils::generate_message_packet,
     Direction,
 };
@@ -19,6 +23,10 @@ impl plugin::EventHandler for SwimSpeedCommand {
         }
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound, Fid::EntityEffect])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -48,24 +56,20 @@ impl plugin::EventHandler for SwimSpeedCommand {
         effect_info: &functions::clientbound::play::EntityEffect,
     ) -> Option<Vec<(Packet, Direction)>> {
         if effect_info.amplifier != self.current_amplifier && effect_info.effect_id == 30 {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_varint(effect_info.entity_id);
-            raw_packet.encode_ubyte(effect_info.effect_id);
-            if self.current_amplifier == 255 {
-                return Some(vec![(
-                    Packet::from(raw_packet, 0x37),
-                    Direction::Clientbound,
-                )]);
+            let packet = if self.current_amplifier == 255 {
+                RemoveEntityEffect {
+                    entity_id: effect_info.entity_id,
+                    effect_id: effect_info.effect_id,
+                }
+                .encode_packet()
             } else {
-                raw_packet.encode_ubyte(self.current_amplifier);
-                raw_packet.encode_varint(effect_info.duration);
-                raw_packet.encode_ubyte(effect_info.flags);
-
-                return Some(vec![(
-                    Packet::from(raw_packet, fid_to_pid(functions::Fid::EntityEffect)),
-                    Direction::Clientbound,
-                )]);
-            }
+                EntityEffect {
+                    amplifier: self.current_amplifier,
+                    ..effect_info.clone()
+                }
+                .encode_packet()
+            };
+            return Some(vec![(packet.ok()?, Direction::Clientbound)]);
         }
         None
     }
@@ -1,6 +1,9 @@
 use crate::{
-    functions::serverbound::play::ChatMessageServerbound, packet::Packet, plugin,
-    utils::generate_message_packet, Direction,
+    functions::{serverbound::play::ChatMessageServerbound, Fid},
+    packet::Packet,
+    plugin,
+    utils::generate_message_packet,
+    Direction,
 };
 
 #[derive(Clone)]
@@ -11,6 +14,10 @@ impl plugin::EventHandler for TestPlugin {
         TestPlugin {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(&mut self, message: &ChatMessageServerbound) -> Option<Vec<(Packet, Direction)>> {
         if message.message == ".test" {
             return Some(vec![(
@@ -1,4 +1,9 @@
-use crate::{functions, packet::Packet, plugin, raw_packet::RawPacket, Direction};
+use crate::{
+    functions::{self, clientbound::play::ChangeGameState, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin, Direction,
+};
 
 #[derive(Clone)]
 pub struct UpdateGame {}
@@ -8,6 +13,10 @@ impl plugin::EventHandler for UpdateGame {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -24,13 +33,8 @@ impl plugin::EventHandler for UpdateGame {
                 None => return None,
             }
             .unwrap();
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_ubyte(reason);
-            raw_packet.encode_float(value);
-            Some(vec![(
-                Packet::from(raw_packet, 0x1D),
-                Direction::Clientbound,
-            )])
+            let state = ChangeGameState { reason, value };
+            Some(vec![(state.encode_packet().ok()?, Direction::Clientbound)])
         } else {
             None
         }
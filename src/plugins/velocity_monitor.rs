@@ -1,6 +1,7 @@
-use crate::{packet::Packet, plugin, Direction};
+use crate::{functions::Fid, packet::Packet, plugin, Direction};
 use std::time::Instant;
 
+#[allow(dead_code)]
 #[derive(Clone)]
 pub struct Velocity {
     prev_x: f64,
@@ -19,6 +20,10 @@ impl plugin::EventHandler for Velocity {
         }
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::PlayerPosition, Fid::PlayerPositionRotation])
+    }
+
     fn on_move(&mut self, x: f64, y: f64, z: f64) -> Option<Vec<(Packet, Direction)>> {
         let distance =
             ((self.prev_x - x).powi(2) + (self.prev_y - y).powi(2) + (self.prev_z - z).powi(2))
@@ -1,4 +1,9 @@
-use crate::{functions, packet::Packet, plugin, raw_packet::RawPacket, Direction};
+use crate::{
+    functions::{self, clientbound::play::ChangeGameState, Fid},
+    packet::Packet,
+    parsable::Parsable,
+    plugin, Direction,
+};
 
 #[derive(Clone)]
 pub struct WeirdSky {}
@@ -8,6 +13,10 @@ impl plugin::EventHandler for WeirdSky {
         Self {}
     }
 
+    fn interested_in(&self) -> Option<&'static [Fid]> {
+        Some(&[Fid::ChatMessageServerbound])
+    }
+
     fn on_message(
         &mut self,
         message: &functions::serverbound::play::ChatMessageServerbound,
@@ -18,13 +27,11 @@ impl plugin::EventHandler for WeirdSky {
                 None => return None,
             }
             .unwrap();
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_ubyte(7);
-            raw_packet.encode_float(sky_numer);
-            Some(vec![(
-                Packet::from(raw_packet, 0x1D),
-                Direction::Clientbound,
-            )])
+            let sky = ChangeGameState {
+                reason: 7,
+                value: sky_numer,
+            };
+            Some(vec![(sky.encode_packet().ok()?, Direction::Clientbound)])
         } else {
             None
         }
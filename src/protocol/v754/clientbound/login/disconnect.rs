@@ -1,26 +1,16 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
-use crate::{SharedState, State};
+use crate::parsable::{Packet, Parsable};
+use crate::{chat::TextComponent, SharedState, State};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(Disconnect)]
 pub struct Disconnect {
-    reason: String,
+    pub reason: TextComponent,
 }
 
 impl Parsable for Disconnect {
-    fn default() -> Self {
-        Self {
-            reason: String::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.reason = packet.decode_string()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        self.reason.to_string()
+    fn always_parse(&self) -> bool {
+        true
     }
 
     fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
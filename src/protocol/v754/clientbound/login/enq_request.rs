@@ -1,9 +1,8 @@
+use crate::{conf::Configuration, functions::serverbound::login::EncResponse, utils, Ciphers};
 use crate::{
-    conf::Configuration,
-    functions::{fid_to_pid, Fid},
-    utils, Ciphers,
+    packet::Packet,
+    parsable::{self, Parsable},
 };
-use crate::{packet::Packet, parsable::Parsable, raw_packet::RawPacket};
 use crate::{Direction, SharedState};
 use hex::encode;
 use rand::Rng;
@@ -13,7 +12,6 @@ use crypto::digest::Digest;
 use crypto::sha1::Sha1;
 
 use std::collections::HashMap;
-use std::iter;
 
 use num_bigint_dig::BigUint;
 use reqwest::Client;
@@ -22,18 +20,19 @@ use rsa_der::public_key_from_der;
 use rustc_serialize::hex::ToHex;
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EncRequest)]
 pub struct EncRequest {
-    server_id: String,
-    public_key_length: i32,
-    public_key: Vec<u8>,
-    verify_token_length: i32,
-    verify_token: Vec<u8>,
+    pub server_id: String,
+    #[prefixed_array]
+    pub public_key: Vec<u8>,
+    #[prefixed_array]
+    pub verify_token: Vec<u8>,
 }
 
 const LEADING_ZERO_REGEX: &str = r#"^0+"#;
 
-fn two_complement(bytes: &mut Vec<u8>) {
+fn two_complement(bytes: &mut [u8]) {
     let mut carry = true;
     for i in (0..bytes.len()).rev() {
         bytes[i] = !bytes[i];
@@ -46,36 +45,21 @@ fn two_complement(bytes: &mut Vec<u8>) {
 
 #[async_trait::async_trait]
 impl Parsable for EncRequest {
-    fn default() -> Self {
-        Self {
-            server_id: String::new(),
-            public_key_length: 0,
-            public_key: Vec::new(),
-            verify_token_length: 0,
-            verify_token: Vec::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.server_id = packet.decode_string()?;
-        self.public_key_length = packet.decode_varint()?;
-        self.public_key = packet.read(self.public_key_length as usize)?;
-        self.verify_token_length = packet.decode_varint()?;
-        self.verify_token = packet.read(self.verify_token_length as usize)?;
-        Ok(())
-    }
-
     fn get_printable(&self) -> String {
         format!(
             "{} {} {} {}",
             // self.server_id,
-            self.public_key_length,
+            self.public_key.len(),
             utils::make_string_fixed_length(encode(self.public_key.clone()), 20),
-            self.verify_token_length,
+            self.verify_token.len(),
             utils::make_string_fixed_length(encode(self.verify_token.clone()), 20)
         )
     }
 
+    fn always_parse(&self) -> bool {
+        true
+    }
+
     fn packet_editing(&self) -> bool {
         true
     }
@@ -94,19 +78,14 @@ impl Parsable for EncRequest {
         hash.input(&status.secret_key);
         hash.input(&self.public_key);
 
-        let mut hex: Vec<u8> = iter::repeat(0).take((hash.output_bits() + 7) / 8).collect();
+        let mut hex: Vec<u8> = vec![0; hash.output_bits().div_ceil(8)];
         hash.result(&mut hex);
 
         let regex = Regex::new(LEADING_ZERO_REGEX).unwrap();
 
         let result_hash = if (hex[0] & 0x80) == 0x80 {
             two_complement(&mut hex);
-            format!(
-                "-{}",
-                regex
-                    .replace(hex.as_slice().to_hex().as_str(), "")
-                    .to_string()
-            )
+            format!("-{}", regex.replace(hex.as_slice().to_hex().as_str(), ""))
         } else {
             regex
                 .replace(hex.as_slice().to_hex().as_str(), "")
@@ -144,39 +123,30 @@ impl Parsable for EncRequest {
             RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)).unwrap();
         let padding = PaddingScheme::new_pkcs1v15_encrypt();
 
-        let mut unformatted_packet = crate::RawPacket::new();
-        unformatted_packet.encode_varint(128);
-        unformatted_packet.push_vec(
-            public_key
-                .encrypt(&mut rng, padding, &status.secret_key[..])
-                .unwrap(),
-        );
-        unformatted_packet.encode_varint(128);
+        // Both are encrypted with the public key of the server.
+        let shared_secret = public_key
+            .encrypt(&mut rng, padding, &status.secret_key[..])
+            .unwrap();
         let padding = PaddingScheme::new_pkcs1v15_encrypt();
-
-        unformatted_packet.push_vec(
-            public_key
-                .encrypt(&mut rng, padding, &self.verify_token[..])
-                .unwrap(),
-        );
-        let response_packet = Packet::from(unformatted_packet, fid_to_pid(Fid::EncResponse));
+        let verify_token = public_key
+            .encrypt(&mut rng, padding, &self.verify_token[..])
+            .unwrap();
+        let response_packet = EncResponse {
+            shared_secret,
+            verify_token,
+        }
+        .encode_packet()?;
         log::debug!("Sending serverbound encryption response");
 
-        // Send to proxy_server packet:
-        // Shared key length (varint)
-        // Shared key encrypted with public key (byte array)
-        // Verify token length (varint)
-        // Verify token encrypted with public key (byte array)
-
         // Reset the access_token to not keep it in memory needlessly.
         status.access_token = String::new();
 
         Ok(vec![(response_packet, Direction::Serverbound)])
     }
 
-    fn post_send_update(&self, ciphers: &mut Ciphers, status: &SharedState) -> Result<(), ()> {
-        ciphers.ps_cipher.enable(&status.secret_key);
-        ciphers.sp_cipher.enable(&status.secret_key);
+    fn post_send_update(&self, ciphers: &Ciphers, status: &SharedState) -> Result<(), ()> {
+        ciphers.ps_cipher.lock().enable(&status.secret_key);
+        ciphers.sp_cipher.lock().enable(&status.secret_key);
         log::debug!("Enabled ciphers");
         Ok(())
     }
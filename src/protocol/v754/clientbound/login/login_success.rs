@@ -1,30 +1,18 @@
+use crate::parsable::{Packet, Parsable};
 use crate::types::Uuid;
-use crate::{parsable::Parsable, raw_packet::RawPacket};
 use crate::{SharedState, State};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(LoginSuccess)]
 pub struct LoginSuccess {
-    uuid: Uuid,
-    username: String,
+    pub uuid: Uuid,
+    pub username: String,
 }
 
 impl Parsable for LoginSuccess {
-    fn default() -> Self {
-        Self {
-            uuid: Uuid::from(0),
-            username: String::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.uuid = packet.decode_uuid()?;
-        self.username = packet.decode_string()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{} {}", self.uuid, self.username,)
+    fn always_parse(&self) -> bool {
+        true
     }
 
     fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
@@ -1,55 +1,61 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::{
+    chunk_cache::notify_plugins,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
 use serde::Serialize;
 
-#[derive(Clone, Debug, Serialize)]
-enum DiggingStatus {
+#[derive(Clone, Debug, Default, Serialize, parsable::Packet)]
+#[packet(varint)]
+pub enum DiggingStatus {
+    #[default]
     Started,
     Cancelled,
     Finished,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(AckPlayerDigging)]
 pub struct AckPlayerDigging {
-    x: i32,
-    y: i32,
-    z: i32,
-    block: i32,
-    status: DiggingStatus,
-    successful: bool,
+    #[position]
+    pub location: (i32, i32, i32),
+    // The state id the block has now, which is the old block if digging wasn't successful.
+    #[varint]
+    pub block: i32,
+    pub status: DiggingStatus,
+    pub successful: bool,
 }
 
+#[async_trait::async_trait]
 impl Parsable for AckPlayerDigging {
-    fn default() -> Self {
-        Self {
-            x: 0,
-            y: 0,
-            z: 0,
-            block: 0,
-            status: DiggingStatus::Started,
-            successful: false,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        let position = packet.decode_position()?;
-        self.x = position.0;
-        self.y = position.1;
-        self.z = position.2;
-        self.block = packet.decode_varint()?;
-        self.status = match packet.decode_varint()? {
-            0x00 => DiggingStatus::Started,
-            0x01 => DiggingStatus::Cancelled,
-            0x02 => DiggingStatus::Finished,
-            _ => return Err(()),
-        };
-        self.successful = packet.decode_bool()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        let (x, y, z) = self.location;
+        status.chunks.set_block(x, y, z, self.block);
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {:?} {}",
-            self.x, self.y, self.z, self.block, self.status, self.successful,
-        )
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::AckPlayerDigging,
+            &[self.location],
+            &status.chunks,
+            plugins,
+        ))
     }
 }
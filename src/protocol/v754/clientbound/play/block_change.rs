@@ -0,0 +1,50 @@
+use crate::{
+    chunk_cache::notify_plugins,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(BlockChange)]
+pub struct BlockChange {
+    #[position]
+    pub location: (i32, i32, i32),
+    // The global state id of the new block.
+    #[varint]
+    pub block_id: i32,
+}
+
+#[async_trait::async_trait]
+impl Parsable for BlockChange {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        let (x, y, z) = self.location;
+        status.chunks.set_block(x, y, z, self.block_id);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::BlockChange,
+            &[self.location],
+            &status.chunks,
+            plugins,
+        ))
+    }
+}
@@ -0,0 +1,12 @@
+use crate::parsable::{Packet, Parsable};
+use serde::Serialize;
+
+// Reason 3 changes the gamemode and 7 the rain level, the value is what it is changed to.
+#[derive(Clone, Serialize, Packet)]
+#[packet(ChangeGameState)]
+pub struct ChangeGameState {
+    pub reason: u8,
+    pub value: f32,
+}
+
+impl Parsable for ChangeGameState {}
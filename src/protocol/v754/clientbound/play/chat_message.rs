@@ -1,52 +1,35 @@
 use crate::{
+    chat::TextComponent,
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::serverbound::play::{ChatMessageServerbound, PlayerBlockPlace},
     packet::Packet,
-    parsable::Parsable,
-    raw_packet::RawPacket,
+    parsable::{self, Parsable},
     types::Uuid,
     Direction, EventHandler, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Debug, Serialize)]
-enum ChatMessagePosition {
+#[derive(Clone, Debug, Default, Serialize, parsable::Packet)]
+#[packet(byte)]
+pub enum ChatMessagePosition {
+    #[default]
     Chat,
     SystemMessage,
     GameInfo,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(ChatMessageClientbound)]
 pub struct ChatMessageClientbound {
-    data: String,
-    position: ChatMessagePosition,
-    sender: Uuid,
+    pub data: TextComponent,
+    pub position: ChatMessagePosition,
+    pub sender: Uuid,
 }
 
 #[async_trait::async_trait]
 impl Parsable for ChatMessageClientbound {
-    fn default() -> Self {
-        Self {
-            data: String::new(),
-            position: ChatMessagePosition::Chat,
-            sender: Uuid::from(0),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.data = packet.decode_chat()?;
-        self.position = match packet.decode_byte()? {
-            0 => ChatMessagePosition::Chat,
-            1 => ChatMessagePosition::SystemMessage,
-            2 => ChatMessagePosition::GameInfo,
-            _ => return Err(()),
-        };
-        self.sender = packet.decode_uuid()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{} {:?} {}", self.data, self.position, self.sender)
+    fn always_parse(&self) -> bool {
+        true
     }
 
     fn packet_editing(&self) -> bool {
@@ -59,37 +42,23 @@ impl Parsable for ChatMessageClientbound {
         _plugins: &mut Vec<Box<dyn EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        if self.data == "{\"text\":\"§d[§5§lF§d][§5FearRP §d-\\u003e §5zegevlier§d]§r now\"}"
-        {
-            Ok(vec![(
-                {
-                    let mut raw_packet = RawPacket::new();
-                    raw_packet.encode_string("/buy".to_string());
-                    Packet::from(raw_packet, 0x03)
-                },
-                Direction::Serverbound,
-            )])
-        } else if self.data == "{\"text\":\"§d[§5§lF§d][§5FearRP §d-\\u003e §5zegevlier§d]§r hi\"}"
-        {
-            Ok(vec![(
-                {
-                    Packet::from(
-                        {
-                            let mut raw_packet = RawPacket::new();
-                            raw_packet.encode_varint(0);
-                            raw_packet.encode_position((1820, 50, 1068));
-                            raw_packet.encode_varint(4);
-                            raw_packet.encode_float(0.0);
-                            raw_packet.encode_float(0.5898391);
-                            raw_packet.encode_float(0.51513046);
-                            raw_packet.encode_bool(false);
-                            raw_packet
-                        },
-                        fid_to_pid(Fid::PlayerBlockPlace),
-                    )
-                },
-                Direction::Serverbound,
-            )])
+        let legacy = self.data.to_legacy('§');
+        if legacy == "§d[§5§lF§d][§5FearRP §d-> §5zegevlier§d]§r now" {
+            let buy = ChatMessageServerbound {
+                message: "/buy".to_string(),
+            };
+            Ok(vec![(buy.encode_packet()?, Direction::Serverbound)])
+        } else if legacy == "§d[§5§lF§d][§5FearRP §d-> §5zegevlier§d]§r hi" {
+            let place = PlayerBlockPlace {
+                hand: 0,
+                location: (1820, 50, 1068),
+                face: 4,
+                cursor_pos_x: 0.0,
+                cursor_pos_y: 0.5898391,
+                cursor_pos_z: 0.51513046,
+                inside_block: false,
+            };
+            Ok(vec![(place.encode_packet()?, Direction::Serverbound)])
         } else {
             Ok(vec![])
         }
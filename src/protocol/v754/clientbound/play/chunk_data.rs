@@ -1,6 +1,11 @@
 use crate::{
-    conf::Configuration, functions::fid_to_pid, packet::Packet, parsable::Parsable,
-    raw_packet::RawPacket, Direction, EventHandler, SharedState,
+    chunk_section::ChunkSection,
+    codec::FieldCodec,
+    functions::Fid,
+    parsable::{PacketFields, Parsable},
+    parse_error::{ParseContext, ParseErrorKind, ParseResult},
+    raw_packet::RawPacket,
+    SharedState,
 };
 use serde::Serialize;
 
@@ -18,33 +23,24 @@ Number of block entities	VarInt	Number of elements in the following array.
 Block entities	Array of NBT Tag	All block entities in the chunk. Use the x, y, and z tags in the NBT to determine their positions.
 */
 
-#[derive(Debug, Clone, Serialize)]
-struct ChunkSection {
-    block_count: i16,
-    bits_per_block: u8,
-    palette: Option<Vec<i32>>,
-    data_array_length: i32,
-    block_ids_array: Vec<i32>,
-}
-
 #[derive(Clone, Serialize)]
 pub struct ChunkData {
-    chunk_x: i32,
-    chunk_z: i32,
-    full_chunk: bool,
-    primary_bit_mask: i32,
-    heightmaps: nbt::Blob,
-    biomes_length: Option<i32>,
-    biomes: Option<Vec<i32>>,
-    size: i32,
-    data: Vec<Option<ChunkSection>>,
-    number_of_block_entities: i32,
-    block_entities: Vec<nbt::Blob>,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub full_chunk: bool,
+    pub primary_bit_mask: i32,
+    pub heightmaps: nbt::Blob,
+    pub biomes_length: Option<i32>,
+    pub biomes: Option<Vec<i32>>,
+    // From the bottom up, only the sections in the primary bit mask are sent.
+    pub data: Vec<Option<ChunkSection>>,
+    pub number_of_block_entities: i32,
+    pub block_entities: Vec<nbt::Blob>,
 }
 
-#[async_trait::async_trait]
-impl Parsable for ChunkData {
-    fn default() -> Self {
+// The sections are too irregular for #[derive(Packet)], so this is written out.
+impl PacketFields for ChunkData {
+    fn default_fields() -> Self {
         Self {
             chunk_x: 0,
             chunk_z: 0,
@@ -53,143 +49,67 @@ impl Parsable for ChunkData {
             heightmaps: nbt::Blob::new(),
             biomes_length: None,
             biomes: None,
-            size: 0,
             data: vec![],
             number_of_block_entities: 0,
             block_entities: vec![],
         }
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.chunk_x = packet.decode_int()?;
-        self.chunk_z = packet.decode_int()?;
-        self.full_chunk = packet.decode_bool()?;
-        self.primary_bit_mask = packet.decode_varint()?;
-        self.heightmaps = packet.decode_nbt()?;
+    fn decode_fields(&mut self, packet: &mut RawPacket) -> ParseResult<()> {
+        self.chunk_x = packet.decode_int().field("chunk_x")?;
+        self.chunk_z = packet.decode_int().field("chunk_z")?;
+        self.full_chunk = packet.decode_bool().field("full_chunk")?;
+        self.primary_bit_mask = packet.decode_varint().field("primary_bit_mask")?;
+        self.heightmaps = packet.decode_nbt_blob().field("heightmaps")?;
         if self.full_chunk {
-            self.biomes_length = Some(packet.decode_varint()?);
+            self.biomes_length = Some(packet.decode_varint().field("biomes_length")?);
             let mut biome_list = vec![];
             for _ in 0..self.biomes_length.unwrap() {
                 biome_list.push(packet.decode_varint()?);
             }
             self.biomes = Some(biome_list);
         }
-        self.size = packet.decode_varint()?;
-
-        let mut raw_chunk_data = RawPacket::from(packet.read(self.size as usize)?);
-        for y in 0..16 {
-            if self.primary_bit_mask & (1 << y) != 0 {
-                let mut chunk_section = ChunkSection {
-                    block_count: 0,
-                    bits_per_block: 0,
-                    palette: None,
-                    data_array_length: 0,
-                    block_ids_array: vec![],
-                };
-                chunk_section.block_count = raw_chunk_data.decode_short()?;
-                chunk_section.bits_per_block = raw_chunk_data.decode_ubyte()?;
-
-                chunk_section.bits_per_block = if chunk_section.bits_per_block <= 4 {
-                    4
-                } else if chunk_section.bits_per_block <= 8 {
-                    chunk_section.bits_per_block
-                } else {
-                    255
-                };
-
-                // log::info!("bpb: {}", chunk_section.bits_per_block);
-
-                if chunk_section.bits_per_block < 255 {
-                    let palette_length = raw_chunk_data.decode_varint()?;
-                    let mut palette = vec![];
-                    for _ in 0..palette_length {
-                        palette.push(raw_chunk_data.decode_varint()?);
-                    }
-                    chunk_section.palette = Some(palette);
-                }
-
-                chunk_section.data_array_length = raw_chunk_data.decode_varint()?;
-                for _ in 0..chunk_section.data_array_length {
-                    let long = raw_chunk_data.decode_ulong()?;
-                    if self.chunk_x == 0 && self.chunk_z == 0 && y == 0 {
-                        log::info!("{}", long);
-                    }
-                    // for i in 0..(64.0_f64 / chunk_section.bits_per_block as f64).ceil() as u8 {
-                    // for i in 0..loop_amount(chunk_section.bits_per_block) {
-                    for i in 0..(64 / chunk_section.bits_per_block) {
-                        let mask = ((1 << chunk_section.bits_per_block) - 1)
-                            << (i * chunk_section.bits_per_block);
-                        let masked_long = (long & mask) >> (i * chunk_section.bits_per_block);
-                        chunk_section
-                            .block_ids_array
-                            .push(match &chunk_section.palette {
-                                Some(pal) => {
-                                    if masked_long as usize >= pal.len() {
-                                        // println!(
-                                        //     "Oh no! {} is out of range of {} long: {:0>64b}",
-                                        //     masked_long,
-                                        //     pal.len(),
-                                        //     long
-                                        // );
-                                        // masked_long as i32
-                                        0
-                                    } else {
-                                        pal[masked_long as usize]
-                                    }
-                                }
-                                None => masked_long as i32,
-                            })
-                    }
-                    // if self.chunk_x == 0 && self.chunk_z == 0 {
-                    //     log::info!("{}", long);
-                    // }
-                }
-                self.data.push(Some(chunk_section));
-            } else {
-                self.data.push(None);
-            }
+        let size = packet.decode_varint().field("size")?;
+        let mut raw_chunk_data = packet.read_packet(size as usize)?;
+        self.data = (0..16)
+            .map(|y| match self.primary_bit_mask & (1 << y) != 0 {
+                true => ChunkSection::decode(&mut raw_chunk_data).map(Some),
+                false => Ok(None),
+            })
+            .collect::<ParseResult<Vec<Option<ChunkSection>>>>()
+            .field("data")?;
+        // The sections have to use up the whole size, anything after them would be lost when it's encoded again.
+        if !raw_chunk_data.is_empty() {
+            return Err(raw_chunk_data.error(ParseErrorKind::InvalidLength(size as i64)))
+                .field("size");
         }
 
-        self.number_of_block_entities = packet.decode_varint()?;
+        self.number_of_block_entities = packet.decode_varint().field("number_of_block_entities")?;
         for _ in 0..self.number_of_block_entities {
-            self.block_entities.push(packet.decode_nbt()?);
+            self.block_entities
+                .push(packet.decode_nbt_blob().field("block_entities")?);
         }
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
+    fn printable_fields(&self) -> String {
         format!(
-            "{} {} {} {} {} {:?} {:?} {:?}",
+            "{} {} {} {:016b} {} block entities",
             self.chunk_x,
             self.chunk_z,
             self.full_chunk,
             self.primary_bit_mask,
-            self.heightmaps,
-            self.biomes,
-            self.data,
-            // make_string_fixed_length(format!("{}", self.heightmaps), 16),
-            // make_string_fixed_length(format!("{:?}", self.biomes), 16),
-            // make_string_fixed_length(format!("{:?}", self.data), 16),
-            self.block_entities
+            self.block_entities.len()
         )
     }
 
-    fn packet_editing(&self) -> bool {
-        true
-    }
-
-    async fn edit_packet(
-        &self,
-        _status: &mut SharedState,
-        _plugins: &mut Vec<Box<dyn EventHandler + Send>>,
-        _config: &Configuration,
-    ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut raw_packet = RawPacket::new();
+    fn encode_fields(&self, raw_packet: &mut RawPacket) -> Result<(), ()> {
         raw_packet.encode_int(self.chunk_x);
         raw_packet.encode_int(self.chunk_z);
         raw_packet.encode_bool(self.full_chunk);
         raw_packet.encode_varint(self.primary_bit_mask);
-        raw_packet.encode_nbt(&self.heightmaps);
+        // Written as a blob, serde would turn the long arrays into lists.
+        raw_packet.encode_nbt_blob(self.heightmaps.clone());
         if self.full_chunk {
             raw_packet.encode_varint(self.biomes_length.unwrap());
             for biome in self.biomes.as_ref().unwrap() {
@@ -198,73 +118,181 @@ impl Parsable for ChunkData {
         }
 
         let mut sections_packet = RawPacket::new();
-
-        for y in 0..16 {
+        for (y, section) in self.data.iter().enumerate().take(16) {
             if self.primary_bit_mask & (1 << y) != 0 {
-                let chunk_data = self.data[y].as_ref().unwrap();
+                section.as_ref().ok_or(())?.encode(&mut sections_packet)?;
+            }
+        }
 
-                let palette = chunk_data.palette.as_ref();
-                sections_packet.encode_short(chunk_data.block_count);
-                sections_packet.encode_ubyte(chunk_data.bits_per_block);
-                if let Some(palette) = palette {
-                    sections_packet.encode_varint(palette.len() as i32);
-                    for palette_id in palette {
-                        sections_packet.encode_varint(palette_id.to_owned());
-                    }
-                }
+        sections_packet.prepend_length();
+        raw_packet.push_vec(sections_packet.get_vec());
 
-                let long_count = 64 * chunk_data.bits_per_block as i32;
+        raw_packet.encode_varint(self.number_of_block_entities);
+        for block_entity in self.block_entities.iter() {
+            raw_packet.encode_nbt_blob(block_entity.clone());
+        }
 
-                // if self.chunk_x == 0 && self.chunk_z == 0 {
-                //     println!("{} {}", long_count, chunk_data.bits_per_block);
-                // }
+        Ok(())
+    }
 
-                sections_packet.encode_varint(long_count);
+    fn fid(&self) -> Option<Fid> {
+        Some(Fid::ChunkData)
+    }
+}
+
+impl Parsable for ChunkData {
+    fn always_parse(&self) -> bool {
+        true
+    }
 
-                let mut block_idx = 0;
-                for _ in 0..long_count {
-                    let mut long = 0;
-                    // for i in 0..(64.0 / chunk_data.bits_per_block as f64).ceil() as u8 {
-                    // for i in 0..loop_amount(chunk_data.bits_per_block) {
-                    for i in 0..64 / chunk_data.bits_per_block {
-                        let block_id = match chunk_data.block_ids_array.get(block_idx) {
-                            Some(block_id) => *block_id,
-                            None => 0,
-                        };
-                        let real_id = match palette {
-                            Some(pal) => (&pal.iter().position(|r| r == &block_id).unwrap())
-                                .to_owned() as i32,
-                            None => block_id,
-                        };
-                        long |= (real_id as i64) << (chunk_data.bits_per_block * i);
-                        block_idx += 1;
-                    }
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.chunks.load(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::BLOCKS;
+
+    // Packs the values like the 1.16 format, without values spanning two longs.
+    fn pack(values: &[u64], bits: u64) -> Vec<u64> {
+        values
+            .chunks((64 / bits) as usize)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |long, (i, value)| long | value << (i as u64 * bits))
+            })
+            .collect()
+    }
 
-                    sections_packet.push_slice(&long.to_be_bytes());
-                }
+    fn encode_section(
+        frame: &mut RawPacket,
+        block_count: i16,
+        bits: u8,
+        palette: Option<&[i32]>,
+        values: &[u64],
+    ) {
+        frame.encode_short(block_count);
+        frame.encode_ubyte(bits);
+        if let Some(palette) = palette {
+            frame.encode_varint(palette.len() as i32);
+            for id in palette {
+                frame.encode_varint(*id);
             }
         }
+        let longs = pack(values, bits as u64);
+        frame.encode_varint(longs.len() as i32);
+        for long in longs {
+            frame.encode_ulong(long);
+        }
+    }
 
-        sections_packet.prepend_length();
-        raw_packet.push_vec(sections_packet.get_vec());
+    // A whole ChunkData like the vanilla server sends it: heightmaps with 37 longs of 9 bits, 1024 biomes,
+    // a section with 4 bits, one with 5 bits (so the top 4 bits of every long are unused), an empty section
+    // that isn't sent, a section with global ids and a block entity. It is written out byte by byte here,
+    // so the section codec isn't used to make it.
+    fn vanilla_frame() -> Vec<u8> {
+        let mut frame = RawPacket::new();
+        frame.encode_int(-3);
+        frame.encode_int(12);
+        frame.encode_bool(true);
+        frame.encode_varint(0b1011);
 
-        raw_packet.encode_varint(self.number_of_block_entities);
-        for block_entity in self.block_entities.iter() {
-            raw_packet.encode_nbt(block_entity);
+        let heights = pack(&[80; 256], 9);
+        assert_eq!(heights.len(), 37);
+        let heights: Vec<i64> = heights.into_iter().map(|long| long as i64).collect();
+        let mut heightmaps = nbt::Blob::new();
+        heightmaps
+            .insert("MOTION_BLOCKING", nbt::Value::LongArray(heights.clone()))
+            .unwrap();
+        heightmaps
+            .insert("WORLD_SURFACE", nbt::Value::LongArray(heights))
+            .unwrap();
+        frame.encode_nbt_blob(heightmaps);
+
+        frame.encode_varint(1024);
+        for _ in 0..1024 {
+            frame.encode_varint(1);
+        }
+
+        let mut sections = RawPacket::new();
+        // Bedrock at the bottom, grass at the top and stone in between.
+        let values: Vec<u64> = (0..4096)
+            .map(|i| match i {
+                0..=255 => 0,
+                3840..=4095 => 2,
+                _ => 1,
+            })
+            .collect();
+        encode_section(&mut sections, 4096, 4, Some(&[33, 1, 9]), &values);
+        let palette: Vec<i32> = (0..17).collect();
+        let values: Vec<u64> = (0..4096).map(|i| i % 17).collect();
+        let block_count = values.iter().filter(|value| **value != 0).count() as i16;
+        encode_section(&mut sections, block_count, 5, Some(&palette), &values);
+        let values: Vec<u64> = (0..4096).map(|i| i * 7 % BLOCKS.len() as u64).collect();
+        encode_section(&mut sections, 4095, 15, None, &values);
+        frame.encode_varint(sections.len() as i32);
+        frame.push_vec(sections.get_vec());
+
+        let mut chest = nbt::Blob::new();
+        chest
+            .insert("id", nbt::Value::String("minecraft:chest".into()))
+            .unwrap();
+        for (name, value) in [("x", -40), ("y", 20), ("z", 200)] {
+            chest.insert(name, nbt::Value::Int(value)).unwrap();
         }
+        frame.encode_varint(1);
+        frame.encode_nbt_blob(chest);
+        frame.get_vec()
+    }
 
-        let mut should_be_same = Self::default();
+    #[test]
+    fn test_chunk_data_round_trip() {
+        let frame = vanilla_frame();
+        let mut chunk_data = ChunkData::default_fields();
+        chunk_data
+            .decode_fields(&mut RawPacket::from(frame.clone()))
+            .unwrap();
 
-        should_be_same.parse_packet(raw_packet.clone()).unwrap();
+        assert_eq!(chunk_data.data.len(), 16);
+        let bottom = chunk_data.data[0].as_ref().unwrap();
+        assert_eq!(bottom.get(0, 0, 0), 33);
+        assert_eq!(bottom.get(5, 8, 5), 1);
+        assert_eq!(bottom.get(15, 15, 15), 9);
+        let second = chunk_data.data[1].as_ref().unwrap();
+        assert_eq!(second.bits_per_block(), 5);
+        assert_eq!(second.get(1, 16, 0), 1);
+        assert!(chunk_data.data[2].is_none());
+        let global = chunk_data.data[3].as_ref().unwrap();
+        assert!(global.palette().is_none());
+        assert_eq!(global.get(3, 48, 0), 21);
+        assert_eq!(chunk_data.block_entities.len(), 1);
 
-        // if self.chunk_x == 0 && self.chunk_z == -1 {
-        //     log::info!("{:?}", self.data);
-        //     log::info!("{:?}", should_be_same.data);
-        // }
+        let mut encoded = RawPacket::new();
+        chunk_data.encode_fields(&mut encoded).unwrap();
+        assert_eq!(encoded.get_vec(), frame);
+    }
 
-        Ok(vec![(
-            Packet::from(raw_packet, fid_to_pid(crate::functions::Fid::ChunkData)),
-            Direction::Clientbound,
-        )])
+    #[test]
+    fn test_chunk_data_leftover_bytes() {
+        // No sections in the mask, but the size says there is a byte of data.
+        let mut frame = RawPacket::new();
+        frame.encode_int(0);
+        frame.encode_int(0);
+        frame.encode_bool(false);
+        frame.encode_varint(0);
+        frame.encode_nbt_blob(nbt::Blob::new());
+        frame.encode_varint(1);
+        frame.encode_ubyte(0);
+        frame.encode_varint(0);
+        let error = ChunkData::default_fields()
+            .decode_fields(&mut frame)
+            .unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidLength(1));
+        assert_eq!(error.field.as_deref(), Some("size"));
     }
 }
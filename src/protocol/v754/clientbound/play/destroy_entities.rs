@@ -0,0 +1,49 @@
+use crate::{
+    conf::Configuration,
+    entities::notify_plugins,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(DestroyEntities)]
+pub struct DestroyEntities {
+    #[prefixed_array]
+    #[varint]
+    pub entity_ids: Vec<i32>,
+}
+
+#[async_trait::async_trait]
+impl Parsable for DestroyEntities {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        for entity_id in &self.entity_ids {
+            status.entities.remove(*entity_id);
+        }
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::DestroyEntities,
+            &self.entity_ids,
+            &status.entities,
+            plugins,
+        ))
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{
+    chat::TextComponent,
+    parsable::{self, Parsable},
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(DisconnectPlay)]
+pub struct DisconnectPlay {
+    pub reason: TextComponent,
+}
+
+impl Parsable for DisconnectPlay {}
@@ -1,40 +1,51 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::{
+    codec::FieldCodec,
+    parsable::{Packet, Parsable},
+    parse_error::ParseResult,
+    raw_packet::RawPacket,
+};
 use serde::Serialize;
 
-#[derive(Clone, Debug, Serialize)]
-enum ScoreboardPosition {
+#[derive(Clone, Debug, Default, Serialize)]
+pub enum ScoreboardPosition {
+    #[default]
     List,
     Sidebar,
     BelowName,
-    TeamSpecificSidebar,
+    // 3 to 18, the team color is the position minus 3.
+    TeamSpecificSidebar(i8),
 }
 
-#[derive(Clone, Serialize)]
-pub struct DisplayScoreboard {
-    position: ScoreboardPosition,
-    name: String,
-}
-
-impl Parsable for DisplayScoreboard {
-    fn default() -> Self {
-        Self {
-            name: String::new(),
-            position: ScoreboardPosition::List,
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.name = packet.decode_string()?;
-        self.position = match packet.decode_byte()? {
+impl FieldCodec for ScoreboardPosition {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        Ok(match packet.decode_byte()? {
             0 => ScoreboardPosition::List,
             1 => ScoreboardPosition::Sidebar,
             2 => ScoreboardPosition::BelowName,
-            _ => ScoreboardPosition::TeamSpecificSidebar,
-        };
+            position => ScoreboardPosition::TeamSpecificSidebar(position - 3),
+        })
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_byte(match self {
+            ScoreboardPosition::List => 0,
+            ScoreboardPosition::Sidebar => 1,
+            ScoreboardPosition::BelowName => 2,
+            ScoreboardPosition::TeamSpecificSidebar(color) => color + 3,
+        });
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!("{:?} {}", self.position, self.name)
+    fn printable(&self) -> String {
+        format!("{:?}", self)
     }
 }
+
+#[derive(Clone, Serialize, Packet)]
+#[packet(DisplayScoreboard)]
+pub struct DisplayScoreboard {
+    pub position: ScoreboardPosition,
+    pub name: String,
+}
+
+impl Parsable for DisplayScoreboard {}
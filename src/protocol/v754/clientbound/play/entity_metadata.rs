@@ -0,0 +1,50 @@
+use crate::{
+    conf::Configuration,
+    entities::notify_plugins,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    types::Metadata,
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EntityMetadata)]
+pub struct EntityMetadata {
+    #[varint]
+    pub entity_id: i32,
+    pub metadata: Metadata,
+}
+
+#[async_trait::async_trait]
+impl Parsable for EntityMetadata {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status
+            .entities
+            .update_metadata(self.entity_id, &self.metadata);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::EntityMetadata,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
+    }
+}
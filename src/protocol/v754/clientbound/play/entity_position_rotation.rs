@@ -0,0 +1,58 @@
+use crate::{
+    conf::Configuration,
+    entities::notify_plugins,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EntityPositionRotation)]
+pub struct EntityPositionRotation {
+    #[varint]
+    pub entity_id: i32,
+    // In 1/4096 of a block.
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+
+#[async_trait::async_trait]
+impl Parsable for EntityPositionRotation {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.entities.move_by(
+            self.entity_id,
+            (self.delta_x, self.delta_y, self.delta_z),
+            self.on_ground,
+        );
+        status.entities.rotate(self.entity_id, self.yaw, self.pitch);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::EntityPositionRotation,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{
+    conf::Configuration,
+    entities::notify_plugins,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+// Sent instead of Entity Position when the entity moved more than 8 blocks.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EntityTeleport)]
+pub struct EntityTeleport {
+    #[varint]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+
+#[async_trait::async_trait]
+impl Parsable for EntityTeleport {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status
+            .entities
+            .teleport(self.entity_id, self.x, self.y, self.z, self.on_ground);
+        status.entities.rotate(self.entity_id, self.yaw, self.pitch);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::EntityTeleport,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    conf::Configuration,
+    entities::notify_plugins,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EntityVelocity)]
+pub struct EntityVelocity {
+    #[varint]
+    pub entity_id: i32,
+    // In 1/8000 of a block per tick.
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+#[async_trait::async_trait]
+impl Parsable for EntityVelocity {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.entities.set_velocity(
+            self.entity_id,
+            (self.velocity_x, self.velocity_y, self.velocity_z),
+        );
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::EntityVelocity,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
+    }
+}
@@ -1,27 +1,33 @@
 use crate::{
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::Fid,
+    metrics,
     packet::Packet,
-    parsable::Parsable,
-    raw_packet::RawPacket,
+    parsable::{self, Parsable},
+    types::World,
     utils::make_string_fixed_length,
     Direction, SharedState,
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(JoinGame)]
 pub struct JoinGame {
     pub player_entity_id: i32,
     pub is_hardcore: bool,
     pub gamemode: u8,
     pub previous_gamemode: i8,
-    pub world_count: i32,
+    #[prefixed_array]
     pub world_names: Vec<String>,
+    #[nbt]
     pub dimension_codec: DimentionCodec,
+    #[nbt]
     pub dimension: DimentionType,
     pub world_name: String,
     pub hashed_seed: i64,
+    #[varint]
     pub max_players: i32,
+    #[varint]
     pub view_distance: i32,
     pub reduced_debug_info: bool,
     pub enable_respawn_screen: bool,
@@ -29,7 +35,7 @@ pub struct JoinGame {
     pub is_flat: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct DimentionCodec {
     #[serde(rename = "minecraft:dimension_type")]
     pub dimension_type_registry: DimentionTypeRegistry,
@@ -37,20 +43,20 @@ pub struct DimentionCodec {
     pub biome_registry: BiomeRegistry,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct DimentionTypeRegistry {
     pub r#type: String,
     pub value: Vec<DimensionTypeRegistryEntry>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct DimensionTypeRegistryEntry {
     pub name: String,
     pub id: i32,
     pub element: DimentionType,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct DimentionType {
     pub piglin_safe: i8,
     pub natural: i8,
@@ -68,20 +74,20 @@ pub struct DimentionType {
     pub has_ceiling: i8,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct BiomeRegistry {
     pub r#type: String,
     pub value: Vec<BiomeRegistryEntry>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct BiomeRegistryEntry {
     pub name: String,
     pub id: i32,
     pub element: BiomeProperties,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct BiomeProperties {
     pub precipitation: String,
     pub depth: f32,
@@ -94,7 +100,7 @@ pub struct BiomeProperties {
     pub particle: Option<Particle>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Effects {
     pub sky_color: i32,
     pub water_fog_color: i32,
@@ -109,7 +115,7 @@ pub struct Effects {
     pub mood_sound: Option<MoodSound>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Music {
     pub replace_current_music: i8,
     pub sound: String,
@@ -117,13 +123,13 @@ pub struct Music {
     pub min_delay: i32,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct AdditionsSound {
     pub sound: String,
     pub tick_chance: f64,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct MoodSound {
     pub sound: String,
     pub tick_delay: i32,
@@ -131,83 +137,31 @@ pub struct MoodSound {
     pub block_search_extent: i32,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Particle {
     pub probability: f32,
     pub options: ParticleOptions,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct ParticleOptions {
     pub r#type: String,
 }
 
 #[async_trait::async_trait]
 impl Parsable for JoinGame {
-    fn default() -> Self {
-        Self {
-            player_entity_id: 0,
-            is_hardcore: false,
-            gamemode: 0,
-            previous_gamemode: 0,
-            world_count: 0,
-            world_names: Vec::new(),
-            dimension_codec: DimentionCodec {
-                dimension_type_registry: DimentionTypeRegistry {
-                    r#type: String::new(),
-                    value: Vec::new(),
-                },
-                biome_registry: BiomeRegistry {
-                    r#type: String::new(),
-                    value: Vec::new(),
-                },
-            },
-            dimension: DimentionType {
-                piglin_safe: 0,
-                natural: 0,
-                ambient_light: 0f32,
-                fixed_time: None,
-                infiniburn: String::new(),
-                respawn_anchor_works: 0,
-                has_skylight: 0,
-                bed_works: 0,
-                effects: String::new(),
-                has_raids: 0,
-                logical_height: 0,
-                coordinate_scale: 0f32,
-                ultrawarm: 0,
-                has_ceiling: 0,
-            },
-            world_name: String::new(),
-            hashed_seed: 0,
-            max_players: 0,
-            view_distance: 0,
-            reduced_debug_info: false,
-            enable_respawn_screen: false,
-            is_debug: false,
-            is_flat: false,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.player_entity_id = packet.decode_int()?;
-        self.is_hardcore = packet.decode_bool()?;
-        self.gamemode = packet.decode_ubyte()?;
-        self.previous_gamemode = packet.decode_byte()?;
-        self.world_count = packet.decode_varint()?;
-        for _ in 0..self.world_count {
-            self.world_names.push(packet.decode_string()?);
-        }
-        self.dimension_codec = packet.decode_nbt()?;
-        self.dimension = packet.decode_nbt()?;
-        self.world_name = packet.decode_identifier()?;
-        self.hashed_seed = packet.decode_long()?;
-        self.max_players = packet.decode_varint()?;
-        self.view_distance = packet.decode_varint()?;
-        self.reduced_debug_info = packet.decode_bool()?;
-        self.enable_respawn_screen = packet.decode_bool()?;
-        self.is_debug = packet.decode_bool()?;
-        self.is_flat = packet.decode_bool()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.world = Some(World {
+            name: self.world_name.clone(),
+            dimension: self.dimension.clone(),
+        });
+        // The client forgets all entities and chunks when the world changes.
+        status.entities.clear();
+        status.chunks.clear();
         Ok(())
     }
 
@@ -242,39 +196,24 @@ impl Parsable for JoinGame {
         plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut join_game_packet: JoinGame = self.to_owned();
+        let mut join_game_packet = None;
 
         for plugin in plugins {
-            match plugin.edit_join_game(&join_game_packet) {
-                Some(new_join_game) => {
-                    join_game_packet = new_join_game;
-                }
-                None => continue,
+            let current = join_game_packet.as_ref().unwrap_or(self);
+            let name = plugin.name();
+            if let Some(new_join_game) =
+                metrics::time_plugin(&Fid::JoinGame, name, || plugin.edit_join_game(current))
+            {
+                join_game_packet = Some(new_join_game);
             }
         }
-        let mut raw_packet = RawPacket::new();
-        raw_packet.encode_int(join_game_packet.player_entity_id);
-        raw_packet.encode_bool(join_game_packet.is_hardcore);
-        raw_packet.encode_ubyte(join_game_packet.gamemode);
-        raw_packet.encode_byte(join_game_packet.previous_gamemode);
-        raw_packet.encode_varint(join_game_packet.world_names.len() as i32);
-        for wn in join_game_packet.world_names {
-            raw_packet.encode_string(wn);
+        // It's only encoded again if a plugin changed it.
+        match join_game_packet {
+            Some(join_game_packet) => Ok(vec![(
+                join_game_packet.encode_packet()?,
+                Direction::Clientbound,
+            )]),
+            None => Ok(vec![]),
         }
-        raw_packet.encode_nbt(join_game_packet.dimension_codec);
-        raw_packet.encode_nbt(join_game_packet.dimension);
-        raw_packet.encode_identifier(join_game_packet.world_name);
-        raw_packet.encode_long(join_game_packet.hashed_seed);
-        raw_packet.encode_varint(join_game_packet.max_players);
-        raw_packet.encode_varint(join_game_packet.view_distance);
-        raw_packet.encode_bool(join_game_packet.reduced_debug_info);
-        raw_packet.encode_bool(join_game_packet.enable_respawn_screen);
-        raw_packet.encode_bool(join_game_packet.is_debug);
-        raw_packet.encode_bool(join_game_packet.is_flat);
-
-        Ok(vec![(
-            Packet::from(raw_packet, fid_to_pid(Fid::JoinGame)),
-            Direction::Clientbound,
-        )])
     }
 }
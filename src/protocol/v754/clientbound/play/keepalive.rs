@@ -1,33 +1,20 @@
 use crate::{
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::serverbound::play::KeepAliveSb,
     packet::Packet,
-    parsable::Parsable,
-    raw_packet::RawPacket,
+    parsable::{self, Parsable},
     Direction, EventHandler, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(KeepAliveCb)]
 pub struct KeepAliveCb {
-    keep_alive_id: i64,
+    pub keep_alive_id: i64,
 }
 
 #[async_trait::async_trait]
 impl Parsable for KeepAliveCb {
-    fn default() -> Self {
-        Self { keep_alive_id: 0 }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.keep_alive_id = packet.decode_long()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{}", self.keep_alive_id,)
-    }
-
     fn packet_editing(&self) -> bool {
         false
     }
@@ -38,29 +25,12 @@ impl Parsable for KeepAliveCb {
         _plugins: &mut Vec<Box<dyn EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        return Ok(vec![
-            (
-                Packet::from(
-                    {
-                        let mut raw_packet = RawPacket::new();
-                        raw_packet.encode_long(self.keep_alive_id);
-                        raw_packet
-                    },
-                    fid_to_pid(Fid::KeepAliveSb),
-                ),
-                Direction::Serverbound,
-            ),
-            (
-                Packet::from(
-                    {
-                        let mut raw_packet = RawPacket::new();
-                        raw_packet.encode_long(self.keep_alive_id);
-                        raw_packet
-                    },
-                    fid_to_pid(Fid::KeepAliveCb),
-                ),
-                Direction::Clientbound,
-            ),
-        ]);
+        let response = KeepAliveSb {
+            keep_alive_id: self.keep_alive_id,
+        };
+        Ok(vec![
+            (response.encode_packet()?, Direction::Serverbound),
+            (self.encode_packet()?, Direction::Clientbound),
+        ])
     }
 }
@@ -63,3 +63,51 @@ pub use set_passenger::*;
 
 mod chunk_data;
 pub use chunk_data::*;
+
+mod disconnect;
+pub use disconnect::*;
+
+mod change_game_state;
+pub use change_game_state::*;
+
+mod remove_entity_effect;
+pub use remove_entity_effect::*;
+
+mod entity_metadata;
+pub use entity_metadata::*;
+
+mod player_position_and_look;
+pub use player_position_and_look::*;
+
+mod respawn;
+pub use respawn::*;
+
+mod entity_position;
+pub use entity_position::*;
+
+mod entity_position_rotation;
+pub use entity_position_rotation::*;
+
+mod entity_teleport;
+pub use entity_teleport::*;
+
+mod entity_velocity;
+pub use entity_velocity::*;
+
+mod destroy_entities;
+pub use destroy_entities::*;
+
+mod player_info;
+pub use player_info::*;
+
+mod player_list_header_footer;
+pub use player_list_header_footer::*;
+
+mod block_change;
+pub use block_change::*;
+
+mod multi_block_change;
+pub use multi_block_change::*;
+
+mod unload_chunk;
+pub use unload_chunk::*;
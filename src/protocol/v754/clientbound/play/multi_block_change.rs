@@ -0,0 +1,113 @@
+use crate::{
+    chunk_cache::notify_plugins,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+// Changes blocks in one chunk section, this is the format since 1.16.2.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(MultiBlockChange)]
+pub struct MultiBlockChange {
+    // The section x and z in 22 bits each and y in 20 bits, like a position.
+    pub section_position: i64,
+    // The inverse of trust edges of the Update Light packet before it.
+    pub inverse_trust_edges: bool,
+    // The state id shifted left by 12, with the x, z and y in the section in the lowest 12 bits.
+    #[prefixed_array]
+    #[varlong]
+    pub blocks: Vec<i64>,
+}
+
+impl MultiBlockChange {
+    pub fn section(&self) -> (i32, i32, i32) {
+        let position = self.section_position;
+        (
+            (position >> 42) as i32,
+            (position << 44 >> 44) as i32,
+            (position << 22 >> 42) as i32,
+        )
+    }
+
+    pub fn set_section(&mut self, (x, y, z): (i32, i32, i32)) {
+        self.section_position =
+            ((x as i64 & 0x3FFFFF) << 42) | ((z as i64 & 0x3FFFFF) << 20) | (y as i64 & 0xFFFFF);
+    }
+
+    // Every changed block with its location in the world and its new state id.
+    pub fn changes(&self) -> Vec<((i32, i32, i32), i32)> {
+        let (section_x, section_y, section_z) = self.section();
+        self.blocks
+            .iter()
+            .map(|block| {
+                let location = (
+                    section_x * 16 + (block >> 8 & 15) as i32,
+                    section_y * 16 + (block & 15) as i32,
+                    section_z * 16 + (block >> 4 & 15) as i32,
+                );
+                (location, (block >> 12) as i32)
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Parsable for MultiBlockChange {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        for ((x, y, z), block_id) in self.changes() {
+            status.chunks.set_block(x, y, z, block_id);
+        }
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        let locations: Vec<(i32, i32, i32)> = self
+            .changes()
+            .into_iter()
+            .map(|(location, _)| location)
+            .collect();
+        Ok(notify_plugins(
+            Fid::MultiBlockChange,
+            &locations,
+            &status.chunks,
+            plugins,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let mut change = MultiBlockChange {
+            section_position: 0,
+            inverse_trust_edges: false,
+            // Stone at x 1, z 2, y 3 and air at x 15, z 15, y 15 of the section.
+            blocks: vec![1 << 12 | 0x123, 0xFFF],
+        };
+        change.set_section((-2, 4, 3));
+        assert_eq!(change.section(), (-2, 4, 3));
+        assert_eq!(
+            change.changes(),
+            vec![((-31, 67, 50), 1), ((-17, 79, 63), 0)]
+        );
+    }
+}
@@ -1,29 +1,21 @@
 use crate::{
-    conf::Configuration, packet::Packet, parsable::Parsable, raw_packet::RawPacket,
-    utils::generate_message_packet, Direction, EventHandler, SharedState,
+    conf::Configuration,
+    packet::Packet,
+    parsable::{self, Parsable},
+    utils::generate_message_packet,
+    Direction, EventHandler, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(OpenBook)]
 pub struct OpenBook {
-    hand: i32,
+    #[varint]
+    pub hand: i32,
 }
 
 #[async_trait::async_trait]
 impl Parsable for OpenBook {
-    fn default() -> Self {
-        Self { hand: 0 }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.hand = packet.decode_varint()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{}", self.hand,)
-    }
-
     fn packet_editing(&self) -> bool {
         false
     }
@@ -1,10 +1,15 @@
 use crate::{
-    conf::Configuration, functions::fid_to_pid, packet::Packet, parsable::Parsable,
-    raw_packet::RawPacket, Direction, SharedState,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    plugin::first_reply,
+    Direction, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerAbilities)]
 pub struct PlayerAbilities {
     pub flags: u8,
     pub flying_speed: f32,
@@ -13,25 +18,6 @@ pub struct PlayerAbilities {
 
 #[async_trait::async_trait]
 impl Parsable for PlayerAbilities {
-    fn default() -> Self {
-        Self {
-            flags: 0,
-            flying_speed: 0f32,
-            fov_modifier: 0f32,
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.flags = packet.decode_ubyte()?;
-        self.flying_speed = packet.decode_float()?;
-        self.fov_modifier = packet.decode_float()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{} {} {}", self.flags, self.flying_speed, self.fov_modifier)
-    }
-
     fn packet_editing(&self) -> bool {
         true
     }
@@ -42,30 +28,8 @@ impl Parsable for PlayerAbilities {
         plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut return_vec = None;
-        for plugin in plugins {
-            match plugin.on_player_abilities(self) {
-                Some(plugin_vec) => {
-                    return_vec = Some(plugin_vec);
-                    break;
-                }
-                None => continue,
-            }
-        }
-        if return_vec.is_none() {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_ubyte(self.flags);
-            raw_packet.encode_float(self.flying_speed);
-            raw_packet.encode_float(self.fov_modifier);
-            return_vec = Some(vec![(
-                Packet::from(
-                    raw_packet,
-                    fid_to_pid(crate::functions::Fid::PlayerAbilities),
-                ),
-                Direction::Clientbound,
-            )]);
-        }
-
-        Ok(return_vec.unwrap())
+        Ok(first_reply(Fid::PlayerAbilities, plugins, |plugin| {
+            plugin.on_player_abilities(self)
+        }))
     }
 }
@@ -0,0 +1,176 @@
+use crate::{
+    chat::TextComponent,
+    codec::FieldCodec,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    parse_error::{ParseError, ParseErrorKind, ParseResult},
+    raw_packet::RawPacket,
+    tab_list::notify_plugins,
+    types::Uuid,
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+// Changes the tab list. Every player in the packet gets the same action, which sets what is sent for them.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerInfo)]
+pub struct PlayerInfo {
+    pub action: PlayerInfoAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum PlayerInfoAction {
+    AddPlayer(Vec<PlayerInfoAdd>),
+    UpdateGamemode(Vec<PlayerInfoGamemode>),
+    UpdateLatency(Vec<PlayerInfoLatency>),
+    UpdateDisplayName(Vec<PlayerInfoDisplayName>),
+    RemovePlayer(Vec<Uuid>),
+}
+
+impl Default for PlayerInfoAction {
+    fn default() -> Self {
+        PlayerInfoAction::AddPlayer(vec![])
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, parsable::Packet)]
+pub struct PlayerInfoAdd {
+    pub uuid: Uuid,
+    pub name: String,
+    #[prefixed_array]
+    pub properties: Vec<PlayerProperty>,
+    #[varint]
+    pub gamemode: i32,
+    // In milliseconds.
+    #[varint]
+    pub ping: i32,
+    pub display_name: Option<TextComponent>,
+}
+
+// Like the skin of the player, with the signature from Mojang if it is signed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, parsable::Packet)]
+pub struct PlayerProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, parsable::Packet)]
+pub struct PlayerInfoGamemode {
+    pub uuid: Uuid,
+    #[varint]
+    pub gamemode: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, parsable::Packet)]
+pub struct PlayerInfoLatency {
+    pub uuid: Uuid,
+    #[varint]
+    pub ping: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, parsable::Packet)]
+pub struct PlayerInfoDisplayName {
+    pub uuid: Uuid,
+    pub display_name: Option<TextComponent>,
+}
+
+fn decode_players<T: FieldCodec>(packet: &mut RawPacket) -> ParseResult<Vec<T>> {
+    let count = packet.decode_varint()?;
+    (0..count).map(|_| T::decode(packet)).collect()
+}
+
+fn encode_players<T: FieldCodec>(players: &[T], packet: &mut RawPacket) -> Result<(), ()> {
+    packet.encode_varint(players.len() as i32);
+    players.iter().try_for_each(|player| player.encode(packet))
+}
+
+impl FieldCodec for PlayerInfoAction {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        let start = packet.position();
+        Ok(match packet.decode_varint()? {
+            0 => PlayerInfoAction::AddPlayer(decode_players(packet)?),
+            1 => PlayerInfoAction::UpdateGamemode(decode_players(packet)?),
+            2 => PlayerInfoAction::UpdateLatency(decode_players(packet)?),
+            3 => PlayerInfoAction::UpdateDisplayName(decode_players(packet)?),
+            4 => PlayerInfoAction::RemovePlayer(decode_players(packet)?),
+            other => {
+                return Err(ParseError::new(
+                    ParseErrorKind::InvalidValue(other as i64),
+                    start,
+                ))
+            }
+        })
+    }
+
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        match self {
+            PlayerInfoAction::AddPlayer(players) => {
+                packet.encode_varint(0);
+                encode_players(players, packet)
+            }
+            PlayerInfoAction::UpdateGamemode(players) => {
+                packet.encode_varint(1);
+                encode_players(players, packet)
+            }
+            PlayerInfoAction::UpdateLatency(players) => {
+                packet.encode_varint(2);
+                encode_players(players, packet)
+            }
+            PlayerInfoAction::UpdateDisplayName(players) => {
+                packet.encode_varint(3);
+                encode_players(players, packet)
+            }
+            PlayerInfoAction::RemovePlayer(players) => {
+                packet.encode_varint(4);
+                encode_players(players, packet)
+            }
+        }
+    }
+
+    fn printable(&self) -> String {
+        match self {
+            PlayerInfoAction::AddPlayer(players) => format!(
+                "add {}",
+                players
+                    .iter()
+                    .map(|player| player.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ),
+            PlayerInfoAction::UpdateGamemode(players) => format!("gamemode {}", players.len()),
+            PlayerInfoAction::UpdateLatency(players) => format!("latency {}", players.len()),
+            PlayerInfoAction::UpdateDisplayName(players) => {
+                format!("display name {}", players.len())
+            }
+            PlayerInfoAction::RemovePlayer(players) => format!("remove {}", players.len()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Parsable for PlayerInfo {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.tab_list.apply(&self.action);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(Fid::PlayerInfo, &status.tab_list, plugins))
+    }
+}
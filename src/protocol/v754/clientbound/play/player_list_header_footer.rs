@@ -0,0 +1,48 @@
+use crate::{
+    chat::TextComponent,
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    tab_list::notify_plugins,
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+// The text above and below the tab list, an empty text removes it.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerListHeaderFooter)]
+pub struct PlayerListHeaderFooter {
+    pub header: TextComponent,
+    pub footer: TextComponent,
+}
+
+#[async_trait::async_trait]
+impl Parsable for PlayerListHeaderFooter {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.tab_list.header = self.header.clone();
+        status.tab_list.footer = self.footer.clone();
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::PlayerListHeaderFooter,
+            &status.tab_list,
+            plugins,
+        ))
+    }
+}
@@ -1,62 +1,81 @@
 use crate::{
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    plugin::first_reply,
+    types::Location,
+    Direction, SharedState,
 };
-use crate::{packet::Packet, parsable::Parsable, raw_packet::RawPacket};
-use crate::{Direction, SharedState};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+// The server moves the player, the client has to answer with a TeleportConfirm.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerPositionAndLook)]
 pub struct PlayerPositionAndLook {
-    x: f64,
-    y: f64,
-    z: f64,
-    yaw: f32,
-    pitch: f32,
-    flags: i8,
-    teleport_id: i32,
-    dismount_vehicle: bool,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    // Every field with its bit set is relative to the current location instead of absolute.
+    pub flags: u8,
+    #[varint]
+    pub teleport_id: i32,
+    pub dismount_vehicle: bool,
 }
 
-#[async_trait::async_trait]
-impl Parsable for PlayerPositionAndLook {
-    fn empty() -> Self {
-        Self {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            yaw: 0.0,
-            pitch: 0.0,
-            flags: 0,
-            teleport_id: 0,
-            dismount_vehicle: false,
+impl PlayerPositionAndLook {
+    pub const RELATIVE_X: u8 = 0x01;
+    pub const RELATIVE_Y: u8 = 0x02;
+    pub const RELATIVE_Z: u8 = 0x04;
+    pub const RELATIVE_YAW: u8 = 0x08;
+    pub const RELATIVE_PITCH: u8 = 0x10;
+
+    // Where the player ends up when they were at `from` before the teleport.
+    pub fn apply(&self, from: Location) -> Location {
+        let relative = |flag: u8| self.flags & flag != 0;
+        Location {
+            x: if relative(Self::RELATIVE_X) {
+                from.x + self.x
+            } else {
+                self.x
+            },
+            y: if relative(Self::RELATIVE_Y) {
+                from.y + self.y
+            } else {
+                self.y
+            },
+            z: if relative(Self::RELATIVE_Z) {
+                from.z + self.z
+            } else {
+                self.z
+            },
+            yaw: if relative(Self::RELATIVE_YAW) {
+                from.yaw + self.yaw
+            } else {
+                self.yaw
+            },
+            pitch: if relative(Self::RELATIVE_PITCH) {
+                from.pitch + self.pitch
+            } else {
+                self.pitch
+            },
         }
     }
+}
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.x = packet.decode_double()?;
-        self.y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.yaw = packet.decode_float()?;
-        self.pitch = packet.decode_float()?;
-        self.flags = packet.decode_byte()?;
-        self.teleport_id = packet.decode_varint()?;
-        self.dismount_vehicle = packet.decode_bool()?;
-        Ok(())
+#[async_trait::async_trait]
+impl Parsable for PlayerPositionAndLook {
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {} {} {} {}",
-            self.x,
-            self.y,
-            self.z,
-            self.yaw,
-            self.pitch,
-            self.flags,
-            self.teleport_id,
-            self.dismount_vehicle
-        )
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.location = Some(self.apply(status.location.unwrap_or_default()));
+        // The server ignores the movement of the client until it confirmed the latest teleport.
+        status.pending_teleport = Some(self.teleport_id);
+        Ok(())
     }
 
     fn packet_editing(&self) -> bool {
@@ -65,27 +84,54 @@ impl Parsable for PlayerPositionAndLook {
 
     async fn edit_packet(
         &self,
-        _status: &mut SharedState,
-        _plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut return_packet_vec = Vec::new();
-
-        let mut raw_packet = RawPacket::new();
-        raw_packet.encode_double(self.x);
-        raw_packet.encode_double(self.y);
-        raw_packet.encode_double(self.z);
-        raw_packet.encode_float(self.yaw);
-        raw_packet.encode_float(self.pitch);
-        raw_packet.encode_byte(self.flags);
-        raw_packet.encode_varint(self.teleport_id);
-        raw_packet.encode_bool(false);
+        let location = status.location.ok_or(())?;
+        Ok(first_reply(Fid::PlayerPositionAndLook, plugins, |plugin| {
+            plugin.on_teleport(&location)
+        }))
+    }
+}
 
-        return_packet_vec.push((
-            Packet::from(raw_packet, fid_to_pid(Fid::PlayerPositionAndLook)),
-            Direction::Serverbound,
-        ));
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(return_packet_vec)
+    #[test]
+    fn test_relative_teleport() {
+        let mut status = SharedState::new();
+        status.location = Some(Location {
+            x: 10.0,
+            y: 64.0,
+            z: -5.0,
+            yaw: 90.0,
+            pitch: 0.0,
+        });
+        let teleport = PlayerPositionAndLook {
+            x: 1.5,
+            y: 70.0,
+            z: -1.0,
+            yaw: 10.0,
+            pitch: 45.0,
+            flags: PlayerPositionAndLook::RELATIVE_X
+                | PlayerPositionAndLook::RELATIVE_Z
+                | PlayerPositionAndLook::RELATIVE_YAW,
+            teleport_id: 3,
+            dismount_vehicle: false,
+        };
+        teleport.update_status(&mut status).unwrap();
+        assert_eq!(
+            status.location,
+            Some(Location {
+                x: 11.5,
+                y: 70.0,
+                z: -6.0,
+                yaw: 100.0,
+                pitch: 45.0,
+            })
+        );
+        assert_eq!(status.pending_teleport, Some(3));
     }
 }
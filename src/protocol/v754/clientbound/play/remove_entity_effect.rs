@@ -0,0 +1,12 @@
+use crate::parsable::{Packet, Parsable};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, Packet)]
+#[packet(RemoveEntityEffect)]
+pub struct RemoveEntityEffect {
+    #[varint]
+    pub entity_id: i32,
+    pub effect_id: u8,
+}
+
+impl Parsable for RemoveEntityEffect {}
@@ -1,27 +1,11 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(ResourcePackSend)]
 pub struct ResourcePackSend {
-    url: String,
-    hash: String,
+    pub url: String,
+    pub hash: String,
 }
 
-impl Parsable for ResourcePackSend {
-    fn default() -> Self {
-        Self {
-            url: String::new(),
-            hash: String::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.url = packet.decode_string()?;
-        self.hash = packet.decode_string()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{} {}", self.url, self.hash,)
-    }
-}
+impl Parsable for ResourcePackSend {}
@@ -0,0 +1,78 @@
+use crate::{
+    conf::Configuration,
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    plugin::first_reply,
+    types::World,
+    Direction, SharedState,
+};
+use serde::Serialize;
+
+use super::DimentionType;
+
+// Sent when the player changes dimension, or when the server moves them to another world.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(Respawn)]
+pub struct Respawn {
+    #[nbt]
+    pub dimension: DimentionType,
+    pub world_name: String,
+    pub hashed_seed: i64,
+    pub gamemode: u8,
+    pub previous_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    // Whether the client keeps the metadata of the player (false when respawning after dying).
+    pub copy_metadata: bool,
+}
+
+#[async_trait::async_trait]
+impl Parsable for Respawn {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.world = Some(World {
+            name: self.world_name.clone(),
+            dimension: self.dimension.clone(),
+        });
+        // The client forgets all entities and chunks when the world changes.
+        status.entities.clear();
+        status.chunks.clear();
+        log::debug!("World changed to {}", self.world_name);
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        _status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(first_reply(Fid::Respawn, plugins, |plugin| {
+            plugin.on_respawn(self)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respawn_updates_world() {
+        let mut status = SharedState::new();
+        let respawn = Respawn {
+            world_name: "minecraft:the_nether".into(),
+            ..Respawn::default()
+        };
+        respawn.update_status(&mut status).unwrap();
+        assert_eq!(status.world.unwrap().name, "minecraft:the_nether");
+    }
+}
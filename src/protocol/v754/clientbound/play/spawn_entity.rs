@@ -1,71 +1,72 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket, types::Uuid};
+use crate::{
+    conf::Configuration,
+    entities::{notify_plugins, Entity, EntityType},
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    types::Uuid,
+    Direction, SharedState,
+};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(SpawnEntity)]
 pub struct SpawnEntity {
-    entity_id: i32,
-    object_uuid: Uuid,
-    r#type: i32,
-    x: f64,
-    y: f64,
-    z: f64,
-    pitch: u8,
-    yaw: u8,
-    data: i32,
-    velocity_x: i16,
-    velocity_y: i16,
-    velocity_z: i16,
+    #[varint]
+    pub entity_id: i32,
+    pub object_uuid: Uuid,
+    #[varint]
+    pub r#type: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: u8,
+    pub yaw: u8,
+    pub data: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
 }
 
+#[async_trait::async_trait]
 impl Parsable for SpawnEntity {
-    fn default() -> Self {
-        Self {
-            entity_id: 0,
-            object_uuid: Uuid::from(0),
-            r#type: 0,
-            x: 0f64,
-            y: 0f64,
-            z: 0f64,
-            pitch: 0,
-            yaw: 0,
-            data: 0,
-            velocity_x: 0,
-            velocity_y: 0,
-            velocity_z: 0,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.entity_id = packet.decode_varint()?;
-        self.object_uuid = packet.decode_uuid()?;
-        self.r#type = packet.decode_varint()?;
-        self.x = packet.decode_double()?;
-        self.y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.pitch = packet.read(1)?[0];
-        self.yaw = packet.read(1)?[0];
-        self.data = packet.decode_int()?;
-        self.velocity_x = packet.decode_short()?;
-        self.velocity_y = packet.decode_short()?;
-        self.velocity_z = packet.decode_short()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {} {} {} {} {} {} {} {}",
-            self.entity_id,
-            self.object_uuid,
-            self.r#type,
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        let mut entity = Entity::new(
+            EntityType::Registry(self.r#type),
+            Some(self.object_uuid.clone()),
             self.x,
             self.y,
             self.z,
-            self.pitch,
-            self.yaw,
-            self.data,
-            self.velocity_x,
-            self.velocity_y,
-            self.velocity_z
-        )
+        );
+        entity.yaw = self.yaw;
+        entity.pitch = self.pitch;
+        status.entities.spawn(self.entity_id, entity);
+        status.entities.set_velocity(
+            self.entity_id,
+            (self.velocity_x, self.velocity_y, self.velocity_z),
+        );
+        Ok(())
+    }
+
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::SpawnEntity,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
     }
 }
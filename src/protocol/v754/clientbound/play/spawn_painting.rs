@@ -1,60 +1,72 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket, types::Uuid};
+use crate::{
+    conf::Configuration,
+    entities::{notify_plugins, Entity, EntityType},
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    types::Uuid,
+    Direction, SharedState,
+};
 use serde::Serialize;
 
-#[derive(Clone, Debug, Serialize)]
-enum FacingDirection {
-    North,
-    South,
-    East,
-    West,
+#[derive(Clone, Debug, Default, Serialize, parsable::Packet)]
+#[packet(ubyte)]
+pub enum FacingDirection {
+    South = 0,
+    West = 1,
+    #[default]
+    North = 2,
+    East = 3,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(SpawnPainting)]
 pub struct SpawnPainting {
-    entity_id: i32,
-    object_uuid: Uuid,
-    motive: i32,
-    x: i32,
-    y: i32,
-    z: i32,
-    direction: FacingDirection,
+    #[varint]
+    pub entity_id: i32,
+    pub object_uuid: Uuid,
+    #[varint]
+    pub motive: i32,
+    #[position]
+    pub location: (i32, i32, i32),
+    pub direction: FacingDirection,
 }
 
+#[async_trait::async_trait]
 impl Parsable for SpawnPainting {
-    fn default() -> Self {
-        Self {
-            entity_id: 0,
-            object_uuid: Uuid::from(0),
-            motive: 0,
-            x: 0,
-            y: 0,
-            z: 0,
-            direction: FacingDirection::North,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.entity_id = packet.decode_varint()?;
-        self.object_uuid = packet.decode_uuid()?;
-        self.motive = packet.decode_varint()?;
-        let position = packet.decode_position()?;
-        self.x = position.0;
-        self.y = position.1;
-        self.z = position.2;
-        match packet.read(1)?[0] {
-            0x00 => self.direction = FacingDirection::South,
-            0x01 => self.direction = FacingDirection::West,
-            0x02 => self.direction = FacingDirection::North,
-            0x03 => self.direction = FacingDirection::East,
-            _ => return Err(()),
-        }
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        // Paintings are placed on a block, the position is the block they hang in.
+        let (x, y, z) = self.location;
+        let entity = Entity::new(
+            EntityType::Painting,
+            Some(self.object_uuid.clone()),
+            x as f64,
+            y as f64,
+            z as f64,
+        );
+        status.entities.spawn(self.entity_id, entity);
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {} {} {:?}",
-            self.entity_id, self.object_uuid, self.motive, self.x, self.y, self.z, self.direction,
-        )
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::SpawnPainting,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
     }
 }
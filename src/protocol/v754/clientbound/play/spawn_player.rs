@@ -1,45 +1,62 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket, types::Uuid};
+use crate::{
+    conf::Configuration,
+    entities::{notify_plugins, Entity, EntityType},
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    types::Uuid,
+    Direction, SharedState,
+};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(SpawnPlayer)]
 pub struct SpawnPlayer {
-    entity_id: i32,
-    player_uuid: Uuid,
-    x: f64,
-    y: f64,
-    z: f64,
-    yaw: u8,
-    pitch: u8,
+    #[varint]
+    pub entity_id: i32,
+    pub player_uuid: Uuid,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: u8,
+    pub pitch: u8,
 }
 
+#[async_trait::async_trait]
 impl Parsable for SpawnPlayer {
-    fn default() -> Self {
-        Self {
-            entity_id: 0,
-            player_uuid: Uuid::from(0),
-            x: 0f64,
-            y: 0f64,
-            z: 0f64,
-            yaw: 0,
-            pitch: 0,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.entity_id = packet.decode_varint()?;
-        self.player_uuid = packet.decode_uuid()?;
-        self.x = packet.decode_double()?;
-        self.y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.yaw = packet.read(1)?[0];
-        self.pitch = packet.read(1)?[0];
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        let mut entity = Entity::new(
+            EntityType::Player,
+            Some(self.player_uuid.clone()),
+            self.x,
+            self.y,
+            self.z,
+        );
+        entity.yaw = self.yaw;
+        entity.pitch = self.pitch;
+        status.entities.spawn(self.entity_id, entity);
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {} {} {}",
-            self.entity_id, self.player_uuid, self.x, self.y, self.z, self.yaw, self.pitch,
-        )
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::SpawnPlayer,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
     }
 }
@@ -1,39 +1,51 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::{
+    conf::Configuration,
+    entities::{notify_plugins, Entity, EntityType},
+    functions::Fid,
+    packet::Packet,
+    parsable::{self, Parsable},
+    Direction, SharedState,
+};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(SpawnXpOrb)]
 pub struct SpawnXpOrb {
-    entity_id: i32,
-    x: f64,
-    y: f64,
-    z: f64,
-    count: i16,
+    #[varint]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub count: i16,
 }
 
+#[async_trait::async_trait]
 impl Parsable for SpawnXpOrb {
-    fn default() -> Self {
-        Self {
-            entity_id: 0,
-            x: 0f64,
-            y: 0f64,
-            z: 0f64,
-            count: 0,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.entity_id = packet.decode_varint()?;
-        self.x = packet.decode_double()?;
-        self.y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.count = packet.decode_short()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        let entity = Entity::new(EntityType::ExperienceOrb, None, self.x, self.y, self.z);
+        status.entities.spawn(self.entity_id, entity);
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {}",
-            self.entity_id, self.x, self.y, self.z, self.count
-        )
+    fn packet_editing(&self) -> bool {
+        true
+    }
+
+    async fn edit_packet(
+        &self,
+        status: &mut SharedState,
+        plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
+        _config: &Configuration,
+    ) -> Result<Vec<(Packet, Direction)>, ()> {
+        Ok(notify_plugins(
+            Fid::SpawnXpOrb,
+            &[self.entity_id],
+            &status.entities,
+            plugins,
+        ))
     }
 }
@@ -1,8 +1,10 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use serde::Serialize;
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
-enum TeamMode {
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Packet)]
+#[packet(byte)]
+pub enum TeamMode {
+    #[default]
     Create,
     Remove,
     Update,
@@ -10,99 +12,41 @@ enum TeamMode {
     RemoveEntities,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(Teams)]
 pub struct Teams {
-    team_name: String,
-    mode: TeamMode,
-    team_display_name: String,
-    friendly_flags: i8,
-    name_tag_visibility: String,
-    collision_rule: String,
-    team_color: i32,
-    team_prefix: String,
-    team_suffix: String,
-    entity_count: i32,
-    entities: Vec<String>,
+    pub team_name: String,
+    pub mode: TeamMode,
+    // The team info is only sent when the team is created or updated.
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub team_display_name: Option<String>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub friendly_flags: Option<i8>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub name_tag_visibility: Option<String>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub collision_rule: Option<String>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    #[varint]
+    pub team_color: Option<i32>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub team_prefix: Option<String>,
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::Update))]
+    pub team_suffix: Option<String>,
+    // And the entities when they change, which includes creating the team.
+    #[optional(if = matches!(self.mode, TeamMode::Create | TeamMode::AddEntities | TeamMode::RemoveEntities))]
+    #[prefixed_array]
+    pub entities: Option<Vec<String>>,
 }
 
 impl Parsable for Teams {
-    fn default() -> Self {
-        Self {
-            team_name: String::new(),
-            mode: TeamMode::Create,
-            team_display_name: String::new(),
-            friendly_flags: 0,
-            name_tag_visibility: String::new(),
-            collision_rule: String::new(),
-            team_color: 0,
-            team_prefix: String::new(),
-            team_suffix: String::new(),
-            entity_count: 0,
-            entities: Vec::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.team_name = packet.decode_string()?;
-        self.mode = match packet.decode_byte()? {
-            0 => {
-                self.team_display_name = packet.decode_string()?;
-                self.friendly_flags = packet.decode_byte()?;
-                self.name_tag_visibility = packet.decode_string()?;
-                self.collision_rule = packet.decode_string()?;
-                self.team_color = packet.decode_varint()?;
-                self.team_prefix = packet.decode_string()?;
-                self.team_suffix = packet.decode_string()?;
-                self.entity_count = packet.decode_varint()?;
-                for _ in 0..self.entity_count {
-                    self.entities.push(packet.decode_string()?);
-                }
-                TeamMode::Create
-            }
-            1 => TeamMode::Remove,
-            2 => {
-                self.team_display_name = packet.decode_string()?;
-                self.friendly_flags = packet.decode_byte()?;
-                self.name_tag_visibility = packet.decode_string()?;
-                self.collision_rule = packet.decode_string()?;
-                self.team_color = packet.decode_varint()?;
-                self.team_prefix = packet.decode_string()?;
-                self.team_suffix = packet.decode_string()?;
-                TeamMode::Update
-            }
-            3 => {
-                self.entity_count = packet.decode_varint()?;
-                for _ in 0..self.entity_count {
-                    self.entities.push(packet.decode_string()?);
-                }
-                TeamMode::AddEntities
-            }
-            4 => {
-                self.entity_count = packet.decode_varint()?;
-                for _ in 0..self.entity_count {
-                    self.entities.push(packet.decode_string()?);
-                }
-                TeamMode::RemoveEntities
-            }
-            _ => return Err(()),
-        };
-        Ok(())
-    }
-
     fn get_printable(&self) -> String {
         if self.mode == TeamMode::Update {
             format!(
                 "{} {} {}",
                 self.team_name,
-                // self.mode,
-                // self.team_display_name,
-                // self.friendly_flags,
-                // self.name_tag_visibility,
-                // self.collision_rule,
-                // self.team_color,
-                self.team_prefix,
-                self.team_suffix,
-                // self.entities,
+                self.team_prefix.as_deref().unwrap_or_default(),
+                self.team_suffix.as_deref().unwrap_or_default(),
             )
         } else {
             String::new()
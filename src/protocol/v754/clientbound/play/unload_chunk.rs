@@ -0,0 +1,23 @@
+use crate::{
+    parsable::{Packet, Parsable},
+    SharedState,
+};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, Packet)]
+#[packet(UnloadChunk)]
+pub struct UnloadChunk {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
+
+impl Parsable for UnloadChunk {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.chunks.unload(self.chunk_x, self.chunk_z);
+        Ok(())
+    }
+}
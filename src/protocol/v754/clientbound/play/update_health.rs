@@ -1,38 +1,27 @@
 use crate::{
-    conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    conf::Configuration, functions::serverbound::play::ChatMessageServerbound,
     utils::generate_message_packet,
 };
-use crate::{packet::Packet, parsable::Parsable, raw_packet::RawPacket};
+use crate::{
+    packet::Packet,
+    parsable::{self, Parsable},
+};
 use crate::{Direction, SharedState};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(UpdateHealth)]
 pub struct UpdateHealth {
-    health: f32,
-    food: i32,
-    food_saturation: f32,
+    pub health: f32,
+    #[varint]
+    pub food: i32,
+    pub food_saturation: f32,
 }
 
 #[async_trait::async_trait]
 impl Parsable for UpdateHealth {
-    fn default() -> Self {
-        Self {
-            health: 0f32,
-            food: 0,
-            food_saturation: 0f32,
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.health = packet.decode_float()?;
-        self.food = packet.decode_varint()?;
-        self.food_saturation = packet.decode_float()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{} {} {}", self.health, self.food, self.food_saturation)
+    fn always_parse(&self) -> bool {
+        true
     }
 
     fn packet_editing(&self) -> bool {
@@ -51,12 +40,10 @@ impl Parsable for UpdateHealth {
                 generate_message_packet("Sent /eat command").unwrap(),
                 Direction::Clientbound,
             ));
-            let mut eat_command = RawPacket::new();
-            eat_command.encode_string("/eat".to_string());
-            return_packet_vec.push((
-                Packet::from(eat_command, fid_to_pid(Fid::ChatMessageServerbound)),
-                Direction::Serverbound,
-            ));
+            let eat_command = ChatMessageServerbound {
+                message: "/eat".to_string(),
+            };
+            return_packet_vec.push((eat_command.encode_packet()?, Direction::Serverbound));
         };
 
         Ok(return_packet_vec)
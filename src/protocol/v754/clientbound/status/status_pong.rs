@@ -1,24 +1,16 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use crate::{SharedState, State};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(StatusPong)]
 pub struct StatusPong {
-    payload: i64,
+    pub payload: i64,
 }
 
 impl Parsable for StatusPong {
-    fn default() -> Self {
-        Self { payload: 0 }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.payload = packet.decode_long()?;
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{}", self.payload)
+    fn always_parse(&self) -> bool {
+        true
     }
 
     fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
@@ -1,24 +1,43 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
-use serde::Serialize;
+use crate::{
+    chat::TextComponent,
+    codec::FieldCodec,
+    parsable::{Packet, Parsable},
+    parse_error::{ParseError, ParseErrorKind, ParseResult},
+    raw_packet::RawPacket,
+};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize)]
-pub struct StatusResponse {
-    json_response: String,
+// Only the description is typed, the rest (version, players, favicon and whatever mods add) is kept as it is.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    #[serde(default)]
+    pub description: TextComponent,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
 }
 
-impl Parsable for StatusResponse {
-    fn default() -> Self {
-        Self {
-            json_response: String::new(),
-        }
+impl FieldCodec for ServerStatus {
+    fn decode(packet: &mut RawPacket) -> ParseResult<Self> {
+        let start = packet.position();
+        let json = packet.decode_string()?;
+        serde_json::from_str(&json)
+            .map_err(|why| ParseError::new(ParseErrorKind::InvalidJson(why.to_string()), start))
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.json_response = packet.decode_string()?;
+    fn encode(&self, packet: &mut RawPacket) -> Result<(), ()> {
+        packet.encode_string(serde_json::to_string(self).map_err(|_| ())?);
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        self.json_response.to_string()
+    fn printable(&self) -> String {
+        self.description.to_plain()
     }
 }
+
+#[derive(Clone, Serialize, Packet)]
+#[packet(StatusResponse)]
+pub struct StatusResponse {
+    pub status: ServerStatus,
+}
+
+impl Parsable for StatusResponse {}
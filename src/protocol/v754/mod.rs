@@ -6,7 +6,9 @@ pub mod serverbound;
 use self::clientbound as cb;
 use self::serverbound as sb;
 
+use lazy_static::lazy_static;
 use maplit::hashmap;
+use serde::{de, Deserialize};
 
 use crate::{
     parsable::Parsable,
@@ -58,60 +60,121 @@ pub enum Fid {
     SetPassenger,
     SteerVehicle,
     EntityAction,
-    // PlayerPositionAndLook,
+    PlayerPositionAndLook,
     ChunkData,
     PlayerBlockPlace,
+    DisconnectPlay,
+    ChangeGameState,
+    RemoveEntityEffect,
+    UseItem,
+    EntityMetadata,
+    Respawn,
+    TeleportConfirm,
+    EntityPosition,
+    EntityPositionRotation,
+    DestroyEntities,
+    EntityVelocity,
+    EntityTeleport,
+    PlayerInfo,
+    PlayerListHeaderFooter,
+    BlockChange,
+    UnloadChunk,
+    MultiBlockChange,
+}
+
+// The packet ids are in packets.json, by protocol version, state, direction and name.
+// The names are the Fid names, the file can have more packets than there are Fids.
+const PACKET_IDS: &str = include_str!("../../../packets.json");
+const PROTOCOL_VERSION: &str = "754";
+
+type PacketIdFile = HashMap<String, HashMap<State, HashMap<Direction, PacketNames>>>;
+
+// The names and ids of the packets of one state and direction, in the order of the file.
+// A HashMap would keep only the last of two packets with the same name, so a copy-paste mistake would go unnoticed.
+struct PacketNames(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for PacketNames {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = PacketNames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map of packet names to ids")
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<PacketNames, A::Error> {
+                let mut names: Vec<(String, String)> = Vec::new();
+                while let Some((name, id)) = map.next_entry::<String, String>()? {
+                    if names.iter().any(|(other, _)| *other == name) {
+                        return Err(de::Error::custom(format!("{} is in there twice", name)));
+                    }
+                    names.push((name, id));
+                }
+                Ok(PacketNames(names))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+lazy_static! {
+    static ref PIDS: HashMap<Fid, i32> = load_packet_ids(PACKET_IDS).unwrap();
 }
 
 pub fn fid_to_pid(fid: Fid) -> i32 {
     match fid {
         Fid::Unparsable => -1,
-        Fid::Handshake => 0x00,
-        Fid::StatusResponse => 0x00,
-        Fid::StatusPong => 0x01,
-        Fid::StatusRequest => 0x00,
-        Fid::StatusPing => 0x01,
-        Fid::Disconnect => 0x00,
-        Fid::EncRequest => 0x01,
-        Fid::LoginSuccess => 0x02,
-        Fid::SetCompression => 0x03,
-        Fid::PluginRequest => 0x04,
-        Fid::LoginStart => 0x00,
-        Fid::EncResponse => 0x01,
-        Fid::PluginResponse => 0x02,
-        Fid::SpawnEntity => 0x00,
-        Fid::SpawnXpOrb => 0x01,
-        Fid::SpawnLivingEntity => 0x02,
-        Fid::SpawnPainting => 0x03,
-        Fid::SpawnPlayer => 0x04,
-        Fid::AckPlayerDigging => 0x07,
-        Fid::ChatMessageClientbound => 0x0E,
-        Fid::TabCompleteClientbound => 0x0F,
-        Fid::ChatMessageServerbound => 0x03,
-        Fid::ResourcePackSend => 0x38,
-        Fid::ClientSettings => 0x05,
-        Fid::UpdateHealth => 0x49,
-        Fid::PlayerPosition => 0x12,
-        Fid::PlayerPositionRotation => 0x13,
-        Fid::PlayerAbilities => 0x30,
-        Fid::KeepAliveCb => 0x1F,
-        Fid::KeepAliveSb => 0x10,
-        Fid::UpdateScore => 0x4D,
-        Fid::DisplayScoreboard => 0x43,
-        Fid::ScoreboardObjective => 0x4A,
-        Fid::Teams => 0x4C,
-        Fid::ResourcePackStatus => 0x21,
-        Fid::EntityEffect => 0x59,
-        Fid::JoinGame => 0x24,
-        Fid::OpenBook => 0x2C,
-        Fid::WindowItems => 0x13,
-        Fid::SetPassenger => 0x4B,
-        Fid::SteerVehicle => 0x1D,
-        Fid::EntityAction => 0x1C,
-        Fid::ChunkData => 0x20,
-        Fid::PlayerBlockPlace => 0x2E,
-        // Fid::PlayerPositionAndLook => 0x38,
+        fid => PIDS[&fid],
+    }
+}
+
+// This is called at startup, so a broken packets.json stops the proxy before the first packet instead of during it.
+pub fn check_packet_ids() -> Result<(), String> {
+    load_packet_ids(PACKET_IDS).map(|_| ())
+}
+
+fn load_packet_ids(data: &str) -> Result<HashMap<Fid, i32>, String> {
+    let versions: PacketIdFile = serde_json::from_str(data).map_err(|why| why.to_string())?;
+    let states = versions
+        .get(PROTOCOL_VERSION)
+        .ok_or(format!("No packet ids for protocol {}", PROTOCOL_VERSION))?;
+
+    let mut ids = HashMap::new();
+    for (state, directions) in states {
+        for (direction, names) in directions {
+            // Two packets with the same id can't be told apart, even if there is no Fid for one of them.
+            let mut seen: HashMap<i32, &String> = HashMap::new();
+            for (name, id) in &names.0 {
+                let id = id
+                    .strip_prefix("0x")
+                    .and_then(|hex| i32::from_str_radix(hex, 16).ok())
+                    .ok_or(format!("{} has an invalid id: {}", name, id))?;
+                if let Some(other) = seen.insert(id, name) {
+                    return Err(format!(
+                        "{} and {} both have id {:#04x} ({:?} {:?})",
+                        other, name, id, state, direction
+                    ));
+                }
+                ids.insert((*state, *direction, name.to_owned()), id);
+            }
+        }
+    }
+
+    let mut pids = HashMap::new();
+    for (direction, states) in registered_fids() {
+        for (state, fids) in states {
+            for fid in fids {
+                let id = ids
+                    .get(&(state, direction, fid.to_string()))
+                    .ok_or(format!("{} has no id ({:?} {:?})", fid, state, direction))?;
+                pids.insert(fid, *id);
+            }
+        }
     }
+    Ok(pids)
 }
 
 impl fmt::Display for Fid {
@@ -125,75 +188,96 @@ pub struct Functions {
     list: HashMap<Fid, Box<dyn Parsable + Send + Sync>>,
 }
 
+// Which packets are parsed, by direction and state. Their ids come from packets.json.
+fn registered_fids() -> HashMap<Direction, HashMap<State, Vec<Fid>>> {
+    hashmap! {
+        Direction::Clientbound => hashmap! {
+            State::Handshaking => vec! [],
+            State::Status => vec! [
+                Fid::StatusResponse,
+                Fid::StatusPong
+            ],
+            State::Login => vec! [
+                Fid::Disconnect,
+                Fid::EncRequest,
+                Fid::LoginSuccess,
+                Fid::SetCompression,
+                Fid::PluginRequest
+            ],
+            State::Play => vec! [
+                Fid::SpawnEntity,
+                Fid::SpawnXpOrb,
+                Fid::SpawnLivingEntity,
+                Fid::SpawnPainting,
+                Fid::SpawnPlayer,
+                Fid::AckPlayerDigging,
+                Fid::ChatMessageClientbound,
+                Fid::TabCompleteClientbound,
+                Fid::ResourcePackSend,
+                Fid::UpdateHealth,
+                Fid::PlayerAbilities,
+                Fid::KeepAliveCb,
+                Fid::UpdateScore,
+                Fid::DisplayScoreboard,
+                Fid::ScoreboardObjective,
+                Fid::Teams,
+                Fid::EntityEffect,
+                Fid::JoinGame,
+                Fid::OpenBook,
+                Fid::WindowItems,
+                Fid::PlayerPositionAndLook,
+                Fid::SetPassenger,
+                Fid::ChunkData,
+                Fid::DisconnectPlay,
+                Fid::ChangeGameState,
+                Fid::RemoveEntityEffect,
+                Fid::EntityMetadata,
+                Fid::Respawn,
+                Fid::EntityPosition,
+                Fid::EntityPositionRotation,
+                Fid::DestroyEntities,
+                Fid::EntityVelocity,
+                Fid::EntityTeleport,
+                Fid::PlayerInfo,
+                Fid::PlayerListHeaderFooter,
+                Fid::BlockChange,
+                Fid::UnloadChunk,
+                Fid::MultiBlockChange,
+            ],
+        },
+        Direction::Serverbound => hashmap! {
+            State::Handshaking => vec! [
+                Fid::Handshake,
+            ],
+            State::Status => vec! [
+                Fid::StatusRequest,
+                Fid::StatusPing,
+            ],
+            State::Login => vec! [
+                Fid::LoginStart,
+                Fid::EncResponse,
+                Fid::PluginResponse,
+            ],
+            State::Play => vec! [
+                Fid::ChatMessageServerbound,
+                Fid::ClientSettings,
+                Fid::PlayerPosition,
+                Fid::PlayerPositionRotation,
+                Fid::KeepAliveSb,
+                Fid::ResourcePackStatus,
+                Fid::SteerVehicle,
+                Fid::EntityAction,
+                Fid::PlayerBlockPlace,
+                Fid::UseItem,
+                Fid::TeleportConfirm,
+            ],
+        }
+    }
+}
+
 impl Functions {
     fn new() -> Self {
-        let map: HashMap<Direction, HashMap<State, Vec<Fid>>> = hashmap! {
-            Direction::Clientbound => hashmap! {
-                State::Handshaking => vec! [],
-                State::Status => vec! [
-                    Fid::StatusResponse,
-                    Fid::StatusPong
-                ],
-                State::Login => vec! [
-                    Fid::Disconnect,
-                    Fid::EncRequest,
-                    Fid::LoginSuccess,
-                    Fid::SetCompression,
-                    Fid::PluginRequest
-                ],
-                State::Play => vec! [
-                    Fid::SpawnEntity,
-                    Fid::SpawnXpOrb,
-                    Fid::SpawnLivingEntity,
-                    Fid::SpawnPainting,
-                    Fid::SpawnPlayer,
-                    Fid::AckPlayerDigging,
-                    Fid::ChatMessageClientbound,
-                    Fid::TabCompleteClientbound,
-                    Fid::ResourcePackSend,
-                    Fid::UpdateHealth,
-                    Fid::PlayerAbilities,
-                    Fid::KeepAliveCb,
-                    Fid::UpdateScore,
-                    Fid::DisplayScoreboard,
-                    Fid::ScoreboardObjective,
-                    Fid::Teams,
-                    Fid::EntityEffect,
-                    Fid::JoinGame,
-                    Fid::OpenBook,
-                    Fid::WindowItems,
-                    // Fid::PlayerPositionAndLook,
-                    Fid::SetPassenger,
-                    Fid::ChunkData,
-                ],
-            },
-            Direction::Serverbound => hashmap! {
-                State::Handshaking => vec! [
-                    Fid::Handshake,
-                ],
-                State::Status => vec! [
-                    Fid::StatusRequest,
-                    Fid::StatusPing,
-                ],
-                State::Login => vec! [
-                    Fid::LoginStart,
-                    Fid::EncResponse,
-                    Fid::PluginResponse,
-                ],
-                State::Play => vec! [
-                    Fid::ChatMessageServerbound,
-                    Fid::ClientSettings,
-                    Fid::PlayerPosition,
-                    Fid::PlayerPositionRotation,
-                    Fid::KeepAliveSb,
-                    Fid::ResourcePackStatus,
-                    Fid::SteerVehicle,
-                    Fid::EntityAction,
-                    Fid::PlayerBlockPlace,
-                ],
-            }
-        };
-        let map = map
+        let map = registered_fids()
             .iter()
             .map(|(direction, state_fid_vec)| {
                 (
@@ -202,7 +286,7 @@ impl Functions {
                         .iter()
                         .map(|(state, fid_vec)| {
                             let mut hashmap = HashMap::new();
-                            for fid in fid_vec.to_owned() {
+                            for fid in fid_vec.iter().copied() {
                                 hashmap.insert(fid_to_pid(fid), fid);
                             }
                             (state.to_owned(), hashmap)
@@ -233,6 +317,10 @@ impl Functions {
     pub fn get(&self, id: &Fid) -> Option<Box<dyn Parsable + Send + Sync>> {
         self.list.get(id).cloned()
     }
+
+    pub fn always_parse(&self, id: &Fid) -> bool {
+        self.list.get(id).is_some_and(|func| func.always_parse())
+    }
 }
 
 pub fn get_functions() -> Functions {
@@ -371,10 +459,10 @@ pub fn get_functions() -> Functions {
 
     functions.add(Fid::JoinGame, Box::new(cb::play::JoinGame::default()));
 
-    // functions.add(
-    //     Fid::PlayerPositionAndLook,
-    //     Box::new(cb::play::PlayerPositionAndLook::empty()),
-    // );
+    functions.add(
+        Fid::PlayerPositionAndLook,
+        Box::new(cb::play::PlayerPositionAndLook::default()),
+    );
 
     functions.add(
         Fid::SetPassenger,
@@ -383,6 +471,69 @@ pub fn get_functions() -> Functions {
 
     functions.add(Fid::ChunkData, Box::new(cb::play::ChunkData::default()));
 
+    functions.add(
+        Fid::DisconnectPlay,
+        Box::new(cb::play::DisconnectPlay::default()),
+    );
+
+    functions.add(
+        Fid::ChangeGameState,
+        Box::new(cb::play::ChangeGameState::default()),
+    );
+
+    functions.add(
+        Fid::RemoveEntityEffect,
+        Box::new(cb::play::RemoveEntityEffect::default()),
+    );
+
+    functions.add(
+        Fid::EntityMetadata,
+        Box::new(cb::play::EntityMetadata::default()),
+    );
+
+    functions.add(Fid::Respawn, Box::new(cb::play::Respawn::default()));
+
+    functions.add(
+        Fid::EntityPosition,
+        Box::new(cb::play::EntityPosition::default()),
+    );
+
+    functions.add(
+        Fid::EntityPositionRotation,
+        Box::new(cb::play::EntityPositionRotation::default()),
+    );
+
+    functions.add(
+        Fid::DestroyEntities,
+        Box::new(cb::play::DestroyEntities::default()),
+    );
+
+    functions.add(
+        Fid::EntityVelocity,
+        Box::new(cb::play::EntityVelocity::default()),
+    );
+
+    functions.add(
+        Fid::EntityTeleport,
+        Box::new(cb::play::EntityTeleport::default()),
+    );
+
+    functions.add(Fid::PlayerInfo, Box::new(cb::play::PlayerInfo::default()));
+
+    functions.add(
+        Fid::PlayerListHeaderFooter,
+        Box::new(cb::play::PlayerListHeaderFooter::default()),
+    );
+
+    functions.add(Fid::BlockChange, Box::new(cb::play::BlockChange::default()));
+
+    functions.add(Fid::UnloadChunk, Box::new(cb::play::UnloadChunk::default()));
+
+    functions.add(
+        Fid::MultiBlockChange,
+        Box::new(cb::play::MultiBlockChange::default()),
+    );
+
     // Serverbound
     functions.add(
         Fid::ChatMessageServerbound,
@@ -426,5 +577,842 @@ pub fn get_functions() -> Functions {
         Box::new(sb::play::EntityAction::default()),
     );
 
+    functions.add(Fid::UseItem, Box::new(sb::play::UseItem::default()));
+
+    functions.add(
+        Fid::TeleportConfirm,
+        Box::new(sb::play::TeleportConfirm::default()),
+    );
+
     functions
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blocks::BLOCKS,
+        chat::TextComponent,
+        chunk_section::{ChunkSection, SECTION_VOLUME},
+        raw_packet::RawPacket,
+        types::{
+            Metadata, MetadataEntry, MetadataValue, Particle, ParticleData, Slot, Uuid,
+            VillagerData,
+        },
+    };
+    use cb::{login::*, play::*, status::*};
+    use nbt::{Blob, Value};
+    use proptest::{collection::vec, prelude::*, test_runner::TestRunner};
+    use sb::{handshaking::*, login::*, play::*, status::*};
+
+    // proptest needs Debug to print failing cases, which the packets don't have.
+    struct Sample(Box<dyn Parsable + Send + Sync>);
+
+    impl fmt::Debug for Sample {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.0.get_printable())
+        }
+    }
+
+    // sample!(a in strategy_a, b in strategy_b => Packet { a, b })
+    macro_rules! sample {
+        ($($field:pat in $strategy:expr),* => $packet:expr) => {
+            ($($strategy,)*)
+                .prop_map(|($($field,)*)| Sample(Box::new($packet)))
+                .boxed()
+        };
+    }
+
+    fn string() -> impl Strategy<Value = String> {
+        ".{0,20}"
+    }
+
+    fn text_component() -> impl Strategy<Value = TextComponent> {
+        let color = proptest::option::of(prop_oneof![Just("red".to_string()), "#[0-9a-f]{6}"]);
+        let leaf = (string(), color, proptest::option::of(any::<bool>()))
+            .prop_map(|(text, color, bold)| TextComponent {
+                color,
+                bold,
+                ..TextComponent::text(text)
+            })
+            .boxed();
+        (leaf.clone(), vec(leaf, 0..3))
+            .prop_map(|(parent, extra)| TextComponent { extra, ..parent })
+    }
+
+    fn uuid() -> impl Strategy<Value = Uuid> {
+        any::<u128>().prop_map(Uuid::from)
+    }
+
+    // x and z are 26 bits, y is 12 bits.
+    fn position() -> impl Strategy<Value = (i32, i32, i32)> {
+        (
+            -(1 << 25)..1 << 25,
+            -(1 << 11)..1 << 11,
+            -(1 << 25)..1 << 25,
+        )
+    }
+
+    // A Blob is a HashMap, so more than one entry wouldn't encode to the same bytes every time.
+    fn blob() -> impl Strategy<Value = Blob> {
+        vec(("[a-z_]{1,8}", any::<i32>()), 0..2).prop_map(|entries| {
+            let mut blob = Blob::new();
+            for (name, value) in entries {
+                blob.insert(name, Value::Int(value)).unwrap();
+            }
+            blob
+        })
+    }
+
+    fn slot() -> impl Strategy<Value = Slot> {
+        prop_oneof![
+            Just(Slot {
+                present: false,
+                item_id: None,
+                item_count: None,
+                nbt: None,
+            }),
+            (any::<i32>(), any::<i8>(), blob()).prop_map(|(item_id, item_count, nbt)| Slot {
+                present: true,
+                item_id: Some(item_id),
+                item_count: Some(item_count),
+                nbt: Some(nbt),
+            }),
+        ]
+    }
+
+    fn dimension_type() -> impl Strategy<Value = DimentionType> {
+        (
+            (any::<i8>(), any::<i8>(), any::<f32>(), any::<Option<f64>>()),
+            (string(), any::<i8>(), any::<i8>(), any::<i8>(), string()),
+            (
+                any::<i8>(),
+                any::<i32>(),
+                any::<f32>(),
+                any::<i8>(),
+                any::<i8>(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (piglin_safe, natural, ambient_light, fixed_time),
+                    (infiniburn, respawn_anchor_works, has_skylight, bed_works, effects),
+                    (has_raids, logical_height, coordinate_scale, ultrawarm, has_ceiling),
+                )| DimentionType {
+                    piglin_safe,
+                    natural,
+                    ambient_light,
+                    fixed_time,
+                    infiniburn,
+                    respawn_anchor_works,
+                    has_skylight,
+                    bed_works,
+                    effects,
+                    has_raids,
+                    logical_height,
+                    coordinate_scale,
+                    ultrawarm,
+                    has_ceiling,
+                },
+            )
+    }
+
+    fn dimension_codec() -> impl Strategy<Value = DimentionCodec> {
+        let dimension = (string(), any::<i32>(), dimension_type())
+            .prop_map(|(name, id, element)| DimensionTypeRegistryEntry { name, id, element });
+        let biome = (string(), any::<i32>(), string()).prop_map(|(name, id, category)| {
+            BiomeRegistryEntry {
+                name,
+                id,
+                element: BiomeProperties {
+                    category,
+                    ..Default::default()
+                },
+            }
+        });
+        (vec(dimension, 0..3), vec(biome, 0..3)).prop_map(|(dimensions, biomes)| DimentionCodec {
+            dimension_type_registry: DimentionTypeRegistry {
+                r#type: "minecraft:dimension_type".to_string(),
+                value: dimensions,
+            },
+            biome_registry: BiomeRegistry {
+                r#type: "minecraft:worldgen/biome".to_string(),
+                value: biomes,
+            },
+        })
+    }
+
+    fn particle() -> impl Strategy<Value = Particle> {
+        prop_oneof![
+            (0..40).prop_filter_map("particle with data", |id| match id {
+                Particle::BLOCK | Particle::DUST | Particle::FALLING_DUST | Particle::ITEM => None,
+                id => Some(Particle {
+                    id,
+                    data: ParticleData::None,
+                }),
+            }),
+            (
+                prop_oneof![Just(Particle::BLOCK), Just(Particle::FALLING_DUST)],
+                any::<i32>()
+            )
+                .prop_map(|(id, state)| Particle {
+                    id,
+                    data: ParticleData::Block(state),
+                }),
+            any::<(f32, f32, f32, f32)>().prop_map(|(red, green, blue, scale)| Particle {
+                id: Particle::DUST,
+                data: ParticleData::Dust {
+                    red,
+                    green,
+                    blue,
+                    scale,
+                },
+            }),
+            slot().prop_map(|slot| Particle {
+                id: Particle::ITEM,
+                data: ParticleData::Item(slot),
+            }),
+        ]
+    }
+
+    fn metadata_value() -> impl Strategy<Value = MetadataValue> {
+        prop_oneof![
+            any::<i8>().prop_map(MetadataValue::Byte),
+            any::<i32>().prop_map(MetadataValue::VarInt),
+            any::<f32>().prop_map(MetadataValue::Float),
+            string().prop_map(MetadataValue::String),
+            text_component().prop_map(MetadataValue::Chat),
+            proptest::option::of(text_component()).prop_map(MetadataValue::OptChat),
+            slot().prop_map(MetadataValue::Slot),
+            any::<bool>().prop_map(MetadataValue::Boolean),
+            any::<(f32, f32, f32)>().prop_map(|(x, y, z)| MetadataValue::Rotation(x, y, z)),
+            position().prop_map(MetadataValue::Position),
+            proptest::option::of(position()).prop_map(MetadataValue::OptPosition),
+            (0..6).prop_map(MetadataValue::Direction),
+            proptest::option::of(uuid()).prop_map(MetadataValue::OptUuid),
+            any::<i32>().prop_map(MetadataValue::BlockId),
+            blob().prop_map(MetadataValue::Nbt),
+            particle().prop_map(MetadataValue::Particle),
+            any::<(i32, i32, i32)>().prop_map(|(villager_type, profession, level)| {
+                MetadataValue::VillagerData(VillagerData {
+                    villager_type,
+                    profession,
+                    level,
+                })
+            }),
+            proptest::option::of(0..i32::MAX).prop_map(MetadataValue::OptVarInt),
+            (0..7).prop_map(MetadataValue::Pose),
+        ]
+    }
+
+    fn player_info_action() -> impl Strategy<Value = PlayerInfoAction> {
+        let display_name = || proptest::option::of(text_component());
+        let property = (string(), string(), proptest::option::of(string())).prop_map(
+            |(name, value, signature)| PlayerProperty {
+                name,
+                value,
+                signature,
+            },
+        );
+        let add = (
+            uuid(),
+            string(),
+            vec(property, 0..2),
+            any::<(i32, i32)>(),
+            display_name(),
+        )
+            .prop_map(|(uuid, name, properties, (gamemode, ping), display_name)| {
+                PlayerInfoAdd {
+                    uuid,
+                    name,
+                    properties,
+                    gamemode,
+                    ping,
+                    display_name,
+                }
+            });
+        prop_oneof![
+            vec(add, 0..3).prop_map(PlayerInfoAction::AddPlayer),
+            vec((uuid(), any::<i32>()), 0..3).prop_map(|players| {
+                PlayerInfoAction::UpdateGamemode(
+                    players
+                        .into_iter()
+                        .map(|(uuid, gamemode)| PlayerInfoGamemode { uuid, gamemode })
+                        .collect(),
+                )
+            }),
+            vec((uuid(), any::<i32>()), 0..3).prop_map(|players| {
+                PlayerInfoAction::UpdateLatency(
+                    players
+                        .into_iter()
+                        .map(|(uuid, ping)| PlayerInfoLatency { uuid, ping })
+                        .collect(),
+                )
+            }),
+            vec((uuid(), display_name()), 0..3).prop_map(|players| {
+                PlayerInfoAction::UpdateDisplayName(
+                    players
+                        .into_iter()
+                        .map(|(uuid, display_name)| PlayerInfoDisplayName { uuid, display_name })
+                        .collect(),
+                )
+            }),
+            vec(uuid(), 0..3).prop_map(PlayerInfoAction::RemovePlayer),
+        ]
+    }
+
+    // Up to 300 different blocks, so sections with the global palette are made too.
+    fn chunk_section() -> impl Strategy<Value = ChunkSection> {
+        vec(0..BLOCKS.len() as i32, 1..300)
+            .prop_flat_map(|palette| vec(proptest::sample::select(palette), SECTION_VOLUME))
+            .prop_map(|blocks| ChunkSection::from_blocks(&blocks).unwrap())
+    }
+
+    fn scoreboard_position() -> impl Strategy<Value = ScoreboardPosition> {
+        prop_oneof![
+            Just(ScoreboardPosition::List),
+            Just(ScoreboardPosition::Sidebar),
+            Just(ScoreboardPosition::BelowName),
+            (0i8..16).prop_map(ScoreboardPosition::TeamSpecificSidebar),
+        ]
+    }
+
+    fn team_mode() -> impl Strategy<Value = TeamMode> {
+        prop_oneof![
+            Just(TeamMode::Create),
+            Just(TeamMode::Remove),
+            Just(TeamMode::Update),
+            Just(TeamMode::AddEntities),
+            Just(TeamMode::RemoveEntities),
+        ]
+    }
+
+    // The match has no catch-all, so a new Fid doesn't compile until it has a strategy here.
+    fn strategy(fid: Fid) -> BoxedStrategy<Sample> {
+        match fid {
+            Fid::Unparsable => unreachable!("Unparsable is never registered"),
+            Fid::Handshake => sample!(
+                protocol_version in any::<i32>(),
+                server_address in string(),
+                server_port in any::<u16>(),
+                next_state in prop_oneof![Just(NextState::Status), Just(NextState::Login)]
+                => Handshake { protocol_version, server_address, server_port, next_state }
+            ),
+            Fid::StatusResponse => sample!(
+                description in text_component(),
+                online in any::<u32>()
+                => StatusResponse {
+                    status: ServerStatus {
+                        description,
+                        other: serde_json::json!({ "players": { "max": 20, "online": online } })
+                            .as_object()
+                            .unwrap()
+                            .clone(),
+                    },
+                }
+            ),
+            Fid::StatusPong => sample!(payload in any::<i64>() => StatusPong { payload }),
+            Fid::StatusRequest => Just(())
+                .prop_map(|_| Sample(Box::new(StatusRequest {})))
+                .boxed(),
+            Fid::StatusPing => sample!(payload in any::<i64>() => StatusPing { payload }),
+            Fid::Disconnect => sample!(reason in text_component() => Disconnect { reason }),
+            Fid::EncRequest => sample!(
+                server_id in string(),
+                public_key in vec(any::<u8>(), 0..64),
+                verify_token in vec(any::<u8>(), 0..8)
+                => EncRequest { server_id, public_key, verify_token }
+            ),
+            Fid::LoginSuccess => sample!(
+                uuid in uuid(), username in string() => LoginSuccess { uuid, username }
+            ),
+            Fid::SetCompression => sample!(
+                threshold in any::<i32>() => SetCompression { threshold }
+            ),
+            Fid::PluginRequest => sample!(
+                message_id in any::<i32>(),
+                channel in string(),
+                data in vec(any::<u8>(), 0..32)
+                => PluginRequest { message_id, channel, data }
+            ),
+            Fid::LoginStart => sample!(username in string() => LoginStart { username }),
+            Fid::EncResponse => sample!(
+                shared_secret in vec(any::<u8>(), 0..64),
+                verify_token in vec(any::<u8>(), 0..8)
+                => EncResponse { shared_secret, verify_token }
+            ),
+            Fid::PluginResponse => sample!(
+                message_id in any::<i32>(),
+                success in any::<bool>(),
+                data in vec(any::<u8>(), 0..32)
+                => PluginResponse { message_id, success, data }
+            ),
+            Fid::SpawnEntity => sample!(
+                entity_id in any::<i32>(),
+                object_uuid in uuid(),
+                r#type in any::<i32>(),
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                (pitch, yaw) in any::<(u8, u8)>(),
+                data in any::<i32>(),
+                (velocity_x, velocity_y, velocity_z) in any::<(i16, i16, i16)>()
+                => SpawnEntity {
+                    entity_id, object_uuid, r#type, x, y, z, pitch, yaw, data,
+                    velocity_x, velocity_y, velocity_z,
+                }
+            ),
+            Fid::SpawnXpOrb => sample!(
+                entity_id in any::<i32>(),
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                count in any::<i16>()
+                => SpawnXpOrb { entity_id, x, y, z, count }
+            ),
+            Fid::SpawnLivingEntity => sample!(
+                entity_id in any::<i32>(),
+                object_uuid in uuid(),
+                r#type in any::<i32>(),
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                (yaw, pitch, head_pitch) in any::<(u8, u8, u8)>(),
+                (velocity_x, velocity_y, velocity_z) in any::<(i16, i16, i16)>()
+                => SpawnLivingEntity {
+                    entity_id, object_uuid, r#type, x, y, z, yaw, pitch, head_pitch,
+                    velocity_x, velocity_y, velocity_z,
+                }
+            ),
+            Fid::SpawnPainting => sample!(
+                entity_id in any::<i32>(),
+                object_uuid in uuid(),
+                motive in any::<i32>(),
+                location in position(),
+                direction in prop_oneof![
+                    Just(FacingDirection::South),
+                    Just(FacingDirection::West),
+                    Just(FacingDirection::North),
+                    Just(FacingDirection::East),
+                ]
+                => SpawnPainting { entity_id, object_uuid, motive, location, direction }
+            ),
+            Fid::SpawnPlayer => sample!(
+                entity_id in any::<i32>(),
+                player_uuid in uuid(),
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                (yaw, pitch) in any::<(u8, u8)>()
+                => SpawnPlayer { entity_id, player_uuid, x, y, z, yaw, pitch }
+            ),
+            Fid::AckPlayerDigging => sample!(
+                location in position(),
+                block in any::<i32>(),
+                status in prop_oneof![
+                    Just(DiggingStatus::Started),
+                    Just(DiggingStatus::Cancelled),
+                    Just(DiggingStatus::Finished),
+                ],
+                successful in any::<bool>()
+                => AckPlayerDigging { location, block, status, successful }
+            ),
+            Fid::ChatMessageClientbound => sample!(
+                data in text_component(),
+                position in prop_oneof![
+                    Just(ChatMessagePosition::Chat),
+                    Just(ChatMessagePosition::SystemMessage),
+                    Just(ChatMessagePosition::GameInfo),
+                ],
+                sender in uuid()
+                => ChatMessageClientbound { data, position, sender }
+            ),
+            Fid::TabCompleteClientbound => sample!(
+                (id, start, length) in any::<(i32, i32, i32)>(),
+                matches in vec(
+                    (string(), proptest::option::of(string()))
+                        .prop_map(|(mat, tooltip)| TabCompleteMatch { mat, tooltip }),
+                    0..4,
+                )
+                => TabCompleteClientbound { id, start, length, matches }
+            ),
+            Fid::ChatMessageServerbound => sample!(
+                message in string() => ChatMessageServerbound { message }
+            ),
+            Fid::ResourcePackSend => sample!(
+                url in string(), hash in string() => ResourcePackSend { url, hash }
+            ),
+            Fid::ClientSettings => sample!(
+                locale in string(),
+                view_distance in any::<i8>(),
+                chat_mode in prop_oneof![
+                    Just(ChatMode::Enabled),
+                    Just(ChatMode::CommandsOnly),
+                    Just(ChatMode::Hidden),
+                ],
+                chat_colors in any::<bool>(),
+                displayed_skin_parts in any::<u8>(),
+                main_hand in prop_oneof![Just(Hand::Left), Just(Hand::Right)]
+                => ClientSettings {
+                    locale, view_distance, chat_mode, chat_colors, displayed_skin_parts, main_hand,
+                }
+            ),
+            Fid::UpdateHealth => sample!(
+                health in any::<f32>(),
+                food in any::<i32>(),
+                food_saturation in any::<f32>()
+                => UpdateHealth { health, food, food_saturation }
+            ),
+            Fid::PlayerPositionAndLook => sample!(
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                (yaw, pitch) in any::<(f32, f32)>(),
+                flags in any::<u8>(),
+                teleport_id in any::<i32>(),
+                dismount_vehicle in any::<bool>()
+                => PlayerPositionAndLook { x, y, z, yaw, pitch, flags, teleport_id, dismount_vehicle }
+            ),
+            Fid::TeleportConfirm => sample!(
+                teleport_id in any::<i32>() => TeleportConfirm { teleport_id }
+            ),
+            Fid::PlayerPosition => sample!(
+                (x, feet_y, z) in any::<(f64, f64, f64)>(),
+                on_ground in any::<bool>()
+                => PlayerPosition { x, feet_y, z, on_ground }
+            ),
+            Fid::PlayerPositionRotation => sample!(
+                (x, feet_y, z) in any::<(f64, f64, f64)>(),
+                (yaw, pitch) in any::<(f32, f32)>(),
+                on_ground in any::<bool>()
+                => PlayerPositionRotation { x, feet_y, z, yaw, pitch, on_ground }
+            ),
+            Fid::PlayerAbilities => sample!(
+                flags in any::<u8>(),
+                (flying_speed, fov_modifier) in any::<(f32, f32)>()
+                => PlayerAbilities { flags, flying_speed, fov_modifier }
+            ),
+            Fid::KeepAliveCb => sample!(
+                keep_alive_id in any::<i64>() => KeepAliveCb { keep_alive_id }
+            ),
+            Fid::KeepAliveSb => sample!(
+                keep_alive_id in any::<i64>() => KeepAliveSb { keep_alive_id }
+            ),
+            Fid::UpdateScore => sample!(
+                entity_name in string(),
+                action in 0i8..2,
+                objective_name in string(),
+                value in any::<i32>()
+                => UpdateScore {
+                    entity_name,
+                    action,
+                    objective_name,
+                    value: if action != 1 { Some(value) } else { None },
+                }
+            ),
+            Fid::DisplayScoreboard => sample!(
+                position in scoreboard_position(),
+                name in string()
+                => DisplayScoreboard { position, name }
+            ),
+            Fid::ScoreboardObjective => sample!(
+                objective_name in string(),
+                mode in prop_oneof![
+                    Just(ScoreboardMode::Create),
+                    Just(ScoreboardMode::Remove),
+                    Just(ScoreboardMode::UpdateDisplayText),
+                ],
+                objective_value in string(),
+                sb_type in prop_oneof![Just(ScoreboardType::Integer), Just(ScoreboardType::Hearts)]
+                => {
+                    let removed = mode == ScoreboardMode::Remove;
+                    ScoreboardObjective {
+                        objective_name,
+                        mode,
+                        objective_value: if removed { None } else { Some(objective_value) },
+                        sb_type: if removed { None } else { Some(sb_type) },
+                    }
+                }
+            ),
+            Fid::Teams => sample!(
+                team_name in string(),
+                mode in team_mode(),
+                (team_display_name, name_tag_visibility, collision_rule) in (string(), string(), string()),
+                (friendly_flags, team_color) in any::<(i8, i32)>(),
+                (team_prefix, team_suffix) in (string(), string()),
+                entities in vec(string(), 0..4)
+                => {
+                    let info = matches!(mode, TeamMode::Create | TeamMode::Update);
+                    let with_entities = matches!(
+                        mode,
+                        TeamMode::Create | TeamMode::AddEntities | TeamMode::RemoveEntities
+                    );
+                    Teams {
+                        team_name,
+                        mode,
+                        team_display_name: Some(team_display_name).filter(|_| info),
+                        friendly_flags: Some(friendly_flags).filter(|_| info),
+                        name_tag_visibility: Some(name_tag_visibility).filter(|_| info),
+                        collision_rule: Some(collision_rule).filter(|_| info),
+                        team_color: Some(team_color).filter(|_| info),
+                        team_prefix: Some(team_prefix).filter(|_| info),
+                        team_suffix: Some(team_suffix).filter(|_| info),
+                        entities: Some(entities).filter(|_| with_entities),
+                    }
+                }
+            ),
+            Fid::ResourcePackStatus => sample!(
+                result in prop_oneof![
+                    Just(ResourcePackResponse::Success),
+                    Just(ResourcePackResponse::Declined),
+                    Just(ResourcePackResponse::Failed),
+                    Just(ResourcePackResponse::Accepted),
+                ]
+                => ResourcePackStatus { result }
+            ),
+            Fid::EntityEffect => sample!(
+                entity_id in any::<i32>(),
+                (effect_id, amplifier, flags) in any::<(u8, u8, u8)>(),
+                duration in any::<i32>()
+                => EntityEffect { entity_id, effect_id, amplifier, duration, flags }
+            ),
+            Fid::JoinGame => sample!(
+                player_entity_id in any::<i32>(),
+                (is_hardcore, gamemode, previous_gamemode) in any::<(bool, u8, i8)>(),
+                world_names in vec(string(), 0..4),
+                dimension_codec in dimension_codec(),
+                dimension in dimension_type(),
+                world_name in string(),
+                (hashed_seed, max_players, view_distance) in any::<(i64, i32, i32)>(),
+                (reduced_debug_info, enable_respawn_screen, is_debug, is_flat)
+                    in any::<(bool, bool, bool, bool)>()
+                => JoinGame {
+                    player_entity_id, is_hardcore, gamemode, previous_gamemode, world_names,
+                    dimension_codec, dimension, world_name, hashed_seed, max_players,
+                    view_distance, reduced_debug_info, enable_respawn_screen, is_debug, is_flat,
+                }
+            ),
+            Fid::OpenBook => sample!(hand in any::<i32>() => OpenBook { hand }),
+            Fid::WindowItems => sample!(
+                window_id in any::<u8>(),
+                slot_data in vec(slot(), 0..8)
+                => WindowItems { window_id, slot_data }
+            ),
+            Fid::SetPassenger => sample!(
+                entity_id in any::<i32>(),
+                passengers in vec(any::<i32>(), 0..8)
+                => SetPassenger { entity_id, passengers }
+            ),
+            Fid::SteerVehicle => sample!(
+                (sideways, forward) in any::<(f32, f32)>(),
+                flags in any::<u8>()
+                => SteerVehicle { sideways, forward, flags }
+            ),
+            Fid::EntityAction => sample!(
+                entity_id in any::<i32>(),
+                action_id in prop_oneof![
+                    Just(Action::StartSneaking),
+                    Just(Action::StopSneaking),
+                    Just(Action::LeaveBed),
+                    Just(Action::StartSprinting),
+                    Just(Action::StopSprinting),
+                    Just(Action::StartJumpWithHorse),
+                    Just(Action::StopJumpWithHorse),
+                    Just(Action::OpenHorseInventory),
+                    Just(Action::StartFlyingWithElytra),
+                ],
+                jump_boost in any::<i32>()
+                => EntityAction { entity_id, action_id, jump_boost }
+            ),
+            // The sections themselves are left empty, the heightmaps, biomes and block entities are not.
+            Fid::ChunkData => sample!(
+                (chunk_x, chunk_z) in any::<(i32, i32)>(),
+                heightmaps in blob(),
+                biomes in proptest::option::of(vec(any::<i32>(), 0..16)),
+                data in vec(proptest::option::weighted(0.1, chunk_section()), 16),
+                block_entities in vec(blob(), 0..4)
+                => ChunkData {
+                    chunk_x,
+                    chunk_z,
+                    full_chunk: biomes.is_some(),
+                    primary_bit_mask: data
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, section)| section.is_some())
+                        .fold(0, |mask, (y, _)| mask | 1 << y),
+                    heightmaps,
+                    biomes_length: biomes.as_ref().map(|biomes| biomes.len() as i32),
+                    biomes,
+                    data,
+                    number_of_block_entities: block_entities.len() as i32,
+                    block_entities,
+                }
+            ),
+            Fid::PlayerBlockPlace => sample!(
+                hand in any::<i32>(),
+                location in position(),
+                face in any::<i32>(),
+                (cursor_pos_x, cursor_pos_y, cursor_pos_z) in any::<(f32, f32, f32)>(),
+                inside_block in any::<bool>()
+                => PlayerBlockPlace {
+                    hand, location, face, cursor_pos_x, cursor_pos_y, cursor_pos_z, inside_block,
+                }
+            ),
+            Fid::DisconnectPlay => sample!(
+                reason in text_component() => DisconnectPlay { reason }
+            ),
+            Fid::ChangeGameState => sample!(
+                reason in any::<u8>(), value in any::<f32>() => ChangeGameState { reason, value }
+            ),
+            Fid::RemoveEntityEffect => sample!(
+                entity_id in any::<i32>(),
+                effect_id in any::<u8>()
+                => RemoveEntityEffect { entity_id, effect_id }
+            ),
+            Fid::UseItem => sample!(hand in any::<i32>() => UseItem { hand }),
+            Fid::EntityPosition => sample!(
+                entity_id in any::<i32>(),
+                (delta_x, delta_y, delta_z) in any::<(i16, i16, i16)>(),
+                on_ground in any::<bool>()
+                => EntityPosition { entity_id, delta_x, delta_y, delta_z, on_ground }
+            ),
+            Fid::EntityPositionRotation => sample!(
+                entity_id in any::<i32>(),
+                (delta_x, delta_y, delta_z) in any::<(i16, i16, i16)>(),
+                (yaw, pitch, on_ground) in any::<(u8, u8, bool)>()
+                => EntityPositionRotation { entity_id, delta_x, delta_y, delta_z, yaw, pitch, on_ground }
+            ),
+            Fid::EntityTeleport => sample!(
+                entity_id in any::<i32>(),
+                (x, y, z) in any::<(f64, f64, f64)>(),
+                (yaw, pitch, on_ground) in any::<(u8, u8, bool)>()
+                => EntityTeleport { entity_id, x, y, z, yaw, pitch, on_ground }
+            ),
+            Fid::EntityVelocity => sample!(
+                entity_id in any::<i32>(),
+                (velocity_x, velocity_y, velocity_z) in any::<(i16, i16, i16)>()
+                => EntityVelocity { entity_id, velocity_x, velocity_y, velocity_z }
+            ),
+            Fid::DestroyEntities => sample!(
+                entity_ids in vec(any::<i32>(), 0..8) => DestroyEntities { entity_ids }
+            ),
+            Fid::PlayerInfo => sample!(action in player_info_action() => PlayerInfo { action }),
+            Fid::PlayerListHeaderFooter => sample!(
+                (header, footer) in (text_component(), text_component())
+                => PlayerListHeaderFooter { header, footer }
+            ),
+            Fid::BlockChange => sample!(
+                location in position(),
+                block_id in any::<i32>()
+                => BlockChange { location, block_id }
+            ),
+            Fid::UnloadChunk => sample!(
+                (chunk_x, chunk_z) in any::<(i32, i32)>() => UnloadChunk { chunk_x, chunk_z }
+            ),
+            Fid::MultiBlockChange => sample!(
+                section_position in any::<i64>(),
+                inverse_trust_edges in any::<bool>(),
+                blocks in vec(any::<i64>(), 0..8)
+                => MultiBlockChange { section_position, inverse_trust_edges, blocks }
+            ),
+            Fid::Respawn => sample!(
+                dimension in dimension_type(),
+                world_name in string(),
+                hashed_seed in any::<i64>(),
+                (gamemode, previous_gamemode) in any::<(u8, i8)>(),
+                (is_debug, is_flat, copy_metadata) in any::<(bool, bool, bool)>()
+                => Respawn {
+                    dimension, world_name, hashed_seed, gamemode, previous_gamemode, is_debug,
+                    is_flat, copy_metadata,
+                }
+            ),
+            Fid::EntityMetadata => sample!(
+                entity_id in any::<i32>(),
+                entries in vec((0u8..0xff, metadata_value()), 0..8)
+                => EntityMetadata {
+                    entity_id,
+                    metadata: Metadata {
+                        entries: entries
+                            .into_iter()
+                            .map(|(index, value)| MetadataEntry { index, value })
+                            .collect(),
+                    },
+                }
+            ),
+        }
+    }
+
+    // decode(encode(x)) has to give x back, compared through the serialized fields and the bytes.
+    fn round_trip(fid: Fid, packet: &(dyn Parsable + Send + Sync)) -> Result<(), TestCaseError> {
+        let encoded = packet
+            .encode_packet()
+            .map_err(|_| TestCaseError::fail("encode_packet failed"))?;
+        let mut data = RawPacket::from(encoded.get_data_uncompressed().unwrap());
+        data.decode_varint().unwrap();
+        prop_assert_eq!(data.decode_varint().unwrap(), fid_to_pid(fid));
+        let bytes = data.get_vec();
+
+        let mut decoded = get_functions().get(&fid).unwrap();
+        decoded
+            .parse_packet(data)
+            .map_err(|why| TestCaseError::fail(format!("{:?}", why)))?;
+        prop_assert_eq!(
+            serde_json::to_value(packet).unwrap(),
+            serde_json::to_value(&*decoded).unwrap()
+        );
+
+        let mut reencoded = RawPacket::new();
+        decoded.encode_fields(&mut reencoded).unwrap();
+        prop_assert_eq!(bytes, reencoded.get_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_ids() {
+        assert!(check_packet_ids().is_ok());
+        assert_eq!(fid_to_pid(Fid::WindowItems), 0x13);
+        assert_eq!(fid_to_pid(Fid::ChangeGameState), 0x1D);
+        assert_eq!(fid_to_pid(Fid::SteerVehicle), 0x1D);
+
+        // The same id in another state or direction is fine, the same id twice in one isn't.
+        let mut file: serde_json::Value = serde_json::from_str(PACKET_IDS).unwrap();
+        file["754"]["play"]["clientbound"]["Unknown"] = "0x1d".into();
+        assert!(load_packet_ids(&file.to_string())
+            .unwrap_err()
+            .contains("id 0x1d"));
+        file["754"]["play"]["clientbound"]["Unknown"] = "0x12".into();
+        assert!(load_packet_ids(&file.to_string()).is_ok());
+
+        file["754"]["play"]["clientbound"]["Unknown"] = "19".into();
+        assert!(load_packet_ids(&file.to_string()).is_err());
+        file["754"]["play"]["clientbound"]
+            .as_object_mut()
+            .unwrap()
+            .remove("Unknown");
+        file["754"]["play"]["clientbound"]
+            .as_object_mut()
+            .unwrap()
+            .remove("WindowItems");
+        assert_eq!(
+            load_packet_ids(&file.to_string()).unwrap_err(),
+            "WindowItems has no id (Play Clientbound)"
+        );
+        assert!(load_packet_ids("{}").is_err());
+
+        // serde_json::Value can't hold the same key twice, so this one is written by hand.
+        let duplicate =
+            r#"{"754": {"play": {"clientbound": {"WindowItems": "0x13", "WindowItems": "0x14"}}}}"#;
+        assert!(load_packet_ids(duplicate)
+            .unwrap_err()
+            .contains("WindowItems is in there twice"));
+    }
+
+    #[test]
+    fn test_every_packet_round_trips() {
+        let functions = get_functions();
+        for fids in functions.map.values().flat_map(|states| states.values()) {
+            for fid in fids.values() {
+                assert!(functions.get(fid).is_some(), "{} is not registered", fid);
+            }
+        }
+
+        for fid in functions.list.keys() {
+            let mut runner = TestRunner::default();
+            if let Err(why) =
+                runner.run(&strategy(*fid), |Sample(packet)| round_trip(*fid, &*packet))
+            {
+                panic!("{} doesn't round trip: {}", fid, why);
+            }
+        }
+    }
+}
@@ -1,54 +1,52 @@
 use crate::{
-    functions::fid_to_pid, packet::Packet, parsable::Parsable, raw_packet::RawPacket, Direction,
-    SharedState,
+    control::{self, ControlLink},
+    parsable::{self, Parsable},
+    utils::generate_disconnect_packet,
+    Direction, SharedState, State,
 };
 
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tracing::Instrument;
 
 #[derive(Serialize, Deserialize, Debug)]
-struct AuthResponse {
-    authentication_token: Option<String>,
-    uuid: Option<String>,
-    allowed: bool,
+pub struct AuthResponse {
+    pub authentication_token: Option<String>,
+    pub uuid: Option<String>,
+    pub allowed: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct AuthRequest {
-    username: String,
-    mc_server_address: String,
-    login_ip: String,
+pub struct AuthRequest {
+    pub username: String,
+    pub mc_server_address: String,
+    pub login_ip: String,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(LoginStart)]
 pub struct LoginStart {
-    username: String,
+    pub username: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AuthSubResponse {
-    success: bool,
-    message: Option<String>,
+    pub success: bool,
+    pub message: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl Parsable for LoginStart {
-    fn default() -> Self {
-        Self {
-            username: String::new(),
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.username = packet.decode_string()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        status.username = self.username.clone();
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        self.username.to_string()
-    }
-
     fn packet_editing(&self) -> bool {
         true
     }
@@ -59,93 +57,36 @@ impl Parsable for LoginStart {
         _plugins: &mut Vec<Box<dyn crate::EventHandler + Send>>,
         config: &crate::conf::Configuration,
     ) -> Result<Vec<(crate::packet::Packet, crate::Direction)>, ()> {
-        let mut status = status;
         if config.ws_enabled {
-            let (mut ws, _) =
-                match connect_async(format!("{}/{}", &config.ws_url, &config.ws_secret)).await {
-                    Ok(ws) => ws,
-                    Err(e) => {
-                        log::error!("{}", e);
-                        let mut new_packet = RawPacket::new();
-                        new_packet.encode_string(
-                            "{\"text\":\"WS server down! Please report this!\"}".to_string(),
-                        );
-
-                        return Ok(vec![(
-                            Packet::from(new_packet, fid_to_pid(crate::functions::Fid::Disconnect)),
-                            Direction::Clientbound,
-                        )]);
-                    }
-                };
-            log::info!("Connection to websocket established.");
-
-            let message_data = serde_json::to_string(&AuthRequest {
-                login_ip: status.user_ip.clone(),
-                mc_server_address: status.server_ip.clone(),
-                username: self.username.clone(),
-            })
-            .unwrap();
-
-            log::debug!("{}", message_data);
-
-            tokio::time::sleep_until(
-                tokio::time::Instant::now() + std::time::Duration::from_millis(100),
-            )
-            .await;
-            ws.send(Message::text(&message_data)).await.unwrap();
-
-            // ws.send(Message::text("Hi!")).await.unwrap();
-
-            log::info!("Sent authentication request!");
-
-            match serde_json::from_str::<AuthSubResponse>(
-                dbg! {ws.next().await.unwrap().unwrap().to_text().unwrap()},
-            )
-            .unwrap()
-            .success
+            let (ws, parsed_return_msg) = match self
+                .authenticate(status, config)
+                .instrument(tracing::info_span!("ws_auth"))
+                .await
             {
-                true => {}
-                false => {
-                    log::error!("No client found listening for that name");
-                    let mut new_packet = RawPacket::new();
-                    new_packet.encode_string("{\"text\":\"Failed to authenticate\"}".to_string());
-
-                    return Ok(vec![(
-                        Packet::from(new_packet, fid_to_pid(crate::functions::Fid::Disconnect)),
-                        Direction::Clientbound,
-                    )]);
-                }
-            };
-
-            let return_msg = match ws.next().await.unwrap() {
-                Ok(msg) => msg,
-                Err(_) => {
-                    let mut new_packet = RawPacket::new();
-                    new_packet.encode_string("{\"text\":\"Failed to authenticate\"}".to_string());
-
+                Ok(auth) => auth,
+                Err(reason) => {
                     return Ok(vec![(
-                        Packet::from(new_packet, fid_to_pid(crate::functions::Fid::Disconnect)),
+                        generate_disconnect_packet(State::Login, reason).ok_or(())?,
                         Direction::Clientbound,
                     )]);
                 }
             };
 
-            let parsed_return_msg: AuthResponse =
-                serde_json::from_str(return_msg.to_text().unwrap()).unwrap();
-
-            println!("{:?}", parsed_return_msg);
-
             if parsed_return_msg.allowed {
                 status.access_token = parsed_return_msg.authentication_token.unwrap();
                 status.uuid = parsed_return_msg.uuid.unwrap();
+                // The link stays open, so the ws server can send commands for this player (or others) over it.
+                if config.ws_control_enabled {
+                    tokio::spawn(
+                        control::serve(ws, status.sessions.clone(), status.connection_id.clone())
+                            .instrument(tracing::Span::current()),
+                    );
+                }
                 return Ok(vec![]);
             } else {
                 log::error!("Connection disallowed!");
-                let mut new_packet = RawPacket::new();
-                new_packet.encode_string("{\"text\":\"Failed to authenticate\"}".to_string());
-
                 return Ok(vec![(
-                    Packet::from(new_packet, fid_to_pid(crate::functions::Fid::Disconnect)),
+                    generate_disconnect_packet(State::Login, "Failed to authenticate").ok_or(())?,
                     Direction::Clientbound,
                 )]);
                 // let mut new_packet = RawPacket::new();
@@ -164,3 +105,67 @@ impl Parsable for LoginStart {
         }
     }
 }
+
+impl LoginStart {
+    // Asks the ws server whether this player may log in. On failure it returns the reason the client is disconnected with.
+    async fn authenticate(
+        &self,
+        status: &SharedState,
+        config: &crate::conf::Configuration,
+    ) -> Result<(ControlLink, AuthResponse), &'static str> {
+        // The timer is stopped when it's dropped, so every return is measured.
+        let _timer = crate::metrics::WS_AUTH_SECONDS.start_timer();
+        let (mut ws, _) =
+            match connect_async(format!("{}/{}", &config.ws_url, &config.ws_secret)).await {
+                Ok(ws) => ws,
+                Err(e) => {
+                    log::error!("{}", e);
+                    return Err("WS server down! Please report this!");
+                }
+            };
+        log::info!("Connection to websocket established.");
+
+        let message_data = serde_json::to_string(&AuthRequest {
+            login_ip: status.user_ip.clone(),
+            mc_server_address: status.server_ip.clone(),
+            username: self.username.clone(),
+        })
+        .unwrap();
+
+        log::debug!("{}", message_data);
+
+        tokio::time::sleep_until(
+            tokio::time::Instant::now() + std::time::Duration::from_millis(100),
+        )
+        .await;
+        ws.send(Message::text(&message_data)).await.unwrap();
+
+        // ws.send(Message::text("Hi!")).await.unwrap();
+
+        log::info!("Sent authentication request!");
+
+        match serde_json::from_str::<AuthSubResponse>(
+            ws.next().await.unwrap().unwrap().to_text().unwrap(),
+        )
+        .unwrap()
+        .success
+        {
+            true => {}
+            false => {
+                log::error!("No client found listening for that name");
+                return Err("Failed to authenticate");
+            }
+        };
+
+        let return_msg = match ws.next().await.unwrap() {
+            Ok(msg) => msg,
+            Err(_) => return Err("Failed to authenticate"),
+        };
+
+        let parsed_return_msg: AuthResponse =
+            serde_json::from_str(return_msg.to_text().unwrap()).unwrap();
+
+        log::debug!("{:?}", parsed_return_msg);
+        Ok((ws, parsed_return_msg))
+    }
+}
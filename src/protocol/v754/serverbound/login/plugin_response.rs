@@ -1,32 +1,20 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use hex::encode;
 
 use crate::utils;
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(PluginResponse)]
 pub struct PluginResponse {
-    message_id: i32,
-    success: bool,
-    data: Vec<u8>,
+    #[varint]
+    pub message_id: i32,
+    pub success: bool,
+    #[rest]
+    pub data: Vec<u8>,
 }
 
 impl Parsable for PluginResponse {
-    fn default() -> Self {
-        Self {
-            message_id: 0,
-            success: false,
-            data: Vec::new(),
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.message_id = packet.decode_varint()?;
-        self.success = packet.decode_bool()?;
-        self.data = packet.get_vec();
-        Ok(())
-    }
-
     fn get_printable(&self) -> String {
         format!(
             "{} {} {}",
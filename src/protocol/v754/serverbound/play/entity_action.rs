@@ -1,13 +1,15 @@
+use crate::conf::Configuration;
 use crate::{
-    conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    packet::Packet,
+    parsable::{self, Parsable},
 };
-use crate::{packet::Packet, parsable::Parsable, raw_packet::RawPacket};
 use crate::{Direction, SharedState};
 use serde::Serialize;
 
-#[derive(Clone, Serialize, Debug)]
-enum Action {
+#[derive(Clone, Serialize, Debug, Default, parsable::Packet)]
+#[packet(varint)]
+pub enum Action {
+    #[default]
     StartSneaking,
     StopSneaking,
     LeaveBed,
@@ -19,49 +21,18 @@ enum Action {
     StartFlyingWithElytra,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(EntityAction)]
 pub struct EntityAction {
-    entity_id: i32,
-    action_id: Action,
-    jump_boost: i32,
+    #[varint]
+    pub entity_id: i32,
+    pub action_id: Action,
+    #[varint]
+    pub jump_boost: i32,
 }
 
 #[async_trait::async_trait]
 impl Parsable for EntityAction {
-    fn default() -> Self {
-        Self {
-            entity_id: 0,
-            action_id: Action::StartSneaking,
-            jump_boost: 0,
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.entity_id = packet.decode_varint()?;
-        self.action_id = match packet.decode_varint()? {
-            0 => Action::StartSneaking,
-            1 => Action::StopSneaking,
-            2 => Action::LeaveBed,
-            3 => Action::StartSprinting,
-            4 => Action::StopSprinting,
-            5 => Action::StartJumpWithHorse,
-            6 => Action::StopJumpWithHorse,
-            7 => Action::OpenHorseInventory,
-            8 => Action::StartFlyingWithElytra,
-            _ => unreachable!(),
-        };
-        self.jump_boost = packet.decode_varint()?;
-
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {:?} {}",
-            self.entity_id, self.action_id, self.jump_boost,
-        )
-    }
-
     fn packet_editing(&self) -> bool {
         false
     }
@@ -72,29 +43,10 @@ impl Parsable for EntityAction {
         _plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut return_packet_vec = Vec::new();
-
-        let mut raw_packet = RawPacket::new();
-
-        raw_packet.encode_varint(self.entity_id);
-        raw_packet.encode_varint(match self.action_id {
-            Action::StartSneaking => 0,
-            Action::StopSneaking => 1,
-            Action::LeaveBed => 2,
-            Action::StartSprinting => 3,
-            Action::StopSprinting => 4,
-            Action::StartJumpWithHorse => 5,
-            Action::StopJumpWithHorse => 6,
-            Action::OpenHorseInventory => 7,
-            Action::StartFlyingWithElytra => 8,
-        });
-        raw_packet.encode_varint(100 * 100);
-
-        return_packet_vec.push((
-            Packet::from(raw_packet, fid_to_pid(Fid::EntityAction)),
-            Direction::Serverbound,
-        ));
-
-        Ok(return_packet_vec)
+        let edited = Self {
+            jump_boost: 100 * 100,
+            ..self.clone()
+        };
+        Ok(vec![(edited.encode_packet()?, Direction::Serverbound)])
     }
 }
@@ -24,3 +24,9 @@ pub use entity_action::*;
 
 mod player_block_placement;
 pub use player_block_placement::*;
+
+mod use_item;
+pub use use_item::*;
+
+mod teleport_confirm;
+pub use teleport_confirm::*;
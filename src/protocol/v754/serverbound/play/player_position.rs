@@ -1,44 +1,40 @@
 use crate::{
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::Fid,
     packet::Packet,
-    parsable::Parsable,
-    raw_packet::RawPacket,
+    parsable::{self, Parsable},
+    plugin::first_reply,
     Direction, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerPosition)]
 pub struct PlayerPosition {
-    x: f64,
-    feet_y: f64,
-    z: f64,
-    on_ground: bool,
+    pub x: f64,
+    pub feet_y: f64,
+    pub z: f64,
+    pub on_ground: bool,
 }
 
 #[async_trait::async_trait]
 impl Parsable for PlayerPosition {
-    fn default() -> Self {
-        Self {
-            x: 0f64,
-            feet_y: 0f64,
-            z: 0f64,
-            on_ground: false,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.x = packet.decode_double()?;
-        self.feet_y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.on_ground = packet.decode_bool()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        // Movement sent before the latest teleport is confirmed gets ignored by the server.
+        if status.pending_teleport.is_none() {
+            if let Some(location) = &mut status.location {
+                location.x = self.x;
+                location.y = self.feet_y;
+                location.z = self.z;
+            }
+        }
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!("{} {} {} {}", self.x, self.feet_y, self.z, self.on_ground,)
-    }
-
     fn packet_editing(&self) -> bool {
         true
     }
@@ -49,26 +45,11 @@ impl Parsable for PlayerPosition {
         plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut return_vec = Vec::new();
-        for plugin in plugins {
-            match plugin.on_move(self.x, self.feet_y, self.z) {
-                Some(plugin_vec) => {
-                    return_vec.extend(plugin_vec);
-                    break;
-                }
-                None => continue,
-            }
-        }
+        let mut return_vec = first_reply(Fid::PlayerPosition, plugins, |plugin| {
+            plugin.on_move(self.x, self.feet_y, self.z)
+        });
         if !return_vec.is_empty() {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_double(self.x);
-            raw_packet.encode_double(self.feet_y);
-            raw_packet.encode_double(self.z);
-            raw_packet.encode_bool(self.on_ground);
-            return_vec.push((
-                Packet::from(raw_packet, fid_to_pid(Fid::PlayerPosition)),
-                Direction::Serverbound,
-            ));
+            return_vec.push((self.encode_packet()?, Direction::Serverbound));
         }
 
         Ok(return_vec)
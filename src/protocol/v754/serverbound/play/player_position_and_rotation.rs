@@ -1,53 +1,44 @@
 use crate::{
     conf::Configuration,
-    functions::{fid_to_pid, Fid},
+    functions::Fid,
     packet::Packet,
-    parsable::Parsable,
-    raw_packet::RawPacket,
+    parsable::{self, Parsable},
+    plugin::first_reply,
     Direction, SharedState,
 };
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(PlayerPositionRotation)]
 pub struct PlayerPositionRotation {
-    x: f64,
-    feet_y: f64,
-    z: f64,
-    yaw: f32,
-    pitch: f32,
-    on_ground: bool,
+    pub x: f64,
+    pub feet_y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
 }
 
 #[async_trait::async_trait]
 impl Parsable for PlayerPositionRotation {
-    fn default() -> Self {
-        Self {
-            x: 0f64,
-            feet_y: 0f64,
-            z: 0f64,
-            yaw: 0f32,
-            pitch: 0f32,
-            on_ground: false,
-        }
+    fn always_parse(&self) -> bool {
+        true
     }
 
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.x = packet.decode_double()?;
-        self.feet_y = packet.decode_double()?;
-        self.z = packet.decode_double()?;
-        self.yaw = packet.decode_float()?;
-        self.pitch = packet.decode_float()?;
-        self.on_ground = packet.decode_bool()?;
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        // Movement sent before the latest teleport is confirmed gets ignored by the server.
+        if status.pending_teleport.is_none() {
+            if let Some(location) = &mut status.location {
+                location.x = self.x;
+                location.y = self.feet_y;
+                location.z = self.z;
+                location.yaw = self.yaw;
+                location.pitch = self.pitch;
+            }
+        }
         Ok(())
     }
 
-    fn get_printable(&self) -> String {
-        format!(
-            "{} {} {} {} {} {}",
-            self.x, self.feet_y, self.z, self.yaw, self.pitch, self.on_ground,
-        )
-    }
-
     fn packet_editing(&self) -> bool {
         true
     }
@@ -58,28 +49,11 @@ impl Parsable for PlayerPositionRotation {
         plugins: &mut Vec<Box<dyn crate::plugin::EventHandler + Send>>,
         _config: &Configuration,
     ) -> Result<Vec<(Packet, Direction)>, ()> {
-        let mut return_vec = Vec::new();
-        for plugin in plugins {
-            match plugin.on_move(self.x, self.feet_y, self.z) {
-                Some(plugin_vec) => {
-                    return_vec.extend(plugin_vec);
-                    break;
-                }
-                None => continue,
-            }
-        }
+        let mut return_vec = first_reply(Fid::PlayerPositionRotation, plugins, |plugin| {
+            plugin.on_move(self.x, self.feet_y, self.z)
+        });
         if !return_vec.is_empty() {
-            let mut raw_packet = RawPacket::new();
-            raw_packet.encode_double(self.x);
-            raw_packet.encode_double(self.feet_y);
-            raw_packet.encode_double(self.z);
-            raw_packet.encode_float(self.yaw);
-            raw_packet.encode_float(self.pitch);
-            raw_packet.encode_bool(self.on_ground);
-            return_vec.push((
-                Packet::from(raw_packet, fid_to_pid(Fid::PlayerPositionRotation)),
-                Direction::Serverbound,
-            ));
+            return_vec.push((self.encode_packet()?, Direction::Serverbound));
         }
         Ok(return_vec)
     }
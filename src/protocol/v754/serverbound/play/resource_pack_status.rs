@@ -1,38 +1,20 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use serde::Serialize;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Packet)]
+#[packet(varint)]
 pub enum ResourcePackResponse {
+    #[default]
     Success,
     Declined,
     Failed,
     Accepted,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(ResourcePackStatus)]
 pub struct ResourcePackStatus {
-    result: ResourcePackResponse,
+    pub result: ResourcePackResponse,
 }
 
-impl Parsable for ResourcePackStatus {
-    fn default() -> Self {
-        Self {
-            result: ResourcePackResponse::Success,
-        }
-    }
-
-    fn parse_packet(&mut self, mut packet: RawPacket) -> Result<(), ()> {
-        self.result = match packet.decode_varint()? {
-            0 => ResourcePackResponse::Success,
-            1 => ResourcePackResponse::Declined,
-            2 => ResourcePackResponse::Failed,
-            3 => ResourcePackResponse::Accepted,
-            _ => return Err(()),
-        };
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("{:?}", self.result)
-    }
-}
+impl Parsable for ResourcePackStatus {}
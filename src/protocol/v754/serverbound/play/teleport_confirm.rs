@@ -0,0 +1,34 @@
+use crate::{
+    parsable::{self, Parsable},
+    SharedState,
+};
+use serde::Serialize;
+
+// The client answers every PlayerPositionAndLook with this.
+#[derive(Clone, Serialize, parsable::Packet)]
+#[packet(TeleportConfirm)]
+pub struct TeleportConfirm {
+    #[varint]
+    pub teleport_id: i32,
+}
+
+#[async_trait::async_trait]
+impl Parsable for TeleportConfirm {
+    fn always_parse(&self) -> bool {
+        true
+    }
+
+    fn update_status(&self, status: &mut SharedState) -> Result<(), ()> {
+        // Like the server only the latest teleport counts, confirming an older one doesn't do anything.
+        if status.pending_teleport == Some(self.teleport_id) {
+            status.pending_teleport = None;
+        } else {
+            log::debug!(
+                "Teleport {} confirmed, waiting for {:?}",
+                self.teleport_id,
+                status.pending_teleport
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use crate::parsable::{Packet, Parsable};
+use serde::Serialize;
+
+#[derive(Clone, Serialize, Packet)]
+#[packet(UseItem)]
+pub struct UseItem {
+    // 0 is the main hand, 1 the off hand.
+    #[varint]
+    pub hand: i32,
+}
+
+impl Parsable for UseItem {}
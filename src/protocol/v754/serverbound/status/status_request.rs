@@ -1,19 +1,8 @@
-use crate::{parsable::Parsable, raw_packet::RawPacket};
+use crate::parsable::{Packet, Parsable};
 use serde::Serialize;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Packet)]
+#[packet(StatusRequest)]
 pub struct StatusRequest {}
 
-impl Parsable for StatusRequest {
-    fn default() -> Self {
-        Self {}
-    }
-
-    fn parse_packet(&mut self, mut _packet: RawPacket) -> Result<(), ()> {
-        Ok(())
-    }
-
-    fn get_printable(&self) -> String {
-        format!("",)
-    }
-}
+impl Parsable for StatusRequest {}
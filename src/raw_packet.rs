@@ -1,22 +1,32 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{de, ser};
 use std::{
     convert::TryInto,
     io::{Read, Write},
 };
 
-use crate::types::{Slot, Uuid};
+use crate::{
+    chat::TextComponent,
+    parse_error::{ParseContext, ParseError, ParseErrorKind, ParseResult},
+    types::{
+        Metadata, MetadataEntry, MetadataValue, Particle, ParticleData, Slot, Uuid, VillagerData,
+    },
+};
 
 // RawPacket holds a raw (unparsed) packet.
+// The data is a cursor over a shared buffer, reading moves the start of it forward without copying anything.
+// The position is how many bytes have been read from it so far, it is used for the offset in errors.
 #[derive(Debug, Clone)]
 pub struct RawPacket {
-    data: Vec<u8>,
+    data: BytesMut,
+    position: usize,
 }
 
 impl Read for RawPacket {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        // (&mut self.data[..]).read(buf)
-        let n = Read::read(&mut &self.data[0..], buf)?;
-        self.data.drain(0..n);
+        let n = buf.len().min(self.data.len());
+        self.data.copy_to_slice(&mut buf[..n]);
+        self.position += n;
         Ok(n)
     }
 }
@@ -28,180 +38,340 @@ impl Write for RawPacket {
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        todo!()
+        Ok(())
     }
 }
 
 impl RawPacket {
     pub fn new() -> RawPacket {
-        RawPacket { data: Vec::new() }
+        RawPacket {
+            data: BytesMut::new(),
+            position: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> RawPacket {
+        RawPacket {
+            data: BytesMut::with_capacity(capacity),
+            position: 0,
+        }
     }
 
-    pub fn from(packet_data: Vec<u8>) -> RawPacket {
-        RawPacket { data: packet_data }
+    // This only copies the data if it is shared with something else.
+    pub fn from(packet_data: impl Into<Bytes>) -> RawPacket {
+        RawPacket {
+            data: BytesMut::from(packet_data.into()),
+            position: 0,
+        }
     }
 
     pub fn push(&mut self, data: u8) {
-        self.data.push(data)
+        self.data.put_u8(data)
     }
 
-    pub fn push_vec(&mut self, mut data: Vec<u8>) {
-        self.data.append(&mut data)
+    pub fn push_vec(&mut self, data: Vec<u8>) {
+        self.data.extend_from_slice(&data)
     }
 
     pub fn push_slice(&mut self, data: &[u8]) {
         self.data.extend_from_slice(data)
     }
 
+    // Adds data that was received to the end. If nothing is left this doesn't copy anything.
+    pub fn push_bytes(&mut self, data: BytesMut) {
+        self.data.unsplit(data)
+    }
+
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn get_slice(&self) -> &[u8] {
-        self.data.as_slice()
+        &self.data
+    }
+
+    pub fn get_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data
     }
 
+    // Copies the data, use into_bytes if the packet isn't needed anymore.
     pub fn get_vec(&self) -> Vec<u8> {
-        self.data.clone()
+        self.data.to_vec()
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.data.freeze()
     }
 
     pub fn clear(&mut self) {
-        self.data = Vec::new();
+        self.data.clear();
+        self.position = 0;
     }
 
-    pub fn read(&mut self, amount: usize) -> Result<Vec<u8>, ()> {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Makes an error at the current position.
+    pub fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.position)
+    }
+
+    fn check_available(&self, amount: usize) -> ParseResult<()> {
         if self.data.len() < amount {
-            return Err(());
+            return Err(self.error(ParseErrorKind::NotEnoughData {
+                needed: amount,
+                available: self.data.len(),
+            }));
         }
-        let to_be_returned = self.data.drain(0..amount);
-        let read_value = to_be_returned.collect::<Vec<u8>>();
-        Ok(read_value)
+        Ok(())
+    }
+
+    // Takes the next bytes off the front, this doesn't copy them.
+    pub fn read(&mut self, amount: usize) -> ParseResult<BytesMut> {
+        self.check_available(amount)?;
+        self.position += amount;
+        Ok(self.data.split_to(amount))
+    }
+
+    // Reads part of the packet as its own packet, errors in it still have the offset from the start of this one.
+    pub fn read_packet(&mut self, amount: usize) -> ParseResult<RawPacket> {
+        let position = self.position;
+        Ok(RawPacket {
+            data: self.read(amount)?,
+            position,
+        })
+    }
+
+    fn read_array<const N: usize>(&mut self) -> ParseResult<[u8; N]> {
+        self.check_available(N)?;
+        let mut array = [0; N];
+        self.data.copy_to_slice(&mut array);
+        self.position += N;
+        Ok(array)
     }
 
     pub fn set(&mut self, value: Vec<u8>) {
-        self.data = value;
+        self.data = BytesMut::from(&value[..]);
+        self.position = 0;
     }
 
     pub fn prepend_length(&mut self) {
-        let mut prepending = Self::new();
+        let mut prepending = Self::with_capacity(self.data.len() + 5);
         prepending.encode_varint(self.data.len() as i32);
-        prepending.push_vec(self.data.clone());
-        self.set(prepending.get_vec());
+        prepending.push_slice(&self.data);
+        *self = prepending;
     }
 
-    pub fn decode_bool(&mut self) -> Result<bool, ()> {
-        Ok(match self.read(1)?[0] {
+    pub fn decode_bool(&mut self) -> ParseResult<bool> {
+        Ok(match self.read_array::<1>()?[0] {
             0x00 => false,
             0x01 => true,
-            _ => return Err(()),
+            value => return Err(self.error(ParseErrorKind::InvalidBool(value))),
         })
     }
 
-    pub fn decode_byte(&mut self) -> Result<i8, ()> {
-        Ok(i8::from_be_bytes(self.read(1)?.try_into().unwrap()))
+    pub fn decode_byte(&mut self) -> ParseResult<i8> {
+        Ok(i8::from_be_bytes(self.read_array::<1>()?))
     }
 
-    pub fn decode_ubyte(&mut self) -> Result<u8, ()> {
-        Ok(self.read(1)?[0])
+    pub fn decode_ubyte(&mut self) -> ParseResult<u8> {
+        Ok(self.read_array::<1>()?[0])
     }
 
-    pub fn decode_short(&mut self) -> Result<i16, ()> {
-        Ok(i16::from_be_bytes(self.read(2)?.try_into().unwrap()))
+    pub fn decode_short(&mut self) -> ParseResult<i16> {
+        Ok(i16::from_be_bytes(self.read_array::<2>()?))
     }
 
-    pub fn decode_ushort(&mut self) -> Result<u16, ()> {
-        Ok(u16::from_be_bytes(self.read(2)?.try_into().unwrap()))
+    pub fn decode_ushort(&mut self) -> ParseResult<u16> {
+        Ok(u16::from_be_bytes(self.read_array::<2>()?))
     }
 
-    pub fn decode_int(&mut self) -> Result<i32, ()> {
-        Ok(i32::from_be_bytes(self.read(4)?.try_into().unwrap()))
+    pub fn decode_int(&mut self) -> ParseResult<i32> {
+        Ok(i32::from_be_bytes(self.read_array::<4>()?))
     }
 
-    pub fn decode_long(&mut self) -> Result<i64, ()> {
-        Ok(i64::from_be_bytes(self.read(8)?.try_into().unwrap()))
+    pub fn decode_long(&mut self) -> ParseResult<i64> {
+        Ok(i64::from_be_bytes(self.read_array::<8>()?))
     }
 
-    pub fn decode_ulong(&mut self) -> Result<u64, ()> {
-        Ok(u64::from_le_bytes(self.read(8)?.try_into().unwrap()))
+    pub fn decode_ulong(&mut self) -> ParseResult<u64> {
+        Ok(u64::from_be_bytes(self.read_array::<8>()?))
     }
 
-    pub fn decode_float(&mut self) -> Result<f32, ()> {
-        Ok(f32::from_be_bytes(self.read(4)?.try_into().unwrap()))
+    pub fn decode_float(&mut self) -> ParseResult<f32> {
+        Ok(f32::from_be_bytes(self.read_array::<4>()?))
     }
 
-    pub fn decode_double(&mut self) -> Result<f64, ()> {
-        Ok(f64::from_be_bytes(self.read(8)?.try_into().unwrap()))
+    pub fn decode_double(&mut self) -> ParseResult<f64> {
+        Ok(f64::from_be_bytes(self.read_array::<8>()?))
     }
 
-    pub fn decode_string(&mut self) -> Result<String, ()> {
+    pub fn decode_string(&mut self) -> ParseResult<String> {
         let string_length = self.decode_varint()?;
-        Ok(String::from_utf8(self.read(string_length.try_into().unwrap())?).unwrap())
+        let string_length: usize = match string_length.try_into() {
+            Ok(string_length) => string_length,
+            Err(_) => {
+                return Err(self.error(ParseErrorKind::InvalidLength(string_length as i64)));
+            }
+        };
+        let start = self.position;
+        String::from_utf8(self.read(string_length)?.to_vec())
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidUtf8, start))
     }
 
-    pub fn decode_chat(&mut self) -> Result<String, ()> {
-        self.decode_string()
+    pub fn decode_chat(&mut self) -> ParseResult<TextComponent> {
+        let start = self.position;
+        let json = self.decode_string()?;
+        serde_json::from_str(&json)
+            .map_err(|why| ParseError::new(ParseErrorKind::InvalidJson(why.to_string()), start))
     }
 
-    pub fn decode_identifier(&mut self) -> Result<String, ()> {
+    pub fn decode_identifier(&mut self) -> ParseResult<String> {
         self.decode_string()
     }
 
-    pub fn decode_varint(&mut self) -> Result<i32, ()> {
+    pub fn decode_varint(&mut self) -> ParseResult<i32> {
         let mut num_read = 0;
         let mut result: i32 = 0;
         let mut read: u8;
         loop {
-            read = self.read(1)?[0];
+            read = self.read_array::<1>()?[0];
             let value: i32 = (read & 0x7F) as i32;
             result |= value << (7 * num_read);
 
             num_read += 1;
-            if num_read > 5 {
-                return Err(());
-            }
             if (read & 0x80) == 0 {
                 break;
             }
+            // Checked before reading the next byte, so the shift above can't overflow.
+            if num_read >= 5 {
+                return Err(self.error(ParseErrorKind::VarIntTooLong));
+            }
         }
         Ok(result)
     }
 
-    pub fn decode_varlong(&mut self) -> Result<i64, ()> {
+    pub fn decode_varlong(&mut self) -> ParseResult<i64> {
         let mut num_read = 0;
         let mut result: i64 = 0;
         let mut read: u8;
         loop {
-            read = self.read(1)?[0];
+            read = self.read_array::<1>()?[0];
             let value: i64 = (read & 0x7F) as i64;
             result |= value << (7 * num_read);
 
             num_read += 1;
-            if num_read > 10 {
-                return Err(());
-            }
             if (read & 0x80) == 0 {
                 break;
             }
+            // Checked before reading the next byte, so the shift above can't overflow.
+            if num_read >= 10 {
+                return Err(self.error(ParseErrorKind::VarIntTooLong));
+            }
         }
         Ok(result)
     }
 
-    pub fn decode_entity_metadata(&mut self) -> Result<(), ()> {
-        // varies, not yet needed so not yet implemented.
-        todo!()
+    pub fn decode_entity_metadata(&mut self) -> ParseResult<Metadata> {
+        let mut entries = vec![];
+        loop {
+            let index = self.decode_ubyte()?;
+            if index == 0xff {
+                return Ok(Metadata { entries });
+            }
+            let value = self
+                .decode_metadata_value()
+                .map_err(|why| why.in_field(&index.to_string()))?;
+            entries.push(MetadataEntry { index, value });
+        }
+    }
+
+    fn decode_metadata_value(&mut self) -> ParseResult<MetadataValue> {
+        let start = self.position;
+        Ok(match self.decode_varint()? {
+            0 => MetadataValue::Byte(self.decode_byte()?),
+            1 => MetadataValue::VarInt(self.decode_varint()?),
+            2 => MetadataValue::Float(self.decode_float()?),
+            3 => MetadataValue::String(self.decode_string()?),
+            4 => MetadataValue::Chat(self.decode_chat()?),
+            5 => MetadataValue::OptChat(match self.decode_bool()? {
+                true => Some(self.decode_chat()?),
+                false => None,
+            }),
+            6 => MetadataValue::Slot(self.decode_slot()?),
+            7 => MetadataValue::Boolean(self.decode_bool()?),
+            8 => MetadataValue::Rotation(
+                self.decode_float()?,
+                self.decode_float()?,
+                self.decode_float()?,
+            ),
+            9 => MetadataValue::Position(self.decode_position()?),
+            10 => MetadataValue::OptPosition(match self.decode_bool()? {
+                true => Some(self.decode_position()?),
+                false => None,
+            }),
+            11 => MetadataValue::Direction(self.decode_varint()?),
+            12 => MetadataValue::OptUuid(match self.decode_bool()? {
+                true => Some(self.decode_uuid()?),
+                false => None,
+            }),
+            13 => MetadataValue::BlockId(self.decode_varint()?),
+            14 => MetadataValue::Nbt(if self.decode_nbt_end()? {
+                nbt::Blob::new()
+            } else {
+                self.decode_nbt_blob()?
+            }),
+            15 => MetadataValue::Particle(self.decode_particle()?),
+            16 => MetadataValue::VillagerData(VillagerData {
+                villager_type: self.decode_varint()?,
+                profession: self.decode_varint()?,
+                level: self.decode_varint()?,
+            }),
+            // 0 is absent, otherwise it's the value + 1.
+            17 => MetadataValue::OptVarInt(match self.decode_varint()? {
+                0 => None,
+                value => Some(value - 1),
+            }),
+            18 => MetadataValue::Pose(self.decode_varint()?),
+            other => {
+                return Err(ParseError::new(
+                    ParseErrorKind::InvalidValue(other as i64),
+                    start,
+                ))
+            }
+        })
     }
 
-    pub fn decode_slot(&mut self) -> Result<Slot, ()> {
+    pub fn decode_particle(&mut self) -> ParseResult<Particle> {
+        let id = self.decode_varint()?;
+        let data = match id {
+            Particle::BLOCK | Particle::FALLING_DUST => ParticleData::Block(self.decode_varint()?),
+            Particle::DUST => ParticleData::Dust {
+                red: self.decode_float()?,
+                green: self.decode_float()?,
+                blue: self.decode_float()?,
+                scale: self.decode_float()?,
+            },
+            Particle::ITEM => ParticleData::Item(self.decode_slot()?),
+            _ => ParticleData::None,
+        };
+        Ok(Particle { id, data })
+    }
+
+    pub fn decode_slot(&mut self) -> ParseResult<Slot> {
         let present = self.decode_bool()?;
         if present {
-            let item_id = self.decode_varint()?;
-            let item_count = self.decode_byte()?;
-            let nbt = if self.get_vec()[0] == 0 {
-                // DO NOT REMOVE, this is to make the empty NBT get not get parsed as another slot later.
-                self.read(1)?;
+            let item_id = self.decode_varint().field("item_id")?;
+            let item_count = self.decode_byte().field("item_count")?;
+            let nbt = if self.decode_nbt_end().field("nbt")? {
                 nbt::Blob::new()
             } else {
-                self.decode_nbt_blob()?
+                self.decode_nbt_blob().field("nbt")?
             };
             Ok(Slot {
                 present,
@@ -219,34 +389,52 @@ impl RawPacket {
         }
     }
 
-    pub fn decode_nbt<T>(&mut self) -> Result<T, ()>
+    // Reads the byte for empty NBT (TAG_End) if that's what comes next.
+    fn decode_nbt_end(&mut self) -> ParseResult<bool> {
+        match self.data.first() {
+            Some(0) => {
+                // DO NOT REMOVE, this is to make the empty NBT get not get parsed as another slot later.
+                self.read(1)?;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(self.error(ParseErrorKind::NotEnoughData {
+                needed: 1,
+                available: 0,
+            })),
+        }
+    }
+
+    pub fn decode_nbt<T>(&mut self) -> ParseResult<T>
     where
         T: de::DeserializeOwned,
     {
-        Ok(nbt::from_reader(self).unwrap())
+        let start = self.position;
+        nbt::from_reader(&mut *self)
+            .map_err(|why| ParseError::new(ParseErrorKind::InvalidNbt(why.to_string()), start))
     }
 
-    pub fn decode_nbt_blob(&mut self) -> Result<nbt::Blob, ()> {
-        Ok(nbt::Blob::from_reader(self).unwrap())
+    pub fn decode_nbt_blob(&mut self) -> ParseResult<nbt::Blob> {
+        let start = self.position;
+        nbt::Blob::from_reader(&mut *self)
+            .map_err(|why| ParseError::new(ParseErrorKind::InvalidNbt(why.to_string()), start))
     }
 
-    pub fn decode_position(&mut self) -> Result<(i32, i32, i32), ()> {
-        let val = i64::from_be_bytes(self.read(8)?.try_into().unwrap());
+    pub fn decode_position(&mut self) -> ParseResult<(i32, i32, i32)> {
+        let val = i64::from_be_bytes(self.read_array::<8>()?);
         Ok((
             (val >> 38) as i32,
-            (val & 0xFFF) as i32,
+            (val << 52 >> 52) as i32,
             (val << 26 >> 38) as i32,
         ))
     }
 
-    pub fn decode_angle(&mut self) -> Result<u8, ()> {
-        Ok(self.read(1)?[0])
+    pub fn decode_angle(&mut self) -> ParseResult<u8> {
+        Ok(self.read_array::<1>()?[0])
     }
 
-    pub fn decode_uuid(&mut self) -> Result<Uuid, ()> {
-        Ok(Uuid::from(u128::from_be_bytes(
-            self.read(16)?.try_into().unwrap(),
-        )))
+    pub fn decode_uuid(&mut self) -> ParseResult<Uuid> {
+        Ok(Uuid::from(u128::from_be_bytes(self.read_array::<16>()?)))
     }
 
     pub fn encode_bool(&mut self, value: bool) {
@@ -297,8 +485,8 @@ impl RawPacket {
         self.push_slice(message.as_bytes());
     }
 
-    pub fn encode_chat(&mut self, message: String) {
-        self.encode_string(message);
+    pub fn encode_chat(&mut self, message: &TextComponent) {
+        self.encode_string(serde_json::to_string(message).unwrap());
     }
 
     pub fn encode_identifier(&mut self, message: String) {
@@ -335,9 +523,82 @@ impl RawPacket {
         }
     }
 
-    pub fn encode_entity_metadata(&mut self) {
-        // varies, not yet needed so not yet implemented.
-        todo!()
+    pub fn encode_entity_metadata(&mut self, metadata: &Metadata) {
+        for entry in &metadata.entries {
+            self.encode_ubyte(entry.index);
+            self.encode_varint(entry.value.type_id());
+            match &entry.value {
+                MetadataValue::Byte(value) => self.encode_byte(*value),
+                MetadataValue::VarInt(value)
+                | MetadataValue::Direction(value)
+                | MetadataValue::BlockId(value)
+                | MetadataValue::Pose(value) => self.encode_varint(*value),
+                MetadataValue::Float(value) => self.encode_float(*value),
+                MetadataValue::String(value) => self.encode_string(value.to_owned()),
+                MetadataValue::Chat(value) => self.encode_chat(value),
+                MetadataValue::OptChat(value) => {
+                    self.encode_bool(value.is_some());
+                    if let Some(value) = value {
+                        self.encode_chat(value);
+                    }
+                }
+                MetadataValue::Slot(slot) => self.encode_slot(slot.to_owned()),
+                MetadataValue::Boolean(value) => self.encode_bool(*value),
+                MetadataValue::Rotation(x, y, z) => {
+                    self.encode_float(*x);
+                    self.encode_float(*y);
+                    self.encode_float(*z);
+                }
+                MetadataValue::Position(position) => self.encode_position(*position),
+                MetadataValue::OptPosition(position) => {
+                    self.encode_bool(position.is_some());
+                    if let Some(position) = position {
+                        self.encode_position(*position);
+                    }
+                }
+                MetadataValue::OptUuid(uuid) => {
+                    self.encode_bool(uuid.is_some());
+                    if let Some(uuid) = uuid {
+                        self.encode_uuid(uuid.value());
+                    }
+                }
+                MetadataValue::Nbt(blob) => {
+                    if *blob != nbt::Blob::new() {
+                        self.encode_nbt_blob(blob.to_owned());
+                    } else {
+                        self.push(0);
+                    }
+                }
+                MetadataValue::Particle(particle) => self.encode_particle(particle),
+                MetadataValue::VillagerData(data) => {
+                    self.encode_varint(data.villager_type);
+                    self.encode_varint(data.profession);
+                    self.encode_varint(data.level);
+                }
+                MetadataValue::OptVarInt(value) => self.encode_varint(value.map_or(0, |v| v + 1)),
+            }
+        }
+        self.encode_ubyte(0xff);
+    }
+
+    pub fn encode_particle(&mut self, particle: &Particle) {
+        self.encode_varint(particle.id);
+        match &particle.data {
+            ParticleData::None => {}
+            ParticleData::Block(state) => self.encode_varint(*state),
+            ParticleData::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            } => {
+                self.encode_float(*red);
+                self.encode_float(*green);
+                self.encode_float(*blue);
+                self.encode_float(*scale);
+            }
+            ParticleData::Item(slot) => self.encode_slot(slot.to_owned()),
+        }
     }
 
     pub fn encode_slot(&mut self, data: Slot) {
@@ -414,6 +675,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ulong() {
+        let mut packet = RawPacket::new();
+        packet.encode_ulong(0x0102_0304_0506_0708);
+        assert_eq!(packet.get_vec(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(packet.decode_ulong().unwrap(), 0x0102_0304_0506_0708);
+    }
+
     #[test]
     fn test_varint_writing() {
         let mut packet = RawPacket::new();
@@ -530,6 +799,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_errors() {
+        let mut packet = RawPacket::from(vec![0x01, 0x02, 0x05, 0x61]);
+        assert!(packet.decode_bool().unwrap());
+        assert_eq!(
+            packet.decode_bool().field("on_ground"),
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidBool(0x02),
+                field: Some("on_ground".to_string()),
+                offset: 2,
+            })
+        );
+        assert_eq!(
+            packet.decode_string().unwrap_err(),
+            ParseError::new(
+                ParseErrorKind::NotEnoughData {
+                    needed: 5,
+                    available: 1
+                },
+                3
+            )
+        );
+
+        let mut packet = RawPacket::from(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+        assert_eq!(
+            packet.decode_varint().unwrap_err().kind,
+            ParseErrorKind::VarIntTooLong
+        );
+
+        let mut packet = RawPacket::from(vec![0x02, 0xc3, 0x28]);
+        assert_eq!(
+            packet.decode_string().unwrap_err(),
+            ParseError::new(ParseErrorKind::InvalidUtf8, 1)
+        );
+
+        // A slot with an item but without the NBT after it.
+        let mut packet = RawPacket::from(vec![0x01, 0x01, 0x01]);
+        assert_eq!(
+            packet.decode_slot().field("slot").unwrap_err().field,
+            Some("slot.nbt".to_string())
+        );
+    }
+
     #[test]
     fn test_read() {
         let mut packet = RawPacket::new();
@@ -566,4 +878,55 @@ mod tests {
         assert_eq!(raw_packet.len(), 0);
         assert_eq!(nbt_data, nbt);
     }
+
+    #[test]
+    fn test_entity_metadata() {
+        // Sneaking (flags and pose), an absent OptVarInt and a red dust particle.
+        let data = vec![
+            0x00, 0x00, 0x02, 0x06, 0x12, 0x05, 0x08, 0x11, 0x00, 0x09, 0x0F, 0x0E, 0x3F, 0x80,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3F, 0x80, 0x00, 0x00,
+            0xFF,
+        ];
+        let metadata = Metadata {
+            entries: vec![
+                MetadataEntry {
+                    index: 0,
+                    value: MetadataValue::Byte(0x02),
+                },
+                MetadataEntry {
+                    index: 6,
+                    value: MetadataValue::Pose(5),
+                },
+                MetadataEntry {
+                    index: 8,
+                    value: MetadataValue::OptVarInt(None),
+                },
+                MetadataEntry {
+                    index: 9,
+                    value: MetadataValue::Particle(Particle {
+                        id: Particle::DUST,
+                        data: ParticleData::Dust {
+                            red: 1.0,
+                            green: 0.0,
+                            blue: 0.0,
+                            scale: 1.0,
+                        },
+                    }),
+                },
+            ],
+        };
+
+        let mut packet = RawPacket::from(data.clone());
+        assert_eq!(packet.decode_entity_metadata().unwrap(), metadata);
+        assert!(packet.is_empty());
+
+        let mut packet = RawPacket::new();
+        packet.encode_entity_metadata(&metadata);
+        assert_eq!(packet.get_vec(), data);
+
+        // There are no types after 18.
+        let mut packet = RawPacket::from(vec![0x00, 0x13, 0x00, 0xFF]);
+        let error = packet.decode_entity_metadata().unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidValue(19));
+    }
 }
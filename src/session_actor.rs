@@ -0,0 +1,291 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::FutureExt;
+use parking_lot::Mutex;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::timeout,
+};
+use tracing::Instrument;
+
+use crate::{
+    circuit_breaker::panic_message,
+    compression::Compressor,
+    conf::Configuration,
+    functions::Fid,
+    parsable::Parsable,
+    plugin::Plugins,
+    sessions::{send_data, SessionInfo},
+    tab_list::TabList,
+    types::Queues,
+    Ciphers, Direction, SharedState, State, SHUTDOWN_CHECK_TIMEOUT,
+};
+
+pub type EventSender = mpsc::UnboundedSender<SessionEvent>;
+
+// Everything that changes the state or the plugins of a session goes through its actor.
+// The actor handles one event at a time, so the two directions can't overwrite each other's changes.
+pub enum SessionEvent {
+    // A packet that was parsed. The actor updates the state, runs the plugins and sends the result (or the original frame).
+    // The reply is sent when the actor is done with the packet, with an error if something about it failed.
+    Packet {
+        packet: Box<dyn Parsable + Send + Sync>,
+        fid: Fid,
+        direction: Direction,
+        frame: Bytes,
+        config: Arc<Configuration>,
+        reply: oneshot::Sender<Result<(), PacketFailure>>,
+    },
+}
+
+// Why the actor couldn't handle a packet. If the state couldn't be updated nothing was sent,
+// the original frame is given back then so the parser can forward it like a packet that didn't parse.
+pub struct PacketFailure {
+    pub reason: String,
+    pub unsent: Option<Bytes>,
+}
+
+impl PacketFailure {
+    // The packet (or what it was replaced with) was sent already.
+    fn sent(reason: String) -> Self {
+        Self {
+            reason,
+            unsent: None,
+        }
+    }
+}
+
+pub struct SessionActor {
+    status: SharedState,
+    plugins: Plugins,
+    // This is what the rest of the proxy sees of the state, it is updated after every event.
+    info: Arc<Mutex<SessionInfo>>,
+    // A copy of the tab list, which is only updated when it changes.
+    tab_list: Arc<Mutex<TabList>>,
+    ciphers: Arc<Ciphers>,
+    compressor: Arc<Mutex<Compressor>>,
+    queues: Queues,
+}
+
+impl SessionActor {
+    pub fn new(
+        status: SharedState,
+        plugins: Plugins,
+        ciphers: Arc<Ciphers>,
+        compressor: Arc<Mutex<Compressor>>,
+        queues: Queues,
+    ) -> Self {
+        let info = Arc::new(Mutex::new(SessionInfo {
+            connection_id: status.connection_id.clone(),
+            username: status.username.clone(),
+            server_ip: status.server_ip.clone(),
+            user_ip: status.user_ip.clone(),
+            state: status.state,
+            compress: status.compress,
+        }));
+        Self {
+            status,
+            plugins,
+            info,
+            tab_list: Arc::new(Mutex::new(TabList::new())),
+            ciphers,
+            compressor,
+            queues,
+        }
+    }
+
+    pub fn info(&self) -> Arc<Mutex<SessionInfo>> {
+        self.info.clone()
+    }
+
+    pub fn tab_list(&self) -> Arc<Mutex<TabList>> {
+        self.tab_list.clone()
+    }
+
+    // Runs until the connection is closed.
+    pub async fn run(
+        mut self,
+        mut events: mpsc::UnboundedReceiver<SessionEvent>,
+        is_closed: Arc<AtomicBool>,
+    ) {
+        loop {
+            let event =
+                match timeout(Duration::from_millis(SHUTDOWN_CHECK_TIMEOUT), events.recv()).await {
+                    Ok(Some(event)) => event,
+                    Ok(None) => return,
+                    Err(_) => {
+                        if is_closed.load(Ordering::Relaxed) {
+                            return;
+                        } else {
+                            continue;
+                        }
+                    }
+                };
+            match event {
+                SessionEvent::Packet {
+                    packet,
+                    fid,
+                    direction,
+                    frame,
+                    config,
+                    reply,
+                } => {
+                    let result = self
+                        .handle_packet(packet, &fid, direction, frame, &config)
+                        .await;
+                    self.publish();
+                    // The parser might be gone already if the connection was closed.
+                    let _ = reply.send(result);
+                }
+            }
+        }
+    }
+
+    async fn handle_packet(
+        &mut self,
+        packet: Box<dyn Parsable + Send + Sync>,
+        fid: &Fid,
+        direction: Direction,
+        frame: Bytes,
+        config: &Configuration,
+    ) -> Result<(), PacketFailure> {
+        // Everything that happens during login gets its own span, so it's clear which step went wrong.
+        let phase_span = if self.status.state == State::Login {
+            tracing::info_span!("login", packet = %fid)
+        } else {
+            tracing::Span::none()
+        };
+
+        // A panic here is caught like one while parsing, the state might be half updated but the connection keeps going.
+        let updated = panic::catch_unwind(AssertUnwindSafe(|| {
+            phase_span.in_scope(|| packet.update_status(&mut self.status))
+        }));
+        let update_error = match updated {
+            Ok(Ok(())) => None,
+            Ok(Err(())) => Some("could not update the state".to_string()),
+            Err(panic) => Some(format!(
+                "panicked while updating the state: {}",
+                panic_message(&*panic)
+            )),
+        };
+        if let Some(reason) = update_error {
+            log::error!("{} {}: {}, sending it unchanged", direction, fid, reason);
+            return Err(PacketFailure {
+                reason,
+                unsent: Some(frame),
+            });
+        }
+        if fid == &Fid::LoginStart {
+            tracing::Span::current().record("username", self.status.username.as_str());
+            // The username has to be known before the edit, which can take a while.
+            self.publish();
+        }
+        if matches!(fid, Fid::PlayerInfo | Fid::PlayerListHeaderFooter) {
+            *self.tab_list.lock() = self.status.tab_list.clone();
+        }
+
+        let mut out_data = vec![(frame, direction)];
+        let mut result = Ok(());
+        // Packet editing takes a lot more time, so it only gets executed if it is needed.
+        if packet.packet_editing() {
+            match self.edit_packet(&*packet, config, &phase_span).await {
+                Ok(Some(edited)) => out_data = edited,
+                Ok(None) => {}
+                Err(e) => {
+                    // The original packet is sent. The plugins and state are changed in place, so their changes stay.
+                    log::error!("{} {}: {}, sending it unchanged", direction, fid, e);
+                    result = Err(PacketFailure::sent(e));
+                }
+            }
+        }
+
+        // This has to be sent before the post send update, because that might enable encryption.
+        for (data, to_direction) in out_data {
+            send_data(&self.queues, &self.ciphers, data, to_direction);
+        }
+
+        let post_send = panic::catch_unwind(AssertUnwindSafe(|| {
+            phase_span.in_scope(|| packet.post_send_update(&self.ciphers, &self.status))
+        }));
+        let post_send_error = match post_send {
+            Ok(Ok(())) => None,
+            Ok(Err(())) => Some("post send update failed".to_string()),
+            Err(panic) => Some(format!(
+                "panicked in the post send update: {}",
+                panic_message(&*panic)
+            )),
+        };
+        match post_send_error {
+            Some(reason) => {
+                log::error!("{} {}: {}", direction, fid, reason);
+                Err(PacketFailure::sent(reason))
+            }
+            None => result,
+        }
+    }
+
+    // Returns the encoded packets that should be sent instead of the original, or None if the original should be sent.
+    async fn edit_packet(
+        &mut self,
+        packet: &(dyn Parsable + Send + Sync),
+        config: &Configuration,
+        phase_span: &tracing::Span,
+    ) -> Result<Option<Vec<(Bytes, Direction)>>, String> {
+        let mut plugins = self.plugins.take_enabled();
+        let edited = AssertUnwindSafe(
+            packet
+                .edit_packet(&mut self.status, &mut plugins, config)
+                .instrument(phase_span.clone()),
+        )
+        .catch_unwind()
+        .await;
+        self.plugins.put_back(plugins);
+
+        // All the packets are encoded before anything is sent, so nothing is sent twice if one of them fails.
+        let compress = self.status.compress;
+        match edited {
+            Ok(Ok(packet_vec)) if packet_vec.is_empty() => Ok(None),
+            Ok(Ok(packet_vec)) => match panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut compressor = self.compressor.lock();
+                compressor.configure(config);
+                packet_vec
+                    .iter()
+                    .map(|(packet, new_direction)| {
+                        let threshold = compressor.threshold(compress, *new_direction);
+                        Ok((packet.get_data(threshold, &mut compressor)?, *new_direction))
+                    })
+                    .collect::<Result<Vec<(Bytes, Direction)>, ()>>()
+            })) {
+                Ok(Ok(encoded)) => Ok(Some(encoded)),
+                Ok(Err(())) => Err("could not encode the edited packet".to_string()),
+                Err(panic) => Err(format!(
+                    "panicked while encoding: {}",
+                    panic_message(&*panic)
+                )),
+            },
+            Ok(Err(())) => Err("could not edit the packet".to_string()),
+            Err(panic) => Err(format!(
+                "panicked while editing: {}",
+                panic_message(&*panic)
+            )),
+        }
+    }
+
+    // Copies the parts of the state that can change to the info the rest of the proxy sees.
+    fn publish(&self) {
+        let mut info = self.info.lock();
+        info.state = self.status.state;
+        info.compress = self.status.compress;
+        if info.username != self.status.username {
+            info.username = self.status.username.clone();
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::{
+    compression::Compressor,
+    functions::Fid,
+    packet::Packet,
+    plugin::PluginSettings,
+    session_actor::EventSender,
+    tab_list::TabList,
+    types::{Ciphers, Direction, Queues, State},
+};
+
+// A handle to everything that is needed to talk to a running connection from outside of its own threads.
+// The state and the plugins are owned by the session actor, events for it are sent with `events`.
+#[derive(Clone)]
+pub struct SessionHandle {
+    pub info: Arc<Mutex<SessionInfo>>,
+    pub ciphers: Arc<Ciphers>,
+    // Shared with the session actor.
+    pub compressor: Arc<Mutex<Compressor>>,
+    pub queues: Queues,
+    pub is_closed: Arc<AtomicBool>,
+    pub events: EventSender,
+    pub plugin_settings: Arc<Mutex<PluginSettings>>,
+    pub counters: Arc<Mutex<PacketCounters>>,
+    pub tab_list: Arc<Mutex<TabList>>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionInfo {
+    pub connection_id: String,
+    pub username: String,
+    pub server_ip: String,
+    pub user_ip: String,
+    pub state: State,
+    pub compress: u32,
+}
+
+// Counts the packets (and their size) that went through a connection.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PacketCounters {
+    pub serverbound: DirectionCounters,
+    pub clientbound: DirectionCounters,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct DirectionCounters {
+    pub packets: u64,
+    pub bytes: u64,
+    pub by_type: HashMap<String, u64>,
+}
+
+impl PacketCounters {
+    pub fn record(&mut self, direction: Direction, fid: &Fid, length: usize) {
+        let counters = match direction {
+            Direction::Serverbound => &mut self.serverbound,
+            Direction::Clientbound => &mut self.clientbound,
+        };
+        counters.packets += 1;
+        counters.bytes += length as u64;
+        *counters.by_type.entry(fid.to_string()).or_insert(0) += 1;
+    }
+}
+
+impl SessionHandle {
+    pub fn info(&self) -> SessionInfo {
+        self.info.lock().clone()
+    }
+
+    pub fn state(&self) -> State {
+        self.info.lock().state
+    }
+
+    pub fn compress(&self) -> u32 {
+        self.info.lock().compress
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed.load(Ordering::Relaxed)
+    }
+
+    // Sends a packet that was made by the proxy to either the client or the server.
+    pub fn send_packet(&self, packet: Packet, direction: Direction) -> Result<(), ()> {
+        let data = {
+            let mut compressor = self.compressor.lock();
+            let threshold = compressor.threshold(self.compress(), direction);
+            packet.get_data(threshold, &mut compressor)?
+        };
+        send_data(&self.queues, &self.ciphers, data, direction);
+        Ok(())
+    }
+
+    // Closes the connection, everything that is still queued will be sent first.
+    pub fn close(&self) {
+        self.is_closed.store(true, Ordering::Release);
+    }
+}
+
+// Pushes already encoded data to the right queue.
+// Serverbound data is encrypted while the cipher is locked, so data sent from different threads can't get out of order.
+// Only encrypted data is copied, the rest is sent with the same buffer it was received in.
+pub fn send_data(queues: &Queues, ciphers: &Ciphers, data: Bytes, direction: Direction) {
+    match direction {
+        Direction::Serverbound => {
+            let mut cipher = ciphers.ps_cipher.lock();
+            if cipher.is_enabled() {
+                let mut data = BytesMut::from(data);
+                cipher.encrypt(&mut data);
+                queues.proxy_server.push(data.freeze());
+            } else {
+                queues.proxy_server.push(data);
+            }
+        }
+        Direction::Clientbound => queues.proxy_client.push(data),
+    }
+}
+
+// All the connections that are currently open, by connection ID.
+#[derive(Clone, Default)]
+pub struct Sessions {
+    map: Arc<Mutex<HashMap<String, SessionHandle>>>,
+    // Connections that were accepted, but aren't connected to the server yet. They only have a state.
+    pending: Arc<Mutex<HashMap<String, State>>>,
+}
+
+// A connection that doesn't have a session yet, it stops being pending when it is dropped or its session is inserted.
+pub struct PendingConnection {
+    sessions: Sessions,
+    connection_id: String,
+}
+
+impl PendingConnection {
+    pub fn set_state(&self, state: State) {
+        if let Some(pending_state) = self.sessions.pending.lock().get_mut(&self.connection_id) {
+            *pending_state = state;
+        }
+    }
+}
+
+impl Drop for PendingConnection {
+    fn drop(&mut self) {
+        self.sessions.pending.lock().remove(&self.connection_id);
+    }
+}
+
+impl Sessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Called when a connection is accepted, so it is counted before it has a session.
+    pub fn accept(&self, connection_id: &str) -> PendingConnection {
+        self.pending
+            .lock()
+            .insert(connection_id.to_string(), State::Handshaking);
+        PendingConnection {
+            sessions: self.clone(),
+            connection_id: connection_id.to_string(),
+        }
+    }
+
+    pub fn insert(&self, connection_id: String, handle: SessionHandle) {
+        self.pending.lock().remove(&connection_id);
+        let mut map = self.map.lock();
+        // Closed connections are removed here, so the map doesn't keep growing.
+        map.retain(|_, session| !session.is_closed());
+        map.insert(connection_id, handle);
+    }
+
+    pub fn get(&self, connection_id: &str) -> Option<SessionHandle> {
+        self.map
+            .lock()
+            .get(connection_id)
+            .filter(|session| !session.is_closed())
+            .cloned()
+    }
+
+    // Finds a session by connection ID, or by username if no connection ID is given.
+    pub fn find(
+        &self,
+        connection_id: Option<&str>,
+        username: Option<&str>,
+    ) -> Option<SessionHandle> {
+        match (connection_id, username) {
+            (Some(connection_id), _) => self.get(connection_id),
+            (None, Some(username)) => self
+                .map
+                .lock()
+                .values()
+                .find(|session| !session.is_closed() && session.info.lock().username == username)
+                .cloned(),
+            (None, None) => None,
+        }
+    }
+
+    // The states of all open connections, including the ones that don't have a session yet.
+    pub fn states(&self) -> Vec<State> {
+        let mut states: Vec<State> = self.list().iter().map(|session| session.state).collect();
+        states.extend(self.pending.lock().values());
+        states
+    }
+
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.map
+            .lock()
+            .values()
+            .filter(|session| !session.is_closed())
+            .map(|session| session.info())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_connections() {
+        let sessions = Sessions::new();
+        let pending = sessions.accept("a");
+        assert_eq!(sessions.states(), vec![State::Handshaking]);
+        pending.set_state(State::Status);
+        assert_eq!(sessions.states(), vec![State::Status]);
+        drop(pending);
+        assert!(sessions.states().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+// The tab list of a session, built from the Player Info and Player List Header/Footer packets.
+// It is how the uuid of a player (from SpawnPlayer for example) can be turned into a name.
+use crate::{
+    chat::TextComponent,
+    functions::clientbound::play::{PlayerInfoAction, PlayerProperty},
+    functions::Fid,
+    packet::Packet,
+    plugin::{first_reply, EventHandler},
+    types::Uuid,
+    Direction,
+};
+use serde::Serialize;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TabListEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<PlayerProperty>,
+    pub gamemode: i32,
+    // In milliseconds.
+    pub ping: i32,
+    pub display_name: Option<TextComponent>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TabList {
+    // In the order they were added.
+    players: Vec<TabListEntry>,
+    pub header: TextComponent,
+    pub footer: TextComponent,
+}
+
+impl TabList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn players(&self) -> &[TabListEntry] {
+        &self.players
+    }
+
+    pub fn get(&self, uuid: &Uuid) -> Option<&TabListEntry> {
+        self.players.iter().find(|player| &player.uuid == uuid)
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&TabListEntry> {
+        self.players
+            .iter()
+            .find(|player| player.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn name_of(&self, uuid: &Uuid) -> Option<&str> {
+        self.get(uuid).map(|player| player.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    fn get_mut(&mut self, uuid: &Uuid) -> Option<&mut TabListEntry> {
+        self.players.iter_mut().find(|player| &player.uuid == uuid)
+    }
+
+    // Updates for players that aren't in the list are ignored, like the client does.
+    pub fn apply(&mut self, action: &PlayerInfoAction) {
+        match action {
+            PlayerInfoAction::AddPlayer(players) => {
+                for added in players {
+                    let entry = TabListEntry {
+                        uuid: added.uuid.clone(),
+                        name: added.name.clone(),
+                        properties: added.properties.clone(),
+                        gamemode: added.gamemode,
+                        ping: added.ping,
+                        display_name: added.display_name.clone(),
+                    };
+                    match self.get_mut(&added.uuid) {
+                        Some(player) => *player = entry,
+                        None => self.players.push(entry),
+                    }
+                }
+            }
+            PlayerInfoAction::UpdateGamemode(players) => {
+                for update in players {
+                    if let Some(player) = self.get_mut(&update.uuid) {
+                        player.gamemode = update.gamemode;
+                    }
+                }
+            }
+            PlayerInfoAction::UpdateLatency(players) => {
+                for update in players {
+                    if let Some(player) = self.get_mut(&update.uuid) {
+                        player.ping = update.ping;
+                    }
+                }
+            }
+            PlayerInfoAction::UpdateDisplayName(players) => {
+                for update in players {
+                    if let Some(player) = self.get_mut(&update.uuid) {
+                        player.display_name = update.display_name.clone();
+                    }
+                }
+            }
+            PlayerInfoAction::RemovePlayer(uuids) => {
+                self.players.retain(|player| !uuids.contains(&player.uuid));
+            }
+        }
+    }
+}
+
+// Shows the changed tab list to the plugins.
+pub fn notify_plugins(
+    fid: Fid,
+    tab_list: &TabList,
+    plugins: &mut [Box<dyn EventHandler + Send>],
+) -> Vec<(Packet, Direction)> {
+    first_reply(fid, plugins, |plugin| plugin.on_tab_list_update(tab_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::clientbound::play::{
+        PlayerInfoAdd, PlayerInfoDisplayName, PlayerInfoGamemode, PlayerInfoLatency,
+    };
+
+    #[test]
+    fn test_tab_list() {
+        let mut tab_list = TabList::new();
+        tab_list.apply(&PlayerInfoAction::AddPlayer(vec![
+            PlayerInfoAdd {
+                uuid: Uuid::from(1),
+                name: "alice".into(),
+                gamemode: 0,
+                ping: 20,
+                ..PlayerInfoAdd::default()
+            },
+            PlayerInfoAdd {
+                uuid: Uuid::from(2),
+                name: "bob".into(),
+                ..PlayerInfoAdd::default()
+            },
+        ]));
+        tab_list.apply(&PlayerInfoAction::UpdateGamemode(vec![
+            PlayerInfoGamemode {
+                uuid: Uuid::from(1),
+                gamemode: 3,
+            },
+        ]));
+        tab_list.apply(&PlayerInfoAction::UpdateLatency(vec![PlayerInfoLatency {
+            uuid: Uuid::from(1),
+            ping: 150,
+        }]));
+        tab_list.apply(&PlayerInfoAction::UpdateDisplayName(vec![
+            PlayerInfoDisplayName {
+                uuid: Uuid::from(2),
+                display_name: Some(TextComponent::text("Bob")),
+            },
+            // Not in the list, so it is ignored.
+            PlayerInfoDisplayName {
+                uuid: Uuid::from(3),
+                display_name: None,
+            },
+        ]));
+
+        let alice = tab_list.get(&Uuid::from(1)).unwrap();
+        assert_eq!((alice.gamemode, alice.ping), (3, 150));
+        assert_eq!(tab_list.name_of(&Uuid::from(2)), Some("bob"));
+        assert_eq!(
+            tab_list.find_by_name("BOB").unwrap().display_name,
+            Some(TextComponent::text("Bob"))
+        );
+        assert_eq!(tab_list.len(), 2);
+
+        tab_list.apply(&PlayerInfoAction::RemovePlayer(vec![Uuid::from(1)]));
+        assert!(tab_list.get(&Uuid::from(1)).is_none());
+        assert_eq!(tab_list.len(), 1);
+    }
+}
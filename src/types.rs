@@ -1,10 +1,16 @@
-use crate::cipher::Cipher;
-use serde::{Serialize, Serializer};
+use crate::{
+    chat::TextComponent, chunk_cache::ChunkCache, cipher::Cipher, entities::Entities,
+    functions::clientbound::play::DimentionType, sessions::Sessions, tab_list::TabList,
+};
+use bytes::Bytes;
+use parking_lot::Mutex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt, sync::Arc};
 
-pub type DataQueue = deadqueue::unlimited::Queue<Vec<u8>>;
+pub type DataQueue = deadqueue::unlimited::Queue<Bytes>;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "lowercase"))]
 pub enum State {
     Handshaking,
     Status,
@@ -22,6 +28,35 @@ pub struct SharedState {
     pub server_ip: String,
     pub user_ip: String,
     pub connection_id: String,
+    pub username: String,
+    // Set by JoinGame and Respawn, None until the player is in game.
+    pub world: Option<World>,
+    // Where the server thinks the player is, None until the first teleport.
+    pub location: Option<Location>,
+    // The id of the last teleport the client hasn't confirmed yet.
+    pub pending_teleport: Option<i32>,
+    pub entities: Entities,
+    pub tab_list: TabList,
+    pub chunks: ChunkCache,
+    // All the sessions of the proxy, control messages on the auth link can be for any of them.
+    pub sessions: Sessions,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Location {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+// The world the player is in.
+#[derive(Clone, Debug)]
+pub struct World {
+    // Like minecraft:overworld
+    pub name: String,
+    pub dimension: DimentionType,
 }
 
 impl SharedState {
@@ -35,19 +70,16 @@ impl SharedState {
             server_ip: String::new(),
             user_ip: String::new(),
             connection_id: String::new(),
+            username: String::new(),
+            world: None,
+            location: None,
+            pending_teleport: None,
+            entities: Entities::new(),
+            tab_list: TabList::new(),
+            chunks: ChunkCache::new(),
+            sessions: Sessions::new(),
         }
     }
-
-    pub fn set(&mut self, new_state: SharedState) {
-        self.compress = new_state.compress;
-        self.state = new_state.state;
-        self.secret_key = new_state.secret_key;
-        self.access_token = new_state.access_token;
-        self.uuid = new_state.uuid;
-        self.server_ip = new_state.server_ip;
-        self.user_ip = new_state.user_ip;
-        self.connection_id = new_state.connection_id;
-    }
 }
 
 impl Default for SharedState {
@@ -56,16 +88,17 @@ impl Default for SharedState {
     }
 }
 
+// Both ciphers have their own lock, so the two directions never have to wait for each other.
 pub struct Ciphers {
-    pub ps_cipher: Cipher,
-    pub sp_cipher: Cipher,
+    pub ps_cipher: Mutex<Cipher>,
+    pub sp_cipher: Mutex<Cipher>,
 }
 
 impl Ciphers {
     pub fn new() -> Ciphers {
         Ciphers {
-            ps_cipher: Cipher::new(),
-            sp_cipher: Cipher::new(),
+            ps_cipher: Mutex::new(Cipher::new()),
+            sp_cipher: Mutex::new(Cipher::new()),
         }
     }
 }
@@ -76,7 +109,8 @@ impl Default for Ciphers {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "lowercase"))]
 pub enum Direction {
     Serverbound,
     Clientbound,
@@ -96,7 +130,7 @@ pub struct Queues {
     pub proxy_server: Arc<DataQueue>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Default)]
 pub struct Slot {
     pub present: bool,
     pub item_id: Option<i32>,
@@ -104,7 +138,110 @@ pub struct Slot {
     pub nbt: Option<nbt::Blob>,
 }
 
-#[derive(Debug, Clone)]
+// The metadata of an entity, only the indexes that are sent are in it.
+// On the wire every entry is the index, the type and the value, and the list ends with index 0xff.
+#[derive(Debug, Clone, PartialEq, Serialize, Default)]
+pub struct Metadata {
+    pub entries: Vec<MetadataEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetadataEntry {
+    pub index: u8,
+    pub value: MetadataValue,
+}
+
+// The variants are in the order of their type ids (0 to 18).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(i32),
+    Float(f32),
+    String(String),
+    Chat(TextComponent),
+    OptChat(Option<TextComponent>),
+    Slot(Slot),
+    Boolean(bool),
+    Rotation(f32, f32, f32),
+    Position((i32, i32, i32)),
+    OptPosition(Option<(i32, i32, i32)>),
+    // Down, up, north, south, west, east.
+    Direction(i32),
+    OptUuid(Option<Uuid>),
+    // 0 (air) means there is no block.
+    BlockId(i32),
+    Nbt(nbt::Blob),
+    Particle(Particle),
+    VillagerData(VillagerData),
+    OptVarInt(Option<i32>),
+    // Standing, fall flying, sleeping, swimming, spin attack, sneaking, dying.
+    Pose(i32),
+}
+
+impl MetadataValue {
+    pub fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::Float(_) => 2,
+            MetadataValue::String(_) => 3,
+            MetadataValue::Chat(_) => 4,
+            MetadataValue::OptChat(_) => 5,
+            MetadataValue::Slot(_) => 6,
+            MetadataValue::Boolean(_) => 7,
+            MetadataValue::Rotation(..) => 8,
+            MetadataValue::Position(_) => 9,
+            MetadataValue::OptPosition(_) => 10,
+            MetadataValue::Direction(_) => 11,
+            MetadataValue::OptUuid(_) => 12,
+            MetadataValue::BlockId(_) => 13,
+            MetadataValue::Nbt(_) => 14,
+            MetadataValue::Particle(_) => 15,
+            MetadataValue::VillagerData(_) => 16,
+            MetadataValue::OptVarInt(_) => 17,
+            MetadataValue::Pose(_) => 18,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Particle {
+    pub id: i32,
+    pub data: ParticleData,
+}
+
+// Only a few particles have extra data, which one depends on the particle id.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ParticleData {
+    None,
+    // minecraft:block and minecraft:falling_dust, the block state.
+    Block(i32),
+    // minecraft:dust, the color is from 0 to 1.
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+    // minecraft:item
+    Item(Slot),
+}
+
+impl Particle {
+    pub const BLOCK: i32 = 3;
+    pub const DUST: i32 = 14;
+    pub const FALLING_DUST: i32 = 23;
+    pub const ITEM: i32 = 34;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VillagerData {
+    pub villager_type: i32,
+    pub profession: i32,
+    pub level: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Uuid {
     value: u128,
 }
@@ -114,7 +251,28 @@ impl Serialize for Uuid {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&format!("{:x}", self.value))
+        // The packet format sends the number itself.
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("{:x}", self.value))
+        } else {
+            serializer.serialize_u128(self.value)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let string = String::deserialize(deserializer)?;
+            u128::from_str_radix(&string.replace('-', ""), 16)
+                .map(Uuid::from)
+                .map_err(de::Error::custom)
+        } else {
+            u128::deserialize(deserializer).map(Uuid::from)
+        }
     }
 }
 
@@ -128,4 +286,8 @@ impl Uuid {
     pub fn from(v: u128) -> Self {
         Self { value: v }
     }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
 }
@@ -1,6 +1,23 @@
-use crate::{functions, packet::Packet, raw_packet::RawPacket};
+use std::time::SystemTime;
+
+use crate::{
+    chat::TextComponent,
+    functions::{
+        clientbound::{
+            login::Disconnect,
+            play::{ChatMessageClientbound, ChatMessagePosition, DisconnectPlay},
+        },
+        Fid,
+    },
+    packet::Packet,
+    parsable::Parsable,
+    types::Uuid,
+    Direction, State,
+};
 use rand::{distributions::Alphanumeric, Rng};
 
+const FAILED_PACKETS_DIRECTORY: &str = "failed_packets";
+
 // This converts a long string into one that's shortened.
 // alongstringlikethis would become alongs...kethis
 pub fn make_string_fixed_length(string: String, length: usize) -> String {
@@ -10,45 +27,55 @@ pub fn make_string_fixed_length(string: String, length: usize) -> String {
         let part_size = length - 3 / 2;
         format!(
             "{}...{}",
-            string[0..part_size].to_string(),
-            string[string.len() - part_size..].to_string()
+            &string[0..part_size],
+            &string[string.len() - part_size..]
         )
     }
 }
 
 pub fn generate_message_packet(text: &str) -> Result<Packet, ()> {
-    let mut raw_packet = RawPacket::new();
-    raw_packet.encode_string(format!(
-        "{{\"extra\":[{{\"color\":\"red\",\"text\":\"proxy\"}},{{\"text\":\"> {}\"}}],\"text\":\"\"}}",
-        text
-    ));
-    raw_packet.encode_byte(1);
-    raw_packet.encode_uuid(0);
-    Ok(Packet::from(
-        raw_packet,
-        functions::fid_to_pid(functions::Fid::ChatMessageClientbound),
-    ))
+    ChatMessageClientbound {
+        data: TextComponent {
+            extra: vec![
+                TextComponent::colored("proxy", "red"),
+                TextComponent::text(format!("> {}", text)),
+            ],
+            ..TextComponent::text("")
+        },
+        position: ChatMessagePosition::SystemMessage,
+        sender: Uuid::from(0),
+    }
+    .encode_packet()
+}
+
+// Makes a packet that kicks the player with a reason, this can only be done while logging in or in game.
+pub fn generate_disconnect_packet(state: State, reason: &str) -> Option<Packet> {
+    let reason = TextComponent::text(reason);
+    match state {
+        State::Login => Disconnect { reason }.encode_packet().ok(),
+        State::Play => DisconnectPlay { reason }.encode_packet().ok(),
+        State::Handshaking | State::Status => None,
+    }
 }
 
-pub fn rainbowfy(message: String) -> String {
-    let mut return_message = String::new();
-    let rainbow_characters = "c6eab5";
-    for (i, cha) in message.chars().enumerate() {
-        match cha {
-            ' ' => return_message.push(cha),
-            _ => {
-                return_message.push('&');
-                return_message.push(
-                    rainbow_characters
-                        .chars()
-                        .nth(i % rainbow_characters.len())
-                        .unwrap(),
-                );
-                return_message.push(cha);
-            }
+// Every character gets the next color of the rainbow, spaces keep the color before them.
+pub fn rainbowfy(message: &str) -> TextComponent {
+    let rainbow_colors = ["red", "gold", "yellow", "green", "aqua", "dark_purple"];
+    let mut parts: Vec<TextComponent> = vec![];
+    for (i, character) in message.chars().enumerate() {
+        match (character, parts.last_mut()) {
+            (' ', Some(last)) => last.text.get_or_insert_with(String::new).push(' '),
+            (' ', None) => parts.push(TextComponent::text(" ")),
+            _ => parts.push(TextComponent::colored(
+                character,
+                rainbow_colors[i % rainbow_colors.len()],
+            )),
         }
     }
-    return_message
+    TextComponent {
+        extra: parts,
+        ..TextComponent::text("")
+    }
 }
 
 pub fn generate_connection_id() -> String {
@@ -59,3 +86,36 @@ pub fn generate_connection_id() -> String {
         .collect();
     rand_string
 }
+
+// Shows the start of some data as hex, used to show packets that could not be parsed.
+pub fn hex_dump(data: &[u8], max_length: usize) -> String {
+    if data.len() <= max_length {
+        hex::encode(data)
+    } else {
+        format!(
+            "{}... ({} more bytes)",
+            hex::encode(&data[..max_length]),
+            data.len() - max_length
+        )
+    }
+}
+
+// Saves the data of a packet that could not be parsed, so it can be used for a regression test.
+// The file has the uncompressed packet (the packet ID and data) without the length in front.
+pub fn save_failed_packet(
+    direction: Direction,
+    func_id: &Fid,
+    data: &[u8],
+) -> std::io::Result<String> {
+    std::fs::create_dir_all(FAILED_PACKETS_DIRECTORY)?;
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let path = format!(
+        "{}/{}-{}-{}.bin",
+        FAILED_PACKETS_DIRECTORY, direction, func_id, timestamp
+    );
+    std::fs::write(&path, data)?;
+    Ok(path)
+}
@@ -0,0 +1,1061 @@
+// A serde data format for the Minecraft protocol, so any struct that derives Serialize and Deserialize
+// can be turned into packet bytes and back with to_bytes and from_bytes.
+//
+// How the serde types are sent:
+//   bool, integers, floats     like the protocol types with the same size (big endian), u128 is a UUID
+//   String, char               a varint length and the UTF-8 bytes
+//   bytes, Vec, maps           a varint length and the items
+//   Option                     a bool for if it's there and then the value
+//   tuples, structs            the fields after each other, without a length
+//   enums                      the variant index as a varint and then its fields
+// VarInt, VarLong and Position (below) are for the protocol types serde doesn't have.
+//
+// The format isn't self describing, so deserialize_any (and with it #[serde(flatten)] and untagged enums) can't work.
+// For packets this can't describe the RawPacket functions can still be used, together with to_packet and from_packet.
+use serde::{
+    de::{self, DeserializeOwned, IntoDeserializer},
+    ser, Deserialize, Serialize,
+};
+use std::{convert::TryFrom, fmt};
+
+use crate::{
+    parse_error::{ParseError, ParseErrorKind},
+    raw_packet::RawPacket,
+};
+
+const VARINT: &str = "$mc::VarInt";
+const VARLONG: &str = "$mc::VarLong";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    // The data couldn't be decoded, this has where and why.
+    Parse(ParseError),
+    // A message from serde or from the type that is (de)serialized.
+    Message(String),
+    // Something the format can't do.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "{}", error),
+            Error::Message(message) => write!(f, "{}", message),
+            Error::Unsupported(what) => write!(f, "{} is not supported", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut packet = RawPacket::new();
+    to_packet(value, &mut packet)?;
+    Ok(packet.get_vec())
+}
+
+// All of the bytes have to be used, leftover data is an error.
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    let mut packet = RawPacket::from(bytes.to_vec());
+    let value = from_packet(&mut packet)?;
+    if !packet.is_empty() {
+        return Err(Error::Message(format!(
+            "{} bytes left after the value",
+            packet.len()
+        )));
+    }
+    Ok(value)
+}
+
+// Adds the value to the end of the packet.
+pub fn to_packet<T: Serialize + ?Sized>(value: &T, packet: &mut RawPacket) -> Result<()> {
+    value.serialize(&mut Serializer {
+        packet,
+        varint: false,
+    })
+}
+
+// Reads a value from the front of the packet, the rest is left in it.
+pub fn from_packet<T: DeserializeOwned>(packet: &mut RawPacket) -> Result<T> {
+    T::deserialize(&mut Deserializer { packet })
+}
+
+// An i32 that is sent as a varint. In other formats it's just the number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VarInt(pub i32);
+
+// An i64 that is sent as a varlong.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VarLong(pub i64);
+
+// A block position (x, y, z) that is sent packed in a long. Human readable formats get the three numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Position(pub i32, pub i32, pub i32);
+
+impl Position {
+    fn pack(&self) -> i64 {
+        (((self.0 as u64 & 0x3FFFFFF) << 38)
+            | ((self.2 as u64 & 0x3FFFFFF) << 12)
+            | (self.1 as u64 & 0xFFF)) as i64
+    }
+
+    fn unpack(value: i64) -> Self {
+        Self(
+            (value >> 38) as i32,
+            (value << 52 >> 52) as i32,
+            (value << 26 >> 38) as i32,
+        )
+    }
+}
+
+impl Serialize for VarInt {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(VARINT, &self.0)
+    }
+}
+
+impl Serialize for VarLong {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(VARLONG, &self.0)
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            (self.0, self.1, self.2).serialize(serializer)
+        } else {
+            serializer.serialize_i64(self.pack())
+        }
+    }
+}
+
+// VarInt and VarLong are newtypes with a special name, this format sees the name and reads a varint.
+// Other formats call visit_newtype_struct, or just give the number.
+macro_rules! var_number_deserialize {
+    ($type:ident, $inner:ty, $name:expr) => {
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: de::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> de::Visitor<'de> for Visitor {
+                    type Value = $type;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        write!(f, "a number")
+                    }
+
+                    // The smaller numbers end up here as well.
+                    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<$type, E> {
+                        <$inner>::try_from(value)
+                            .map($type)
+                            .map_err(|_| E::custom("number out of range"))
+                    }
+
+                    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<$type, E> {
+                        <$inner>::try_from(value)
+                            .map($type)
+                            .map_err(|_| E::custom("number out of range"))
+                    }
+
+                    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+                        self,
+                        deserializer: D,
+                    ) -> std::result::Result<$type, D::Error> {
+                        <$inner>::deserialize(deserializer).map($type)
+                    }
+                }
+
+                deserializer.deserialize_newtype_struct($name, Visitor)
+            }
+        }
+    };
+}
+
+var_number_deserialize!(VarInt, i32, VARINT);
+var_number_deserialize!(VarLong, i64, VARLONG);
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let (x, y, z) = <(i32, i32, i32)>::deserialize(deserializer)?;
+            Ok(Position(x, y, z))
+        } else {
+            i64::deserialize(deserializer).map(Position::unpack)
+        }
+    }
+}
+
+pub struct Serializer<'a> {
+    packet: &'a mut RawPacket,
+    // Set by a VarInt or VarLong for the number inside it.
+    varint: bool,
+}
+
+impl Serializer<'_> {
+    fn length(&mut self, len: Option<usize>) -> Result<()> {
+        match len {
+            Some(len) => {
+                self.packet.encode_varint(len as i32);
+                Ok(())
+            }
+            None => Err(Error::Unsupported("a sequence without a known length")),
+        }
+    }
+}
+
+impl<'a, 'b> ser::Serializer for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.packet.encode_bool(v);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.packet.encode_byte(v);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.packet.encode_short(v);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        if std::mem::take(&mut self.varint) {
+            self.packet.encode_varint(v);
+        } else {
+            self.packet.encode_int(v);
+        }
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        if std::mem::take(&mut self.varint) {
+            self.packet.encode_varlong(v);
+        } else {
+            self.packet.encode_long(v);
+        }
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.packet.push_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.packet.encode_ubyte(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.packet.encode_ushort(v);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.packet.push_slice(&v.to_be_bytes());
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.packet.encode_ulong(v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.packet.encode_uuid(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.packet.encode_float(v);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.packet.encode_double(v);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.packet.encode_varint(v.len() as i32);
+        self.packet.push_slice(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.packet.encode_varint(v.len() as i32);
+        self.packet.push_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.packet.encode_bool(false);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        self.packet.encode_bool(true);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        self.packet.encode_varint(variant_index as i32);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.varint = name == VARINT || name == VARLONG;
+        value.serialize(&mut *self)?;
+        // In case the value wasn't a number.
+        self.varint = false;
+        Ok(())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.packet.encode_varint(variant_index as i32);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self> {
+        self.length(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.packet.encode_varint(variant_index as i32);
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self> {
+        self.length(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.packet.encode_varint(variant_index as i32);
+        Ok(self)
+    }
+}
+
+// All the compound types just write their items after each other.
+macro_rules! serialize_compound {
+    ($trait:ident, $method:ident $(, $key:ident)?) => {
+        impl<'a, 'b> ser::$trait for &'a mut Serializer<'b> {
+            type Ok = ();
+            type Error = Error;
+
+            fn $method<T: Serialize + ?Sized>(
+                &mut self,
+                $($key: &'static str,)?
+                value: &T,
+            ) -> Result<()> {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<()> {
+                Ok(())
+            }
+        }
+    };
+}
+
+serialize_compound!(SerializeSeq, serialize_element);
+serialize_compound!(SerializeTuple, serialize_element);
+serialize_compound!(SerializeTupleStruct, serialize_field);
+serialize_compound!(SerializeTupleVariant, serialize_field);
+serialize_compound!(SerializeStruct, serialize_field, _key);
+serialize_compound!(SerializeStructVariant, serialize_field, _key);
+
+impl<'a, 'b> ser::SerializeMap for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct Deserializer<'a> {
+    packet: &'a mut RawPacket,
+}
+
+impl Deserializer<'_> {
+    fn length(&mut self) -> Result<usize> {
+        let length = self.packet.decode_varint()?;
+        if length < 0 {
+            return Err(self
+                .packet
+                .error(ParseErrorKind::InvalidLength(length as i64))
+                .into());
+        }
+        Ok(length as usize)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(&self.packet.read(N)?);
+        Ok(array)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let length = self.length()?;
+        Ok(self.packet.read(length)?.to_vec())
+    }
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for &'a mut Deserializer<'b> {
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::Unsupported("deserializing without knowing the type"))
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bool(self.packet.decode_bool()?)
+    }
+
+    fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i8(self.packet.decode_byte()?)
+    }
+
+    fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i16(self.packet.decode_short()?)
+    }
+
+    fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i32(self.packet.decode_int()?)
+    }
+
+    fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i64(self.packet.decode_long()?)
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_i128(i128::from_be_bytes(self.array()?))
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u8(self.packet.decode_ubyte()?)
+    }
+
+    fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u16(self.packet.decode_ushort()?)
+    }
+
+    fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u32(u32::from_be_bytes(self.array()?))
+    }
+
+    fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u64(self.packet.decode_ulong()?)
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u128(u128::from_be_bytes(self.array()?))
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f32(self.packet.decode_float()?)
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(self.packet.decode_double()?)
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let string = self.packet.decode_string()?;
+        let mut chars = string.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(Error::Message(format!(
+                "expected one character, got {:?}",
+                string
+            ))),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.packet.decode_string()?)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_byte_buf(self.bytes()?)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.packet.decode_bool()? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        match name {
+            VARINT => visitor.visit_i32(self.packet.decode_varint()?),
+            VARLONG => visitor.visit_i64(self.packet.decode_varlong()?),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let length = self.length()?;
+        visitor.visit_seq(Items {
+            deserializer: self,
+            left: length,
+        })
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(Items {
+            deserializer: self,
+            left: len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let length = self.length()?;
+        visitor.visit_map(Items {
+            deserializer: self,
+            left: length,
+        })
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::Unsupported("deserializing an identifier"))
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::Unsupported("skipping a value"))
+    }
+}
+
+// The items of a sequence, tuple, struct or map, with how many are still left.
+struct Items<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+    left: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Items<'_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    // The length comes from the packet, so it isn't trusted for the capacity.
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.left.min(4096))
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Items<'_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(&mut *self.deserializer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.left.min(4096))
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for &mut Deserializer<'_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let index = self.packet.decode_varint()?;
+        if index < 0 {
+            return Err(self
+                .packet
+                .error(ParseErrorKind::InvalidValue(index as i64))
+                .into());
+        }
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(index as u32))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Deserializer<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Uuid;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Action {
+        Nothing,
+        Move(VarInt),
+        Look { yaw: f32, pitch: f32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Everything {
+        id: VarInt,
+        time: VarLong,
+        health: f32,
+        on_ground: bool,
+        name: String,
+        location: Position,
+        uuid: Uuid,
+        tooltip: Option<String>,
+        missing: Option<i16>,
+        passengers: Vec<VarInt>,
+        actions: Vec<Action>,
+        scores: BTreeMap<String, i32>,
+        rotation: (u8, i8),
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Everything {
+            id: VarInt(300),
+            time: VarLong(-1),
+            health: 20.0,
+            on_ground: true,
+            name: "bob".to_string(),
+            location: Position(-10, -64, 2000),
+            uuid: Uuid::from(0x1234),
+            tooltip: Some("hi".to_string()),
+            missing: None,
+            passengers: vec![VarInt(1), VarInt(-1)],
+            actions: vec![
+                Action::Nothing,
+                Action::Move(VarInt(2)),
+                Action::Look {
+                    yaw: 90.0,
+                    pitch: 0.5,
+                },
+            ],
+            scores: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+            rotation: (255, -1),
+        };
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(from_bytes::<Everything>(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn test_wire_bytes() {
+        // The same bytes the RawPacket functions make.
+        let mut packet = RawPacket::new();
+        packet.encode_varint(300);
+        packet.encode_string("bob".to_string());
+        packet.encode_position((-10, -64, 2000));
+        packet.encode_bool(true);
+        packet.encode_short(5);
+        packet.encode_varint(2);
+        packet.encode_varint(7);
+        packet.encode_varint(-1);
+        let expected = packet.get_vec();
+
+        let value = (
+            VarInt(300),
+            "bob",
+            Position(-10, -64, 2000),
+            Some(5i16),
+            vec![VarInt(7), VarInt(-1)],
+        );
+        assert_eq!(to_bytes(&value).unwrap(), expected);
+
+        // Other formats just see the numbers.
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"[300,"bob",[-10,-64,2000],5,[7,-1]]"#
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        // Not enough data for the second field.
+        let error = from_bytes::<(VarInt, i32)>(&[0x01, 0x00]).unwrap_err();
+        assert!(matches!(error, Error::Parse(ParseError { offset: 1, .. })));
+        // Leftover data.
+        assert!(from_bytes::<VarInt>(&[0x01, 0x00]).is_err());
+        // A variant that doesn't exist.
+        assert!(from_bytes::<Action>(&[0x05]).is_err());
+        // A negative length.
+        assert!(from_bytes::<Vec<u8>>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).is_err());
+    }
+
+    fn kind(error: Error) -> ParseErrorKind {
+        match error {
+            Error::Parse(error) => error.kind,
+            other => panic!("not a parse error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_var_number_boundaries() {
+        let varints: &[(i32, &[u8])] = &[
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (16383, &[0xFF, 0x7F]),
+            (16384, &[0x80, 0x80, 0x01]),
+            (i32::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        ];
+        for (value, bytes) in varints {
+            assert_eq!(to_bytes(&VarInt(*value)).unwrap(), *bytes, "{}", value);
+            assert_eq!(from_bytes::<VarInt>(bytes).unwrap(), VarInt(*value));
+        }
+
+        let varlongs: &[(i64, &[u8])] = &[
+            (0, &[0x00]),
+            (128, &[0x80, 0x01]),
+            (i32::MAX as i64 + 1, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+            (
+                i64::MAX,
+                &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F],
+            ),
+            (
+                -1,
+                &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01],
+            ),
+            (
+                i64::MIN,
+                &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+            ),
+        ];
+        for (value, bytes) in varlongs {
+            assert_eq!(to_bytes(&VarLong(*value)).unwrap(), *bytes, "{}", value);
+            assert_eq!(from_bytes::<VarLong>(bytes).unwrap(), VarLong(*value));
+        }
+
+        // One byte more than the longest number, or cut off before the last byte.
+        let error = from_bytes::<VarInt>(&[0x80; 6]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::VarIntTooLong);
+        let error = from_bytes::<VarLong>(&[0x80; 11]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::VarIntTooLong);
+        let error = from_bytes::<VarInt>(&[0x80, 0x80]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+    }
+
+    #[test]
+    fn test_bad_lengths() {
+        // Longer than the data that is there.
+        let error = from_bytes::<String>(&[0x05, b'a', b'b']).unwrap_err();
+        assert_eq!(
+            kind(error),
+            ParseErrorKind::NotEnoughData {
+                needed: 5,
+                available: 2
+            }
+        );
+        let error = from_bytes::<Vec<VarInt>>(&[0x03, 0x01, 0x02]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+        let error = from_bytes::<BTreeMap<String, i32>>(&[0x01, 0x01, b'a']).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+
+        // Negative.
+        let error = from_bytes::<String>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidLength(-1));
+        let error =
+            from_bytes::<BTreeMap<String, i32>>(&[0x80, 0x80, 0x80, 0x80, 0x08]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidLength(i32::MIN as i64));
+
+        // A huge length doesn't allocate anything before the data runs out.
+        let error = from_bytes::<Vec<u64>>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        // 0xC3 starts a two byte character, 0x28 can't continue it.
+        let error = from_bytes::<String>(&[0x02, 0xC3, 0x28]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidUtf8);
+        let error = from_bytes::<(VarInt, String)>(&[0x01, 0x01, 0xFF]).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Parse(ParseError {
+                kind: ParseErrorKind::InvalidUtf8,
+                offset: 2,
+                ..
+            })
+        ));
+        let error = from_bytes::<char>(&[0x01, 0x80]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidUtf8);
+
+        // Multi byte characters count their bytes, not their chars.
+        assert_eq!(to_bytes("é").unwrap(), [0x02, 0xC3, 0xA9]);
+        assert_eq!(from_bytes::<char>(&[0x02, 0xC3, 0xA9]).unwrap(), 'é');
+        assert!(matches!(
+            from_bytes::<char>(&[0x02, b'a', b'b']).unwrap_err(),
+            Error::Message(_)
+        ));
+    }
+
+    #[test]
+    fn test_enums() {
+        assert_eq!(to_bytes(&Action::Nothing).unwrap(), [0x00]);
+        assert_eq!(
+            to_bytes(&Action::Move(VarInt(128))).unwrap(),
+            [0x01, 0x80, 0x01]
+        );
+        let look = Action::Look {
+            yaw: 1.0,
+            pitch: -2.0,
+        };
+        let bytes = to_bytes(&look).unwrap();
+        assert_eq!(
+            bytes,
+            [0x02, 0x3F, 0x80, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(from_bytes::<Action>(&bytes).unwrap(), look);
+
+        // The fields of the variant are missing.
+        let error = from_bytes::<Action>(&[0x02, 0x3F, 0x80]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+        // A negative variant index.
+        let error = from_bytes::<Action>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidValue(-1));
+    }
+
+    #[test]
+    fn test_options() {
+        let values = [None, Some(None), Some(Some(VarInt(5)))];
+        let encoded: [&[u8]; 3] = [&[0x00], &[0x01, 0x00], &[0x01, 0x01, 0x05]];
+        for (value, bytes) in values.iter().zip(encoded) {
+            assert_eq!(to_bytes(value).unwrap(), bytes);
+            assert_eq!(from_bytes::<Option<Option<VarInt>>>(bytes).unwrap(), *value);
+        }
+
+        // Only 0 and 1 are bools.
+        let error = from_bytes::<Option<i16>>(&[0x02, 0x00, 0x01]).unwrap_err();
+        assert_eq!(kind(error), ParseErrorKind::InvalidBool(2));
+        // There, but without the value.
+        let error = from_bytes::<Option<i16>>(&[0x01, 0x00]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+    }
+
+    #[test]
+    fn test_nested_sequences() {
+        let value = vec![
+            vec![],
+            vec![Some("a".to_string()), None],
+            vec![Some(String::new())],
+        ];
+        let bytes = to_bytes(&value).unwrap();
+        assert_eq!(
+            bytes,
+            [0x03, 0x00, 0x02, 0x01, 0x01, b'a', 0x00, 0x01, 0x01, 0x00]
+        );
+        assert_eq!(
+            from_bytes::<Vec<Vec<Option<String>>>>(&bytes).unwrap(),
+            value
+        );
+
+        let nested = vec![(VarInt(1), vec![vec![VarLong(-1)]]), (VarInt(2), vec![])];
+        let bytes = to_bytes(&nested).unwrap();
+        assert_eq!(
+            from_bytes::<Vec<(VarInt, Vec<Vec<VarLong>>)>>(&bytes).unwrap(),
+            nested
+        );
+
+        // The inner length is longer than what's left.
+        let error = from_bytes::<Vec<Vec<u8>>>(&[0x02, 0x01, 0x07, 0x02, 0x08]).unwrap_err();
+        assert!(matches!(kind(error), ParseErrorKind::NotEnoughData { .. }));
+        // The outer length is too short, so the rest is left over.
+        assert!(matches!(
+            from_bytes::<Vec<Vec<u8>>>(&[0x01, 0x01, 0x07, 0x01, 0x08]).unwrap_err(),
+            Error::Message(_)
+        ));
+    }
+}